@@ -0,0 +1,44 @@
+//! Completes a prefix from the dictionary that `BkTree` indexes, and falls back to fuzzy
+//! suggestions when nothing starts with it.
+//!
+//! ```sh
+//! cargo run --example autocomplete -- bo
+//! cargo run --example autocomplete -- cok
+//! ```
+use dsa::{
+    distances::levenshtein::levenshtein_distance,
+    trees::{
+        bk_tree::{BkTree, DICTIONARY},
+        radix_tree::RadixTree,
+    },
+};
+
+fn main() {
+    let prefix = std::env::args().nth(1).unwrap_or_default();
+
+    let completions: RadixTree<u8, &str> = DICTIONARY.iter().map(|word| (word, *word)).collect();
+    let mut fuzzy = BkTree::new(levenshtein_distance);
+    for word in DICTIONARY {
+        fuzzy.insert(word.to_string());
+    }
+
+    let words: Vec<&str> = completions
+        .starts_with(prefix.as_bytes())
+        .map(|(_, word)| *word)
+        .collect();
+    if !words.is_empty() {
+        println!("{}", words.join("\n"));
+        return;
+    }
+
+    let mut suggestions = fuzzy.find(&prefix, 1);
+    suggestions.sort_by_key(|(word, dist)| (*dist, word.as_str()));
+    if suggestions.is_empty() {
+        println!("no completions for {prefix:?}");
+    } else {
+        println!("no completions for {prefix:?}, did you mean:");
+        for (word, _) in suggestions {
+            println!("{word}");
+        }
+    }
+}
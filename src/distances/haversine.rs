@@ -0,0 +1,40 @@
+//@ The mean radius of the Earth, in kilometers.
+pub const EARTH_RADIUS_KM: f64 = 6371.0;
+
+//@ The [great-circle distance](https://en.wikipedia.org/wiki/Haversine_formula) in kilometers
+//@ between two `[latitude, longitude]` points given in degrees. Treating coordinates as points in
+//@ the plane gets distances wrong away from the equator, since a degree of longitude shrinks
+//@ towards the poles, and it also puts places on either side of the antimeridian far apart.
+pub fn haversine_distance(a: &[f64; 2], b: &[f64; 2]) -> f64 {
+    let [lat_a, lon_a] = a.map(f64::to_radians);
+    let [lat_b, lon_b] = b.map(f64::to_radians);
+    let h = ((lat_b - lat_a) / 2.0).sin().powi(2)
+        + lat_a.cos() * lat_b.cos() * ((lon_b - lon_a) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().min(1.0).asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn ex1() {
+        let paris = [48.8566, 2.3522];
+        let london = [51.5074, -0.1278];
+        assert!((haversine_distance(&paris, &london) - 343.5).abs() < 1.0);
+        //@ Fiji and Samoa are about 1150km apart, across the antimeridian.
+        let suva = [-18.1248, 178.4501];
+        let apia = [-13.8507, -171.7514];
+        assert!((haversine_distance(&suva, &apia) - 1150.0).abs() < 20.0);
+    }
+
+    #[quickcheck]
+    fn prop_haversine_distance_symmetric(a: (i16, i16), b: (i16, i16)) -> bool {
+        let point = |(lat, lon): (i16, i16)| [f64::from(lat % 90), f64::from(lon % 180)];
+        let (a, b) = (point(a), point(b));
+        let d = haversine_distance(&a, &b);
+        d == haversine_distance(&b, &a)
+            && (0.0..=EARTH_RADIUS_KM * std::f64::consts::PI).contains(&d)
+    }
+}
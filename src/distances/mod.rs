@@ -1,2 +1,3 @@
 pub mod hamming;
+pub mod haversine;
 pub mod levenshtein;
@@ -1,47 +1,82 @@
-use std::{
-    cmp::Reverse,
-    collections::{BinaryHeap, HashMap, HashSet},
-};
+use std::{cmp::Reverse, collections::BinaryHeap};
 
+use super::graph::{Adjacency, Graph};
+
+//@ Finds the cheapest way to connect cities `1..=n` with roads, where every connection is a
+//@ possible road and its cost. Connections to cities outside of `1..=n` are ignored. Returns
+//@ `None` if some city can't be connected.
 pub fn minimum_cost(n: u32, connections: Vec<(u32, u32, u32)>) -> Option<u32> {
-    let mut graph: HashMap<u32, Vec<(u32, u32)>> = HashMap::new();
+    let cities = 1..=n;
+    let (mut graph, mut ids) = Graph::from_edges(
+        false,
+        connections
+            .into_iter()
+            .filter(|(u, v, _)| cities.contains(u) && cities.contains(v)),
+    );
+    //@ A single city is connected without any roads.
+    if n > 0 {
+        graph.id(&mut ids, 1);
+    }
+    if graph.node_count() != n as usize {
+        return None;
+    }
+    prim(&graph).map(|total| u32::try_from(total).unwrap_or(u32::MAX))
+}
 
-    for (u, v, cost) in connections {
-        graph.entry(u).or_default().push((cost, v));
-        graph.entry(v).or_default().push((cost, u));
+//@ [Prim's algorithm](https://en.wikipedia.org/wiki/Prim%27s_algorithm) finds the total weight
+//@ of a minimum spanning tree of an undirected graph, or `None` if the graph isn't connected.
+//@
+//@ It grows the tree from the first node, always adding the cheapest edge that leads out of it,
+//@ which it finds with a min-heap of the edges out of every node in the tree. An edge can be
+//@ pushed again after its node was already reached more cheaply, so those are skipped when
+//@ they're popped. Totals are `u64`s, so they can't overflow.
+//@
+//@ [Kruskal's algorithm](./kruskal.html) also returns the edges of the tree, and handles
+//@ disconnected graphs by finding a tree for every component.
+pub fn prim<G: Adjacency>(graph: &G) -> Option<u64>
+where
+    G::Edge: Copy + Into<u64>,
+{
+    assert!(
+        !graph.is_directed(),
+        "spanning trees need an undirected graph"
+    );
+    let n = graph.node_count();
+    if n == 0 {
+        return Some(0);
     }
 
     let mut min_heap = BinaryHeap::new();
-    min_heap.push(Reverse((0, 1)));
-    let mut visited = HashSet::new();
-    let mut total_cost: u32 = 0;
-
-    while !min_heap.is_empty() && visited.len() < n as usize {
-        if let Some(Reverse((cost, u))) = min_heap.pop() {
-            if visited.contains(&u) {
-                continue;
-            }
-            visited.insert(u);
-            total_cost = total_cost.saturating_add(cost);
+    min_heap.push(Reverse((0, 0)));
+    let mut visited = vec![false; n];
+    let mut reached = 0;
+    let mut total_cost: u64 = 0;
 
-            for (edge_cost, v) in graph.entry(u).or_default() {
-                if !visited.contains(v) {
-                    min_heap.push(Reverse((*edge_cost, *v)));
-                }
+    while let Some(Reverse((cost, u))) = min_heap.pop() {
+        if visited[u] {
+            continue;
+        }
+        visited[u] = true;
+        reached += 1;
+        total_cost += cost;
+        if reached == n {
+            break;
+        }
+
+        for (v, &edge_cost) in graph.edges_from(u) {
+            if !visited[v] {
+                min_heap.push(Reverse((edge_cost.into(), v)));
             }
         }
     }
 
-    if visited.len() == n as usize {
-        Some(total_cost)
-    } else {
-        None
-    }
+    (reached == n).then_some(total_cost)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::minimum_cost;
+    use super::*;
+    use crate::graphs::graph::Csr;
     use quickcheck_macros::quickcheck;
 
     #[test]
@@ -65,6 +100,26 @@ mod tests {
         assert_eq!(res, None);
     }
 
+    //@ Cities that aren't connected to anything, and a single city, which doesn't need roads.
+    #[test]
+    fn ex4() {
+        assert_eq!(minimum_cost(3, vec![(1, 2, 3), (2, 7, 1)]), None);
+        assert_eq!(minimum_cost(1, vec![]), Some(0));
+    }
+
+    #[test]
+    fn csr() {
+        let (graph, _) = Graph::from_edges(false, [("a", "b", 4u8), ("b", "c", 1), ("a", "c", 2)]);
+        assert_eq!(prim(&Csr::from(graph)), Some(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "spanning trees need an undirected graph")]
+    fn directed() {
+        let (graph, _) = Graph::from_edges(true, [("a", "b", 1u32)]);
+        prim(&graph);
+    }
+
     #[quickcheck]
     fn verify(n: u32, input: Vec<(u32, u32, u32)>) -> bool {
         minimum_cost(n, input);
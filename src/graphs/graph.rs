@@ -0,0 +1,376 @@
+use std::{collections::HashMap, hash::Hash, iter, ops::Range};
+
+//@ A general purpose graph, which the algorithms in this module share, instead of each of them
+//@ taking its own kind of adjacency list.
+//@
+//@ Nodes are numbered from zero in the order they're added, and every node has a weight of type
+//@ `N`, like a name, and every edge has a weight of type `E`, like a cost. Graphs without weights
+//@ use `()`, which takes no space. Since nodes are indices, the algorithms can keep their state
+//@ in `Vec`s instead of `HashMap`s.
+pub type NodeId = usize;
+
+//@ What the algorithms need from a graph: its nodes, and the edges out of each of them. Both
+//@ `Graph` and its compact `Csr` form implement it.
+pub trait Adjacency {
+    type Edge;
+
+    fn node_count(&self) -> usize;
+
+    //@ Whether the edges are one way. An undirected graph has every edge in the lists of both of
+    //@ its ends.
+    fn is_directed(&self) -> bool;
+
+    fn edges_from(&self, node: NodeId) -> impl Iterator<Item = (NodeId, &Self::Edge)>;
+
+    fn neighbors(&self, node: NodeId) -> impl Iterator<Item = NodeId> {
+        self.edges_from(node).map(|(to, _)| to)
+    }
+
+    fn node_ids(&self) -> Range<NodeId> {
+        0..self.node_count()
+    }
+}
+
+//@ An adjacency list, which can grow one node or edge at a time. An undirected edge is stored
+//@ in the lists of both of its ends, except for a self loop, which is only stored once.
+pub struct Graph<N, E = ()> {
+    nodes: Vec<N>,
+    edges: Vec<Vec<(NodeId, E)>>,
+    edge_count: usize,
+    directed: bool,
+}
+
+impl<N, E> Default for Graph<N, E> {
+    fn default() -> Self {
+        Graph::directed()
+    }
+}
+
+impl<N, E> Graph<N, E> {
+    pub fn directed() -> Self {
+        Graph {
+            nodes: vec![],
+            edges: vec![],
+            edge_count: 0,
+            directed: true,
+        }
+    }
+
+    pub fn undirected() -> Self {
+        Graph {
+            directed: false,
+            ..Graph::directed()
+        }
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    pub fn add_node(&mut self, node: N) -> NodeId {
+        self.nodes.push(node);
+        self.edges.push(vec![]);
+        self.nodes.len() - 1
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    //@ Undirected edges count once.
+    pub fn edge_count(&self) -> usize {
+        self.edge_count
+    }
+
+    pub fn node(&self, id: NodeId) -> &N {
+        &self.nodes[id]
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut N {
+        &mut self.nodes[id]
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &N)> {
+        self.nodes.iter().enumerate()
+    }
+
+    pub fn edges_from(&self, id: NodeId) -> impl Iterator<Item = (NodeId, &E)> {
+        self.edges[id].iter().map(|(to, edge)| (*to, edge))
+    }
+
+    pub fn neighbors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.edges[id].iter().map(|(to, _)| *to)
+    }
+
+    //@ Every edge once, as `(from, to, edge)`. Undirected edges come out from their smaller end.
+    pub fn edges(&self) -> impl Iterator<Item = (NodeId, NodeId, &E)> {
+        self.edges
+            .iter()
+            .enumerate()
+            .flat_map(move |(from, edges)| {
+                edges
+                    .iter()
+                    .filter(move |(to, _)| self.directed || from <= *to)
+                    .map(move |(to, edge)| (from, *to, edge))
+            })
+    }
+}
+
+impl<N, E: Clone> Graph<N, E> {
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId, edge: E) {
+        let n = self.nodes.len();
+        assert!(
+            from < n && to < n,
+            "edge {from} -> {to} in a graph of {n} nodes"
+        );
+        if !self.directed && from != to {
+            self.edges[to].push((from, edge.clone()));
+        }
+        self.edges[from].push((to, edge));
+        self.edge_count += 1;
+    }
+}
+
+impl<N: Hash + Eq + Clone, E: Clone> Graph<N, E> {
+    //@ Builds a graph from a list of `(from, to, edge)` triples, where nodes are identified by
+    //@ their weights, like the ids of cities. Each one becomes a node the first time it shows up.
+    //@ Also returns the id of every node.
+    pub fn from_edges(
+        directed: bool,
+        edges: impl IntoIterator<Item = (N, N, E)>,
+    ) -> (Self, HashMap<N, NodeId>) {
+        let mut graph = if directed {
+            Graph::directed()
+        } else {
+            Graph::undirected()
+        };
+        let mut ids = HashMap::new();
+        for (from, to, edge) in edges {
+            let [from, to] = [from, to].map(|node| graph.id(&mut ids, node));
+            graph.add_edge(from, to, edge);
+        }
+        (graph, ids)
+    }
+
+    //@ The id of `node`, which gets added if it isn't in `ids` yet. `ids` has to map the nodes of
+    //@ this graph to their ids, so this stays inside the crate.
+    pub(crate) fn id(&mut self, ids: &mut HashMap<N, NodeId>, node: N) -> NodeId {
+        if let Some(&id) = ids.get(&node) {
+            return id;
+        }
+        let id = self.add_node(node.clone());
+        ids.insert(node, id);
+        id
+    }
+}
+
+impl<N: Hash + Eq + Clone> Graph<N> {
+    //@ Builds a directed graph from an adjacency list like the one `kosaraju` takes, where every
+    //@ key maps to the nodes it has an edge to. Keys without edges still become nodes.
+    pub fn from_adjacency(adjacency: &HashMap<N, Vec<N>>) -> (Self, HashMap<N, NodeId>) {
+        let mut graph = Graph::directed();
+        let mut ids = HashMap::new();
+        for (from, to) in adjacency {
+            let from = graph.id(&mut ids, from.clone());
+            for to in to {
+                let to = graph.id(&mut ids, to.clone());
+                graph.add_edge(from, to, ());
+            }
+        }
+        (graph, ids)
+    }
+}
+
+impl<N, E> Adjacency for Graph<N, E> {
+    type Edge = E;
+
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    fn edges_from(&self, node: NodeId) -> impl Iterator<Item = (NodeId, &E)> {
+        Graph::edges_from(self, node)
+    }
+}
+
+//@ ## Compressed Sparse Row
+//@ A `Vec` of `Vec`s costs an allocation and 24 bytes per node, and the edges of different nodes
+//@ end up all over the heap. A graph that won't change can be packed into the [CSR
+//@ format](https://en.wikipedia.org/wiki/Sparse_matrix#Compressed_sparse_row_(CSR,_CRS_or_Yale_format))
+//@ instead: all of the edges in one `Vec`, sorted by the node they start from, and the offset
+//@ where each node's edges start in another, so the edges of node `i` are at
+//@ `offsets[i]..offsets[i + 1]`. Targets and weights are kept apart, so that algorithms that
+//@ don't look at weights only read the targets.
+pub struct Csr<N, E = ()> {
+    nodes: Vec<N>,
+    offsets: Vec<usize>,
+    targets: Vec<NodeId>,
+    weights: Vec<E>,
+    edge_count: usize,
+    directed: bool,
+}
+
+impl<N, E> From<Graph<N, E>> for Csr<N, E> {
+    fn from(graph: Graph<N, E>) -> Self {
+        let offsets = iter::once(0)
+            .chain(graph.edges.iter().scan(0, |offset, edges| {
+                *offset += edges.len();
+                Some(*offset)
+            }))
+            .collect();
+        let (targets, weights) = graph.edges.into_iter().flatten().unzip();
+        Csr {
+            nodes: graph.nodes,
+            offsets,
+            targets,
+            weights,
+            edge_count: graph.edge_count,
+            directed: graph.directed,
+        }
+    }
+}
+
+impl<N, E> Csr<N, E> {
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edge_count
+    }
+
+    pub fn node(&self, id: NodeId) -> &N {
+        &self.nodes[id]
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &N)> {
+        self.nodes.iter().enumerate()
+    }
+
+    fn range(&self, id: NodeId) -> Range<usize> {
+        self.offsets[id]..self.offsets[id + 1]
+    }
+
+    pub fn edges_from(&self, id: NodeId) -> impl Iterator<Item = (NodeId, &E)> {
+        let range = self.range(id);
+        self.targets[range.clone()]
+            .iter()
+            .copied()
+            .zip(&self.weights[range])
+    }
+
+    pub fn neighbors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.targets[self.range(id)].iter().copied()
+    }
+
+    //@ Every edge once, like `Graph::edges`.
+    pub fn edges(&self) -> impl Iterator<Item = (NodeId, NodeId, &E)> {
+        (0..self.nodes.len()).flat_map(move |from| {
+            self.edges_from(from)
+                .filter(move |(to, _)| self.directed || from <= *to)
+                .map(move |(to, edge)| (from, to, edge))
+        })
+    }
+}
+
+impl<N, E> Adjacency for Csr<N, E> {
+    type Edge = E;
+
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    fn edges_from(&self, node: NodeId) -> impl Iterator<Item = (NodeId, &E)> {
+        Csr::edges_from(self, node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    #[test]
+    fn ex1() {
+        let (roads, ids) = Graph::from_edges(
+            false,
+            [
+                ("Oslo", "Bergen", 463),
+                ("Oslo", "Trondheim", 494),
+                ("Bergen", "Bergen", 0),
+            ],
+        );
+        assert_eq!(roads.node_count(), 3);
+        assert_eq!(roads.edge_count(), 3);
+        let from_bergen: Vec<_> = roads
+            .edges_from(ids["Bergen"])
+            .map(|(to, km)| (*roads.node(to), *km))
+            .collect();
+        assert_eq!(from_bergen, [("Oslo", 463), ("Bergen", 0)]);
+        let edges: Vec<_> = roads.edges().map(|(a, b, km)| (a, b, *km)).collect();
+        assert_eq!(edges, [(0, 1, 463), (0, 2, 494), (1, 1, 0)]);
+
+        let mut deps = Graph::directed();
+        let [app, lib, core] = ["app", "lib", "core"].map(|name| deps.add_node(name));
+        deps.add_edge(app, lib, ());
+        deps.add_edge(lib, core, ());
+        deps.add_edge(app, core, ());
+        assert_eq!(deps.neighbors(app).collect::<Vec<_>>(), [lib, core]);
+        assert_eq!(deps.neighbors(core).count(), 0);
+
+        let deps = Csr::from(deps);
+        assert_eq!(deps.neighbors(app).collect::<Vec<_>>(), [lib, core]);
+        assert_eq!(deps.edge_count(), 3);
+    }
+
+    #[test]
+    fn from_adjacency() {
+        let (graph, ids) = Graph::from_adjacency(&HashMap::from([(1, vec![2, 3]), (4, vec![])]));
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.neighbors(ids[&4]).count(), 0);
+        let mut neighbors: Vec<_> = graph.neighbors(ids[&1]).map(|id| graph.node(id)).collect();
+        neighbors.sort();
+        assert_eq!(neighbors, [&2, &3]);
+    }
+
+    //@ The CSR form has the same edges, in the same order, and both list every undirected edge
+    //@ from both ends.
+    #[quickcheck]
+    fn csr_matches_graph(directed: bool, edges: Vec<(u8, u8, u32)>) -> bool {
+        let (graph, _) =
+            Graph::from_edges(directed, edges.iter().map(|&(a, b, w)| (a % 16, b % 16, w)));
+        let expected: Vec<Vec<(NodeId, u32)>> = graph
+            .node_ids()
+            .map(|id| graph.edges_from(id).map(|(to, w)| (to, *w)).collect())
+            .collect();
+        let edge_list: Vec<_> = graph.edges().map(|(a, b, w)| (a, b, *w)).collect();
+        let both_ends = graph.node_ids().all(|id| {
+            graph
+                .neighbors(id)
+                .all(|to| directed || graph.neighbors(to).any(|back| back == id))
+        });
+        let csr = Csr::from(graph);
+        both_ends
+            && edge_list.len() == edges.len()
+            && csr.edge_count() == edges.len()
+            && csr.edges().map(|(a, b, w)| (a, b, *w)).eq(edge_list)
+            && csr.node_ids().all(|id| {
+                csr.edges_from(id)
+                    .map(|(to, w)| (to, *w))
+                    .eq(expected[id].clone())
+            })
+    }
+}
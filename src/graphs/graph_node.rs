@@ -0,0 +1,262 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+    iter,
+    rc::Rc,
+};
+
+use crate::trees::tree_node::{GraphLink, GraphNode};
+
+//@ Building, cloning and traversing graphs made of linked
+//@ [`GraphNode`](../trees/tree_node.html)s.
+//@
+//@ Values don't have to be unique, so the traversals identify nodes by their address, which is
+//@ what `Rc::as_ptr` gives us.
+type Key<T> = *const RefCell<GraphNode<T>>;
+
+//@ Builds a graph from an adjacency list like the one `kosaraju` takes, where every key maps to
+//@ the list of nodes it has an edge to. Nodes that only show up as targets get created too.
+//@ The returned map owns the graph: dropping it frees every node.
+pub fn from_adjacency<T: Hash + Eq + Clone>(
+    adjacency: &HashMap<T, Vec<T>>,
+) -> HashMap<T, GraphLink<T>> {
+    let mut nodes = HashMap::new();
+    for (from, to) in adjacency {
+        for val in iter::once(from).chain(to) {
+            nodes
+                .entry(val.clone())
+                .or_insert_with(|| GraphNode::new(val.clone()));
+        }
+    }
+    for (from, to) in adjacency {
+        for to in to {
+            GraphNode::connect(&nodes[from], &nodes[to]);
+        }
+    }
+    nodes
+}
+
+//@ The inverse of `from_adjacency` for the part of the graph reachable from `start`, assuming
+//@ every node holds a different value.
+pub fn to_adjacency<T: Hash + Eq + Clone>(start: &GraphLink<T>) -> HashMap<T, Vec<T>> {
+    bfs(start)
+        .map(|node| {
+            let node = node.borrow();
+            let neighbors = node
+                .neighbors()
+                .iter()
+                .map(|n| n.borrow().val.clone())
+                .collect();
+            (node.val.clone(), neighbors)
+        })
+        .collect()
+}
+
+//@ ## Traversals
+//@ Both traversals only visit the nodes reachable from `start`, each of them once, which is what
+//@ keeps them from going around a cycle forever.
+//@
+//@ Breadth-first search marks a node as seen when it's queued, so it's only queued once.
+pub struct Bfs<T> {
+    queue: VecDeque<GraphLink<T>>,
+    seen: HashSet<Key<T>>,
+}
+
+pub fn bfs<T>(start: &GraphLink<T>) -> Bfs<T> {
+    Bfs {
+        queue: VecDeque::from([Rc::clone(start)]),
+        seen: HashSet::from([Rc::as_ptr(start)]),
+    }
+}
+
+impl<T> Iterator for Bfs<T> {
+    type Item = GraphLink<T>;
+
+    fn next(&mut self) -> Option<GraphLink<T>> {
+        let node = self.queue.pop_front()?;
+        for neighbor in node.borrow().neighbors() {
+            if self.seen.insert(Rc::as_ptr(&neighbor)) {
+                self.queue.push_back(neighbor);
+            }
+        }
+        Some(node)
+    }
+}
+
+//@ Depth-first search with an explicit stack has to mark a node as seen when it's popped instead,
+//@ which allows a node to be on the stack more than once but yields the nodes in the same
+//@ pre-order as the recursive version. Neighbors are pushed in reverse so that the first one is
+//@ visited first.
+pub struct Dfs<T> {
+    stack: Vec<GraphLink<T>>,
+    seen: HashSet<Key<T>>,
+}
+
+pub fn dfs<T>(start: &GraphLink<T>) -> Dfs<T> {
+    Dfs {
+        stack: vec![Rc::clone(start)],
+        seen: HashSet::new(),
+    }
+}
+
+impl<T> Iterator for Dfs<T> {
+    type Item = GraphLink<T>;
+
+    fn next(&mut self) -> Option<GraphLink<T>> {
+        loop {
+            let node = self.stack.pop()?;
+            if !self.seen.insert(Rc::as_ptr(&node)) {
+                continue;
+            }
+            let neighbors = node.borrow().neighbors();
+            self.stack.extend(
+                neighbors
+                    .into_iter()
+                    .rev()
+                    .filter(|n| !self.seen.contains(&Rc::as_ptr(n))),
+            );
+            return Some(node);
+        }
+    }
+}
+
+//@ ## Cloning
+//@ Deriving `Clone` on a node only copies its edges, which still point into the old graph.
+//@ A deep clone has to create a new node for every reachable node first, and only then connect
+//@ the copies, using a map from old nodes to their copies. That way an edge that closes a cycle
+//@ points to the copy that already exists instead of creating another one.
+//@
+//@ The copies are returned in BFS order, so the first one is the copy of `start`, and the `Vec`
+//@ owns the new graph.
+pub fn deep_clone<T: Clone>(start: &GraphLink<T>) -> Vec<GraphLink<T>> {
+    let originals: Vec<GraphLink<T>> = bfs(start).collect();
+    let copies: HashMap<Key<T>, GraphLink<T>> = originals
+        .iter()
+        .map(|node| (Rc::as_ptr(node), GraphNode::new(node.borrow().val.clone())))
+        .collect();
+
+    for node in &originals {
+        let copy = &copies[&Rc::as_ptr(node)];
+        for neighbor in node.borrow().neighbors() {
+            GraphNode::connect(copy, &copies[&Rc::as_ptr(&neighbor)]);
+        }
+    }
+    originals
+        .iter()
+        .map(|node| Rc::clone(&copies[&Rc::as_ptr(node)]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    fn vals<T: Clone>(nodes: impl Iterator<Item = GraphLink<T>>) -> Vec<T> {
+        nodes.map(|node| node.borrow().val.clone()).collect()
+    }
+
+    //@ The square from LeetCode's "Clone Graph": 1 - 2 - 3 - 4 - 1, with undirected edges.
+    fn square() -> HashMap<u32, Vec<u32>> {
+        HashMap::from([
+            (1, vec![2, 4]),
+            (2, vec![1, 3]),
+            (3, vec![2, 4]),
+            (4, vec![1, 3]),
+        ])
+    }
+
+    #[test]
+    fn traversals() {
+        let graph = from_adjacency(&square());
+        assert_eq!(vals(bfs(&graph[&1])), vec![1, 2, 4, 3]);
+        assert_eq!(vals(dfs(&graph[&1])), vec![1, 2, 3, 4]);
+        assert_eq!(to_adjacency(&graph[&1]), square());
+    }
+
+    #[test]
+    fn clone_square() {
+        let graph = from_adjacency(&square());
+        let copies = deep_clone(&graph[&1]);
+        assert_eq!(copies.len(), 4);
+        assert_eq!(to_adjacency(&copies[0]), square());
+
+        //@ No copy is an original node, and changing the copies leaves the originals alone.
+        let originals: HashSet<_> = graph.values().map(Rc::as_ptr).collect();
+        assert!(copies.iter().all(|c| !originals.contains(&Rc::as_ptr(c))));
+        copies[0].borrow_mut().val = 10;
+        copies[0].borrow_mut().neighbors.clear();
+        assert_eq!(to_adjacency(&graph[&1]), square());
+    }
+
+    //@ Dropping the map that owns a cyclic graph frees all of its nodes.
+    #[test]
+    fn cycles_dont_leak() {
+        let graph = from_adjacency(&square());
+        let weak = Rc::downgrade(&graph[&3]);
+        let copies = deep_clone(&graph[&1]);
+        drop(graph);
+        assert!(weak.upgrade().is_none());
+        assert_eq!(vals(bfs(&copies[0])), vec![1, 2, 4, 3]);
+    }
+
+    #[test]
+    fn self_loop_and_duplicates() {
+        let a = GraphNode::new('a');
+        let b = GraphNode::new('a');
+        GraphNode::connect(&a, &a);
+        GraphNode::connect(&a, &b);
+        GraphNode::connect(&b, &a);
+
+        let copies = deep_clone(&a);
+        assert_eq!(copies.len(), 2);
+        let first = copies[0].borrow().neighbors();
+        assert!(Rc::ptr_eq(&first[0], &copies[0]));
+        assert!(Rc::ptr_eq(&first[1], &copies[1]));
+        assert_eq!(vals(dfs(&copies[0])), vec!['a', 'a']);
+    }
+
+    //@ The nodes reachable from the start are computed straight from the adjacency list. Both
+    //@ traversals have to visit exactly those, the BFS in order of distance, and a clone must
+    //@ have the same reachable adjacency list.
+    #[quickcheck]
+    fn matches_adjacency(edges: Vec<(u8, u8)>, start: u8) -> bool {
+        let mut adjacency: HashMap<u8, Vec<u8>> = HashMap::new();
+        for &(from, to) in &edges {
+            adjacency.entry(from % 16).or_default().push(to % 16);
+        }
+        adjacency.entry(start % 16).or_default();
+        let start = start % 16;
+        let graph = from_adjacency(&adjacency);
+
+        let mut dist = HashMap::from([(start, 0)]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            for &next in adjacency.get(&node).into_iter().flatten() {
+                if !dist.contains_key(&next) {
+                    dist.insert(next, dist[&node] + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        let reachable: HashMap<u8, Vec<u8>> = dist
+            .keys()
+            .map(|node| (*node, adjacency.get(node).cloned().unwrap_or_default()))
+            .collect();
+
+        let bfs_order = vals(bfs(&graph[&start]));
+        let mut dfs_order = vals(dfs(&graph[&start]));
+        dfs_order.sort();
+        let mut expected: Vec<u8> = dist.keys().copied().collect();
+        expected.sort();
+
+        let copies = deep_clone(&graph[&start]);
+        bfs_order.windows(2).all(|w| dist[&w[0]] <= dist[&w[1]])
+            && bfs_order.len() == expected.len()
+            && dfs_order == expected
+            && to_adjacency(&graph[&start]) == reachable
+            && to_adjacency(&copies[0]) == reachable
+    }
+}
@@ -1,4 +1,7 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::graph::{Adjacency, Graph, NodeId};
 
 //@ An implementation of Kosaraju's algorithm, which finds the strongly connected components (SCCs)
 //@ of a graph, where any node in an SCC can reach any other node in the SCC.
@@ -9,101 +12,150 @@ use std::collections::{HashMap, HashSet, VecDeque};
 //@ Output: A dictionary where the key is the root of each SCC, and the value is the list of
 //@ connected nodes in the SCCs.
 //@
-//@ The algorithm takes 4 steps.
-pub fn kosaraju(graph: HashMap<u32, Vec<u32>>) -> HashMap<u32, Vec<u32>> {
-    //@ 1. the algorithm allocates a few data structures required later.
-    //@ - A dictionary of outdegrees `outdegrees`, where each node is the key and its values are the nodes it is connected to.
-    let mut outdegrees: HashMap<u32, Vec<u32>> = HashMap::new();
-    //@ - A dictionary of indegrees `indegrees`, which is the opposite. Every node notes which nodes connect to it.
-    let mut indegrees: HashMap<u32, Vec<u32>> = HashMap::new();
-    //@ - A set `visited`, which is used to avoid cycles when visiting the graph.
-    let mut visited = HashSet::new();
-    //@ - A deque `l`, which is used to keep track of the order in which nodes are visited.
-    let mut l = VecDeque::new();
-    //@ - A set `assigned`, which is used to keep track of the nodes assigned to an SCC.
-    let mut assigned = HashSet::new();
-    //@ - A dictionary `islands` which is used to note the SCCs and is returned to the caller.
-    let mut islands: HashMap<u32, Vec<u32>> = HashMap::new();
-
-    //@ 2. the algorithm populates the outdegrees and indegrees. Since the input
-    //@ is already an outdegree graph, we iterate through each node's edges and do the following:
-    for (node, edges) in &graph {
-        for edge in edges {
-            //@ For the outdegree graph, set the outdegree's key as the node and add the edge to its values.
-            outdegrees.entry(*node).or_default().push(*edge);
-            //@ For the indegree graph, set the indegree's key as the edge and add the node to its values.
-            indegrees.entry(*edge).or_default().push(*node);
-        }
-    }
-
-    //@ 3. the algorithm creates a visit function, which is called on every node in the graph.
-    //@ `Visit(node)` is a recursive function that does the following:
-    //@  - If `node` is not in the visited set:
-    //@     - Add `node` to `visited`.
-    //@     - For each outdegree of node, call `visit(outdegree)`.
-    //@     - Prepend node to `l`.
-    //@  - Else:
-    //@     - Do nothing
-    fn visit(
-        node: u32,
-        visited: &mut HashSet<u32>,
-        outdegrees: &HashMap<u32, Vec<u32>>,
-        l: &mut VecDeque<u32>,
-    ) {
-        if !visited.contains(&node) {
-            visited.insert(node);
-            let neighbors = outdegrees.get(&node).cloned().unwrap_or_default();
-            for neighbor in neighbors {
-                visit(neighbor, visited, outdegrees, l);
-            }
-            l.push_front(node);
-        }
-    }
-
-    //@ The algorithm then visits all of the nodes in the graph.
-    for node in graph.keys() {
-        visit(*node, &mut visited, &outdegrees, &mut l);
-    }
-
-    //@ 4. the algorithm creates an assign function, which is called on every node in l in order.
-    //@ `Assign(node, root)` is a recursive function that does the following:
-    //@  - If a `node` is not in the assigned set:
-    //@     - Add `node` to `assigned`.
-    //@     - Assign `node` to `root`'s SCC.
-    //@     - For each indegree of node, call `assign(indegree, root)`.
-    //@  - Else:
-    //@     - Do nothing
-    fn assign(
-        node: u32,
-        root: u32,
-        assigned: &mut HashSet<u32>,
-        indegrees: &HashMap<u32, Vec<u32>>,
-        islands: &mut HashMap<u32, Vec<u32>>,
-    ) {
-        if !assigned.contains(&node) {
-            assigned.insert(node);
-            islands.entry(root).or_default().push(node);
-            let neighbors = indegrees.get(&node).cloned().unwrap_or_default();
-            for neighbor in neighbors {
-                assign(neighbor, root, assigned, indegrees, islands);
+//@ It builds a [`Graph`](./graph.html) out of the dictionary, and translates the components that
+//@ `strongly_connected_components` finds back.
+pub fn kosaraju<N: Hash + Eq + Clone>(graph: HashMap<N, Vec<N>>) -> HashMap<N, Vec<N>> {
+    let (graph, _) = Graph::from_adjacency(&graph);
+    strongly_connected_components(&graph)
+        .into_iter()
+        .map(|component| {
+            let nodes: Vec<N> = component.iter().map(|&id| graph.node(id).clone()).collect();
+            (nodes[0].clone(), nodes)
+        })
+        .collect()
+}
+
+//@ Returns the SCCs of any graph as lists of node ids, where the first node of each SCC is its
+//@ root. The SCCs come out in topological order of the condensation, the graph with a node per
+//@ SCC: if there's an edge from one SCC to another, the first one comes first.
+//@
+//@ Both searches use explicit stacks, since a recursive search is as deep as the longest path,
+//@ and a chain of 50,000 nodes already overflows the 2MB stack of a spawned thread. The stack
+//@ holds the iterator over the rest of the neighbors of each node on it, which is what a
+//@ recursive call would keep in its frame.
+//@
+//@ The algorithm takes 3 steps.
+pub fn strongly_connected_components<G: Adjacency>(graph: &G) -> Vec<Vec<NodeId>> {
+    let n = graph.node_count();
+
+    //@ 1. The algorithm visits every node with a depth-first search, and notes the order in
+    //@ which it finishes them, after all of their descendants. The node that finishes last is in
+    //@ an SCC that no other SCC has an edge to.
+    let mut visited = vec![false; n];
+    let mut finished = Vec::with_capacity(n);
+    for start in graph.node_ids() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut stack = vec![(start, graph.neighbors(start))];
+        while let Some((node, neighbors)) = stack.last_mut() {
+            if let Some(next) = neighbors.find(|&next| !visited[next]) {
+                visited[next] = true;
+                stack.push((next, graph.neighbors(next)));
+            } else {
+                finished.push(*node);
+                stack.pop();
             }
         }
     }
 
-    //@ And then assigns all the nodes in the graph.
-    for node in l {
-        assign(node, node, &mut assigned, &indegrees, &mut islands);
+    //@ 2. It reverses the edges of the graph. The indegrees of all nodes go in one `Vec`, in the
+    //@ same [compact form](./graph.html#compressed-sparse-row) as a `Csr`, instead of a `Vec`
+    //@ per node.
+    let mut offsets = vec![0; n + 1];
+    for node in graph.node_ids() {
+        for next in graph.neighbors(node) {
+            offsets[next + 1] += 1;
+        }
+    }
+    for i in 0..n {
+        offsets[i + 1] += offsets[i];
+    }
+    let mut indegrees = vec![0; offsets[n]];
+    let mut filled = offsets.clone();
+    for node in graph.node_ids() {
+        for next in graph.neighbors(node) {
+            indegrees[filled[next]] = node;
+            filled[next] += 1;
+        }
+    }
+
+    //@ 3. Going from the last node to finish to the first, every node that isn't in an SCC yet
+    //@ is the root of a new one, which is every node that can reach it and isn't in an SCC yet.
+    //@ Those are found with a search on the reversed edges.
+    let mut assigned = vec![false; n];
+    let mut islands = vec![];
+    for &root in finished.iter().rev() {
+        if assigned[root] {
+            continue;
+        }
+        assigned[root] = true;
+        let mut island = vec![];
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            island.push(node);
+            for &prev in &indegrees[offsets[node]..offsets[node + 1]] {
+                if !assigned[prev] {
+                    assigned[prev] = true;
+                    stack.push(prev);
+                }
+            }
+        }
+        islands.push(island);
     }
 
     //@ And returns the strongly connected components.
     islands
 }
 
+//@ The condensation of a graph has a node per SCC, with the ids of its members, and an edge
+//@ between two SCCs if any of their members have one. It's always a DAG, since a cycle between
+//@ SCCs would make them one SCC. Its node ids are the indexes of the SCCs in topological order,
+//@ so every edge goes from a smaller id to a larger one, and it also returns the SCC of every
+//@ node of the original graph.
+//@
+//@ Many edges between the same two SCCs only make one edge in the condensation. Instead of
+//@ sorting the targets of each SCC, the last SCC that added an edge to a target is kept, so
+//@ removing the duplicates takes O(V + E) time.
+pub fn condensation<G: Adjacency>(graph: &G) -> (Graph<Vec<NodeId>>, Vec<NodeId>) {
+    let components = strongly_connected_components(graph);
+    let mut component = vec![0; graph.node_count()];
+    for (id, members) in components.iter().enumerate() {
+        for &node in members {
+            component[node] = id;
+        }
+    }
+
+    let mut dag = Graph::directed();
+    for members in &components {
+        dag.add_node(members.clone());
+    }
+    let mut added_by = vec![usize::MAX; components.len()];
+    for (id, members) in components.iter().enumerate() {
+        for &node in members {
+            for next in graph.neighbors(node) {
+                let to = component[next];
+                if to != id && added_by[to] != id {
+                    added_by[to] = id;
+                    dag.add_edge(id, to, ());
+                }
+            }
+        }
+    }
+    (dag, component)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::graphs::graph::Csr;
+    use crate::graphs::tarjan::tarjan;
     use insta::assert_yaml_snapshot;
     use quickcheck_macros::quickcheck;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::collections::{HashSet, VecDeque};
+    use test::{black_box, Bencher};
 
     fn sort_for_test(input: HashMap<u32, Vec<u32>>) -> Vec<Vec<u32>> {
         let mut sorted: Vec<Vec<_>> = input.values().cloned().collect();
@@ -135,6 +187,25 @@ mod tests {
         assert_yaml_snapshot!(sort_for_test(kosaraju(input)));
     }
 
+    //@ Components of a graph in the compact form, as ids.
+    #[test]
+    fn csr() {
+        let edges = [(0, 1), (1, 0), (1, 2), (2, 3), (3, 4), (4, 2)];
+        let (graph, ids) = Graph::from_edges(true, edges.map(|(a, b)| (a, b, ())));
+        let graph = Csr::from(graph);
+        let mut components: Vec<Vec<u32>> = strongly_connected_components(&graph)
+            .into_iter()
+            .map(|c| {
+                let mut c: Vec<u32> = c.into_iter().map(|id| *graph.node(id)).collect();
+                c.sort();
+                c
+            })
+            .collect();
+        components.sort();
+        assert_eq!(components, [vec![0, 1], vec![2, 3, 4]]);
+        assert_eq!(ids.len(), 5);
+    }
+
     #[quickcheck]
     fn verify_all_islands(input: HashMap<u32, Vec<u32>>) -> bool {
         if input.len() > 50 {
@@ -148,4 +219,201 @@ mod tests {
         let all_islands: HashSet<&u32> = HashSet::from_iter(result.values().flatten());
         unique_vals == all_islands
     }
+
+    //@ Two cycles, with three edges from the first to the second, and a node that points into
+    //@ both of them.
+    #[test]
+    fn condensed() {
+        let edges = [
+            (0, 1),
+            (1, 0),
+            (0, 2),
+            (1, 2),
+            (1, 3),
+            (2, 3),
+            (3, 2),
+            (4, 0),
+            (4, 3),
+        ];
+        let (graph, _) = Graph::from_edges(true, edges.map(|(a, b)| (a, b, ())));
+        let (dag, component) = condensation(&graph);
+        let members: Vec<Vec<u32>> = dag
+            .nodes()
+            .map(|(_, members)| {
+                let mut members: Vec<u32> = members.iter().map(|&id| *graph.node(id)).collect();
+                members.sort();
+                members
+            })
+            .collect();
+        assert_eq!(members, [vec![4], vec![0, 1], vec![2, 3]]);
+        assert_eq!(
+            dag.edges().map(|(a, b, _)| (a, b)).collect::<Vec<_>>(),
+            [(0, 1), (0, 2), (1, 2)]
+        );
+        assert!(graph
+            .nodes()
+            .all(|(id, _)| dag.node(component[id]).contains(&id)));
+    }
+
+    //@ The condensation has an edge exactly where the graph has one between different SCCs, only
+    //@ once, and from a smaller id to a larger one.
+    #[quickcheck]
+    fn condensation_is_a_dag(edges: Vec<(u8, u8)>) -> bool {
+        let (graph, _) =
+            Graph::from_edges(true, edges.into_iter().map(|(a, b)| (a % 32, b % 32, ())));
+        let (dag, component) = condensation(&graph);
+        let mut expected: Vec<(NodeId, NodeId)> = graph
+            .edges()
+            .map(|(a, b, _)| (component[a], component[b]))
+            .filter(|(a, b)| a != b)
+            .collect();
+        expected.sort();
+        expected.dedup();
+        let mut actual: Vec<(NodeId, NodeId)> = dag.edges().map(|(a, b, _)| (a, b)).collect();
+        let count = actual.len();
+        actual.sort();
+        count == expected.len() && actual == expected && actual.iter().all(|(a, b)| a < b)
+    }
+
+    //@ A chain of a million nodes, closed into a cycle halfway, is as deep as a graph gets.
+    #[test]
+    fn long_chain() {
+        let n = 1_000_000;
+        let mut graph = Graph::directed();
+        for i in 0..n {
+            graph.add_node(i);
+        }
+        for i in 1..n {
+            graph.add_edge(i - 1, i, ());
+        }
+        graph.add_edge(n / 2, 0, ());
+        for components in [strongly_connected_components(&graph), tarjan(&graph)] {
+            assert_eq!(components.len(), n / 2);
+            assert_eq!(components[0].len(), n / 2 + 1);
+            assert_eq!(components[1], [n / 2 + 1]);
+        }
+    }
+
+    //@ The recursive version this module used to have, with the same two searches, to benchmark
+    //@ against.
+    fn recursive<G: Adjacency>(graph: &G) -> Vec<Vec<NodeId>> {
+        fn visit<G: Adjacency>(
+            node: NodeId,
+            visited: &mut [bool],
+            graph: &G,
+            l: &mut VecDeque<NodeId>,
+        ) {
+            if !visited[node] {
+                visited[node] = true;
+                for neighbor in graph.neighbors(node) {
+                    visit(neighbor, visited, graph, l);
+                }
+                l.push_front(node);
+            }
+        }
+
+        fn assign(
+            node: NodeId,
+            assigned: &mut [bool],
+            indegrees: &[Vec<NodeId>],
+            island: &mut Vec<NodeId>,
+        ) {
+            if !assigned[node] {
+                assigned[node] = true;
+                island.push(node);
+                for &neighbor in &indegrees[node] {
+                    assign(neighbor, assigned, indegrees, island);
+                }
+            }
+        }
+
+        let n = graph.node_count();
+        let mut indegrees = vec![vec![]; n];
+        for node in graph.node_ids() {
+            for next in graph.neighbors(node) {
+                indegrees[next].push(node);
+            }
+        }
+        let (mut visited, mut l) = (vec![false; n], VecDeque::new());
+        for node in graph.node_ids() {
+            visit(node, &mut visited, graph, &mut l);
+        }
+        let mut assigned = vec![false; n];
+        let mut islands = vec![];
+        for node in l {
+            if !assigned[node] {
+                let mut island = vec![];
+                assign(node, &mut assigned, &indegrees, &mut island);
+                islands.push(island);
+            }
+        }
+        islands
+    }
+
+    #[quickcheck]
+    fn matches_recursive(edges: Vec<(u8, u8)>) -> bool {
+        let (graph, _) =
+            Graph::from_edges(true, edges.into_iter().map(|(a, b)| (a % 32, b % 32, ())));
+        let sorted = |mut components: Vec<Vec<NodeId>>| {
+            components.iter_mut().for_each(|c| c.sort());
+            components.sort();
+            components
+        };
+        sorted(strongly_connected_components(&graph)) == sorted(recursive(&graph))
+    }
+
+    //@ Random graphs with 3 edges per node, which have one giant SCC and lots of small ones.
+    //@ With 10k nodes, which the recursive version can still handle on the 2MB stack of a test
+    //@ thread:
+    //@ - recursive: 3.56ms
+    //@ - kosaraju: 1.83ms
+    //@ - tarjan: 1.23ms
+    //@
+    //@ The recursive version reverses the graph into a `Vec` per node, and finishes nodes by
+    //@ pushing them to the front of a `VecDeque`. Tarjan's algorithm is faster still, since it
+    //@ only searches once and doesn't reverse the graph at all. With a million nodes, most of the
+    //@ time goes to cache misses, and the two are closer:
+    //@ - kosaraju: 698ms
+    //@ - tarjan: 609ms
+    fn random_graph(n: usize) -> Csr<()> {
+        let mut rng = StdRng::seed_from_u64(46);
+        let mut graph = Graph::directed();
+        for _ in 0..n {
+            graph.add_node(());
+        }
+        for _ in 0..3 * n {
+            graph.add_edge(rng.gen_range(0..n), rng.gen_range(0..n), ());
+        }
+        Csr::from(graph)
+    }
+
+    #[bench]
+    fn bench_recursive_10k(b: &mut Bencher) {
+        let graph = random_graph(10_000);
+        b.iter(|| black_box(recursive(&graph)));
+    }
+
+    #[bench]
+    fn bench_kosaraju_10k(b: &mut Bencher) {
+        let graph = random_graph(10_000);
+        b.iter(|| black_box(strongly_connected_components(&graph)));
+    }
+
+    #[bench]
+    fn bench_tarjan_10k(b: &mut Bencher) {
+        let graph = random_graph(10_000);
+        b.iter(|| black_box(tarjan(&graph)));
+    }
+
+    #[bench]
+    fn bench_kosaraju_1m(b: &mut Bencher) {
+        let graph = random_graph(1_000_000);
+        b.iter(|| black_box(strongly_connected_components(&graph)));
+    }
+
+    #[bench]
+    fn bench_tarjan_1m(b: &mut Bencher) {
+        let graph = random_graph(1_000_000);
+        b.iter(|| black_box(tarjan(&graph)));
+    }
 }
@@ -0,0 +1,201 @@
+use super::graph::{Adjacency, NodeId};
+use super::union_find::UnionFind;
+
+//@ A minimum spanning forest: a minimum spanning tree of every connected component of a graph.
+pub struct SpanningForest<E> {
+    //@ The edges of the forest, from the cheapest to the most expensive.
+    pub edges: Vec<(NodeId, NodeId, E)>,
+    pub total: u64,
+    //@ The number of trees, which is the number of connected components.
+    pub trees: usize,
+}
+
+impl<E> SpanningForest<E> {
+    //@ Whether the forest is a single tree, which connects the whole graph.
+    pub fn is_spanning_tree(&self) -> bool {
+        self.trees <= 1
+    }
+}
+
+//@ [Kruskal's algorithm](https://en.wikipedia.org/wiki/Kruskal%27s_algorithm) finds a minimum
+//@ spanning forest of an undirected graph. It goes through the edges from the cheapest to the
+//@ most expensive, and adds every edge that connects two different trees of the forest so far,
+//@ which a [union-find](./union_find.html) keeps track of. Sorting the edges takes O(E log(E))
+//@ time, and the union-find takes close to O(1) per edge.
+//@
+//@ Unlike [Prim's algorithm](./connecting_cities_with_minimum_cost.html), which grows a single
+//@ tree from one node, it doesn't need a starting node, and a disconnected graph just ends up as
+//@ more than one tree. Its total is a `u64`, for the same reason as Prim's.
+//@
+//@ An undirected graph has every edge in the lists of both of its nodes, so only the copies from
+//@ the smaller node are used. A directed graph would lose half of its edges that way, so it's
+//@ rejected.
+pub fn kruskal<G: Adjacency>(graph: &G) -> SpanningForest<G::Edge>
+where
+    G::Edge: Copy + Into<u64>,
+{
+    assert!(
+        !graph.is_directed(),
+        "spanning trees need an undirected graph"
+    );
+    let mut edges: Vec<(u64, NodeId, NodeId, G::Edge)> = graph
+        .node_ids()
+        .flat_map(|from| {
+            graph
+                .edges_from(from)
+                .filter(move |&(to, _)| from < to)
+                .map(move |(to, &edge)| (edge.into(), from, to, edge))
+        })
+        .collect();
+    edges.sort_unstable_by_key(|&(cost, from, to, _)| (cost, from, to));
+
+    let mut trees = UnionFind::new(graph.node_count());
+    let mut forest = vec![];
+    let mut total: u64 = 0;
+    for (cost, from, to, edge) in edges {
+        if trees.union(from, to) {
+            forest.push((from, to, edge));
+            total += cost;
+        }
+    }
+    SpanningForest {
+        edges: forest,
+        total,
+        trees: trees.set_count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use test::{black_box, Bencher};
+
+    use super::*;
+    use crate::graphs::connecting_cities_with_minimum_cost::prim;
+    use crate::graphs::graph::{Csr, Graph};
+
+    #[test]
+    fn ex1() {
+        let (graph, _) = Graph::from_edges(false, [(1, 2, 5u32), (1, 3, 6), (2, 3, 1)]);
+        let forest = kruskal(&graph);
+        let edges: Vec<(u32, u32, u32)> = forest
+            .edges
+            .iter()
+            .map(|&(a, b, cost)| (*graph.node(a), *graph.node(b), cost))
+            .collect();
+        assert_eq!(edges, [(2, 3, 1), (1, 2, 5)]);
+        assert_eq!(forest.total, 6);
+        assert!(forest.is_spanning_tree());
+    }
+
+    //@ Two islands, and a city on neither, make three trees.
+    #[test]
+    fn forest() {
+        let mut graph = Graph::undirected();
+        for city in ["a", "b", "c", "d", "e"] {
+            graph.add_node(city);
+        }
+        graph.add_edge(0, 1, 3u8);
+        graph.add_edge(2, 3, 4);
+        graph.add_edge(3, 2, 2);
+        let forest = kruskal(&graph);
+        assert_eq!(forest.edges, [(2, 3, 2), (0, 1, 3)]);
+        assert_eq!(forest.total, 5);
+        assert_eq!(forest.trees, 3);
+        assert!(!forest.is_spanning_tree());
+    }
+
+    #[test]
+    #[should_panic(expected = "spanning trees need an undirected graph")]
+    fn directed() {
+        let (graph, _) = Graph::from_edges(true, [(1, 0, 1u32)]);
+        kruskal(&Csr::from(graph));
+    }
+
+    //@ Edges that cost more than `u32::MAX` together.
+    #[test]
+    fn large_total() {
+        let (graph, _) = Graph::from_edges(false, [(0, 1, u32::MAX), (1, 2, u32::MAX)]);
+        assert_eq!(kruskal(&Csr::from(graph)).total, 2 * u64::from(u32::MAX));
+    }
+
+    //@ Prim's algorithm only works on connected graphs, but connecting every node to a new one
+    //@ with edges that cost more than any other makes any graph connected. Its minimum spanning
+    //@ tree is then a minimum spanning forest of the original graph, plus one of the new edges
+    //@ for every tree. Kruskal's forest also has to have one edge less than the number of nodes
+    //@ in every tree, with no cycles.
+    #[quickcheck]
+    fn matches_prim(n: u8, edges: Vec<(u8, u8, u16)>) -> bool {
+        let n = usize::from(n % 32);
+        let (mut graph, mut connected) = (Graph::undirected(), Graph::undirected());
+        for i in 0..=n {
+            graph.add_node(i);
+            connected.add_node(i);
+        }
+        for &(a, b, cost) in &edges {
+            let (a, b) = (usize::from(a) % (n + 1), usize::from(b) % (n + 1));
+            graph.add_edge(a, b, u32::from(cost));
+            connected.add_edge(a, b, u32::from(cost));
+        }
+        let forest = kruskal(&graph);
+
+        let heavy = u32::from(u16::MAX) + 1;
+        connected.add_node(n + 1);
+        for i in 0..=n {
+            connected.add_edge(i, n + 1, heavy);
+        }
+        let mut trees = UnionFind::new(n + 1);
+        let acyclic = forest.edges.iter().all(|&(a, b, _)| trees.union(a, b));
+
+        acyclic
+            && forest.edges.len() + forest.trees == n + 1
+            && forest
+                .edges
+                .iter()
+                .map(|&(_, _, c)| u64::from(c))
+                .sum::<u64>()
+                == forest.total
+            && prim(&connected) == Some(forest.total + forest.trees as u64 * u64::from(heavy))
+            && (prim(&graph) == Some(forest.total)) == forest.is_spanning_tree()
+    }
+
+    //@ A connected random graph with 100k nodes and a million edges:
+    //@ - kruskal: 129ms
+    //@ - prim: 386ms
+    //@
+    //@ Kruskal's algorithm sorts the edges once, in a single `Vec`, and then mostly finds roots
+    //@ in a union-find that stays flat. Prim's algorithm pushes most edges onto its heap, where
+    //@ every push and pop jumps around memory, and pops the stale ones again later.
+    fn random_graph() -> Csr<(), u32> {
+        let n = 100_000;
+        let mut rng = StdRng::seed_from_u64(50);
+        let mut graph = Graph::undirected();
+        for _ in 0..n {
+            graph.add_node(());
+        }
+        for i in 1..n {
+            graph.add_edge(rng.gen_range(0..i), i, rng.gen_range(0..1_000_000));
+        }
+        for _ in n..1_000_000 {
+            graph.add_edge(
+                rng.gen_range(0..n),
+                rng.gen_range(0..n),
+                rng.gen_range(0..1_000_000),
+            );
+        }
+        Csr::from(graph)
+    }
+
+    #[bench]
+    fn bench_kruskal(b: &mut Bencher) {
+        let graph = random_graph();
+        b.iter(|| black_box(kruskal(&graph).total));
+    }
+
+    #[bench]
+    fn bench_prim(b: &mut Bencher) {
+        let graph = random_graph();
+        b.iter(|| black_box(prim(&graph)));
+    }
+}
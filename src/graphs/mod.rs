@@ -1,4 +1,11 @@
 pub mod connecting_cities_with_minimum_cost;
+pub mod graph;
+pub mod graph_node;
 pub mod kosaraju;
+pub mod kruskal;
 pub mod minimum_cost_to_connect_sticks;
 pub mod rooted_topological_sort;
+pub mod tarjan;
+pub mod topological_sort;
+pub mod two_sat;
+pub mod union_find;
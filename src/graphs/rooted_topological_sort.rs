@@ -1,56 +1,341 @@
 use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use super::graph::{Adjacency, NodeId};
+use super::topological_sort::topological_sort;
 
 //@ This function prints out a topological sort of a company.
 //@ This function works for any DAG where there is one root node which can have many children.
+//@
+//@ The chart is checked by building an [`OrgChart`](#org-charts) out of it first, so a chart
+//@ without exactly one CEO, or with a cycle of managers, or with an employee whose manager
+//@ isn't in it, has no order.
 pub fn rooted_topological_sort(chart: &[(u32, u32, String)]) -> Option<Vec<(u64, String)>> {
-    let mut emp_to_mgr: HashMap<u32, u32> = HashMap::new();
-    let mut mgr_to_emp: HashMap<u32, Vec<u32>> = HashMap::new();
-    let mut emp_to_name: HashMap<u32, String> = HashMap::new();
-
-    //@ We start out without having found the CEO.
-    let mut ceo = None;
+    let chart = OrgChart::new(chart).ok()?;
 
-    //@ For every employee in the chart
-    for (emp_id, mgr_id, name) in chart {
-        //@ If they manage themselves, they're the CEO.
-        if emp_id == mgr_id {
-            ceo = Some(emp_id);
-        } else {
-            //@ Otherwise, we add the employee as a subordinate to their manager
-            mgr_to_emp.entry(*mgr_id).or_default().push(*emp_id);
-            //@ And add their manager to the employee.
-            emp_to_mgr.insert(*emp_id, *mgr_id);
-        }
-        //@ We then maintain a mapping of employee to name for faster lookup.
-        emp_to_name.insert(*emp_id, name.to_string());
-    }
-
-    //@ If we can't find a CEO, then there's no way to continue
-    ceo?;
+    //@ Every employee is a node, with an edge from their manager to them, and we look up the
+    //@ name of every employee in the traversal.
+    Some(
+        depth_first_with_depth(&Reports(&chart.employees), chart.root)
+            .into_iter()
+            .map(|(depth, i)| (depth, chart.employees[i].name.clone()))
+            .collect(),
+    )
+}
 
-    //@ Next, we bfs through the org chart, starting with the CEO
+//@ Traverses any graph from `root`, returning every node with its depth.
+pub fn depth_first_with_depth<G: Adjacency>(graph: &G, root: NodeId) -> Vec<(u64, NodeId)> {
+    //@ Next, we walk the org chart depth-first, starting with the CEO. Pushing reports onto the
+    //@ front of the queue and popping from the front makes it a stack.
     let mut q = VecDeque::new();
-    q.push_back((ceo.unwrap(), 0));
+    q.push_back((root, 0));
 
     let mut res = vec![];
 
     //@ While we have employees to process
     while let Some((emp_id, depth)) = q.pop_front() {
         //@ we push the employee and its depth to the result
-        res.push((depth, emp_to_name[emp_id].clone()));
+        res.push((depth, emp_id));
 
         //@ then, for this employee's subordinates (if there are any)
-        if let Some(reports) = mgr_to_emp.get(emp_id) {
-            //@ We add them to the front of the queue, since we want to process them before other
-            //@ peer employees.
-            for report_id in reports {
-                q.push_front((report_id, depth + 1));
-            }
+        //@ We add them to the front of the queue, since we want to process them before other
+        //@ peer employees.
+        for report_id in graph.neighbors(emp_id) {
+            q.push_front((report_id, depth + 1));
         }
     }
 
     //@ And then we return the collection.
-    Some(res)
+    res
+}
+
+//@ ## Org Charts
+//@
+//@ An `OrgChart` is the company as a tree, checked when it's built, which answers the questions
+//@ people ask about it:
+//@ - `subtree_size(id)`: how many people are in someone's organization, counting themselves.
+//@ - `chain_of_command(id)`: who someone reports to, all the way up to the CEO.
+//@ - `lowest_common_manager(a, b)`: the lowest manager that two people both report to.
+//@ - `reparent(id, manager)`: moves someone, and their whole organization, under a new manager.
+//@
+//@ Employees are stored in an arena, in the order of the chart, and refer to their manager and
+//@ reports by index. The depth and subtree size of every employee are computed up front, in
+//@ breadth-first order, where every manager comes before their reports.
+//@
+//@ The lowest common manager uses
+//@ [binary lifting](https://cp-algorithms.com/graph/lca_binary_lifting.html): `up[k][i]` is the
+//@ manager `2^k` levels above employee `i`, which is the manager `2^(k - 1)` levels above the
+//@ one `2^(k - 1)` levels above them, so every level of the table comes from the one before
+//@ it. To find the lowest common manager of `a` and `b`, the deeper one jumps up by the
+//@ difference in their depths, one bit at a time. If they're the same person, that's the
+//@ answer. Otherwise, they both jump up by `2^k` whenever that doesn't land on the same person,
+//@ from the biggest jump down, which leaves them right below their lowest common manager. That
+//@ takes O(log(n)) time, instead of the O(n) of walking up a long chain of command.
+//@
+//@ Moving someone changes the depths and subtree sizes of everyone under them, and the sizes
+//@ of everyone above them, so `reparent` rebuilds all of the tables, in O(n log(n)) time.
+pub struct OrgChart {
+    employees: Vec<Employee>,
+    ids: HashMap<u32, usize>,
+    root: usize,
+    depth: Vec<usize>,
+    size: Vec<usize>,
+    up: Vec<Vec<usize>>,
+}
+
+//@ The CEO is their own manager.
+struct Employee {
+    id: u32,
+    name: String,
+    manager: usize,
+    reports: Vec<usize>,
+}
+
+//@ Every employee is a node, with an edge to each of their reports.
+struct Reports<'a>(&'a [Employee]);
+
+impl Adjacency for Reports<'_> {
+    type Edge = ();
+
+    fn node_count(&self) -> usize {
+        self.0.len()
+    }
+
+    fn is_directed(&self) -> bool {
+        true
+    }
+
+    fn edges_from(&self, node: NodeId) -> impl Iterator<Item = (NodeId, &())> {
+        self.0[node].reports.iter().map(|&report| (report, &()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrgChartError {
+    //@ Nobody manages themselves, which only happens in an empty chart, since otherwise following
+    //@ managers up would find a cycle.
+    NoRoot,
+    //@ Two employees manage themselves.
+    MultipleRoots(u32, u32),
+    DuplicateEmployee(u32),
+    MissingManager { employee: u32, manager: u32 },
+    //@ Employees who manage each other in a circle, each one managing the next.
+    Cycle(Vec<u32>),
+    UnknownEmployee(u32),
+    //@ The CEO can't be moved under anyone.
+    MovingRoot(u32),
+    //@ The new manager is the employee, or someone under them.
+    ManagerInSubtree { employee: u32, manager: u32 },
+}
+
+impl Display for OrgChartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrgChartError::NoRoot => write!(f, "nobody manages themselves"),
+            OrgChartError::MultipleRoots(a, b) => {
+                write!(f, "employees {a} and {b} both manage themselves")
+            }
+            OrgChartError::DuplicateEmployee(id) => write!(f, "employee {id} is listed twice"),
+            OrgChartError::MissingManager { employee, manager } => {
+                write!(f, "employee {employee} has unknown manager {manager}")
+            }
+            OrgChartError::Cycle(cycle) => {
+                let cycle: Vec<String> = cycle.iter().map(|id| id.to_string()).collect();
+                write!(f, "employees manage each other: {}", cycle.join(" -> "))
+            }
+            OrgChartError::UnknownEmployee(id) => write!(f, "unknown employee {id}"),
+            OrgChartError::MovingRoot(id) => {
+                write!(f, "employee {id} is the CEO and can't be moved")
+            }
+            OrgChartError::ManagerInSubtree { employee, manager } => {
+                write!(f, "employee {manager} reports to employee {employee}")
+            }
+        }
+    }
+}
+
+impl Error for OrgChartError {}
+
+impl OrgChart {
+    //@ Builds the chart from `(employee, manager, name)` triples, where the CEO is the one
+    //@ employee who manages themselves.
+    pub fn new(chart: &[(u32, u32, String)]) -> Result<Self, OrgChartError> {
+        let mut ids = HashMap::new();
+        let mut root: Option<usize> = None;
+        for (i, (employee, manager, _)) in chart.iter().enumerate() {
+            if ids.insert(*employee, i).is_some() {
+                return Err(OrgChartError::DuplicateEmployee(*employee));
+            }
+            if employee == manager {
+                if let Some(first) = root {
+                    return Err(OrgChartError::MultipleRoots(chart[first].0, *employee));
+                }
+                root = Some(i);
+            }
+        }
+
+        let mut employees = Vec::with_capacity(chart.len());
+        for (employee, manager, name) in chart {
+            let manager = *ids.get(manager).ok_or(OrgChartError::MissingManager {
+                employee: *employee,
+                manager: *manager,
+            })?;
+            employees.push(Employee {
+                id: *employee,
+                name: name.clone(),
+                manager,
+                reports: vec![],
+            });
+        }
+        for i in 0..employees.len() {
+            let manager = employees[i].manager;
+            if manager != i {
+                employees[manager].reports.push(i);
+            }
+        }
+
+        //@ Everyone has one manager, so the chart is a tree exactly when there's no cycle, which
+        //@ a [topological sort](./topological_sort.html) finds.
+        topological_sort(&Reports(&employees)).map_err(|err| {
+            OrgChartError::Cycle(err.cycle().iter().map(|&i| employees[i].id).collect())
+        })?;
+        let root = root.ok_or(OrgChartError::NoRoot)?;
+
+        let mut chart = OrgChart {
+            employees,
+            ids,
+            root,
+            depth: vec![],
+            size: vec![],
+            up: vec![],
+        };
+        chart.index();
+        Ok(chart)
+    }
+
+    //@ Fills in the depths, subtree sizes and binary lifting table.
+    fn index(&mut self) {
+        let n = self.employees.len();
+        let mut order = vec![self.root];
+        let mut i = 0;
+        while i < order.len() {
+            order.extend(self.employees[order[i]].reports.iter().copied());
+            i += 1;
+        }
+
+        self.depth = vec![0; n];
+        for &i in &order[1..] {
+            self.depth[i] = self.depth[self.employees[i].manager] + 1;
+        }
+        self.size = vec![1; n];
+        for &i in order[1..].iter().rev() {
+            self.size[self.employees[i].manager] += self.size[i];
+        }
+
+        //@ Jumps of up to `2^(levels - 1)` add up to any depth in the chart.
+        let max_depth = self.depth.iter().copied().max().unwrap_or(0);
+        let levels = (usize::BITS - max_depth.leading_zeros()).max(1);
+        self.up = vec![self.employees.iter().map(|e| e.manager).collect()];
+        for k in 1..levels as usize {
+            let prev = &self.up[k - 1];
+            let next = (0..n).map(|i| prev[prev[i]]).collect();
+            self.up.push(next);
+        }
+    }
+
+    fn index_of(&self, id: u32) -> Result<usize, OrgChartError> {
+        self.ids
+            .get(&id)
+            .copied()
+            .ok_or(OrgChartError::UnknownEmployee(id))
+    }
+
+    pub fn headcount(&self) -> usize {
+        self.employees.len()
+    }
+
+    pub fn ceo(&self) -> u32 {
+        self.employees[self.root].id
+    }
+
+    pub fn name(&self, id: u32) -> Option<&str> {
+        let i = self.index_of(id).ok()?;
+        Some(&self.employees[i].name)
+    }
+
+    //@ The manager of `id`, which the CEO doesn't have.
+    pub fn manager(&self, id: u32) -> Option<u32> {
+        let i = self.index_of(id).ok()?;
+        (i != self.root).then(|| self.employees[self.employees[i].manager].id)
+    }
+
+    pub fn subtree_size(&self, id: u32) -> Option<usize> {
+        Some(self.size[self.index_of(id).ok()?])
+    }
+
+    //@ `id`, their manager, their manager's manager, and so on up to the CEO.
+    pub fn chain_of_command(&self, id: u32) -> Option<Vec<u32>> {
+        let mut i = self.index_of(id).ok()?;
+        let mut chain = vec![self.employees[i].id];
+        while i != self.root {
+            i = self.employees[i].manager;
+            chain.push(self.employees[i].id);
+        }
+        Some(chain)
+    }
+
+    //@ The lowest manager that both `a` and `b` are under, where everyone is under themselves,
+    //@ so if `a` manages `b`, it's `a`.
+    pub fn lowest_common_manager(&self, a: u32, b: u32) -> Option<u32> {
+        let (a, b) = (self.index_of(a).ok()?, self.index_of(b).ok()?);
+        Some(self.employees[self.lca(a, b)].id)
+    }
+
+    fn lca(&self, mut a: usize, mut b: usize) -> usize {
+        if self.depth[a] < self.depth[b] {
+            (a, b) = (b, a);
+        }
+        let diff = self.depth[a] - self.depth[b];
+        for (k, up) in self.up.iter().enumerate() {
+            if diff >> k & 1 == 1 {
+                a = up[a];
+            }
+        }
+        if a == b {
+            return a;
+        }
+        for up in self.up.iter().rev() {
+            if up[a] != up[b] {
+                (a, b) = (up[a], up[b]);
+            }
+        }
+        self.up[0][a]
+    }
+
+    //@ Moves `id` and everyone under them to report to `manager`. Moving someone under
+    //@ themselves would disconnect them from the CEO, so the new manager can't be under them.
+    pub fn reparent(&mut self, id: u32, manager: u32) -> Result<(), OrgChartError> {
+        let (i, m) = (self.index_of(id)?, self.index_of(manager)?);
+        if i == self.root {
+            return Err(OrgChartError::MovingRoot(id));
+        }
+        if self.lca(i, m) == i {
+            return Err(OrgChartError::ManagerInSubtree {
+                employee: id,
+                manager,
+            });
+        }
+        let old = self.employees[i].manager;
+        if old == m {
+            return Ok(());
+        }
+        self.employees[old].reports.retain(|&report| report != i);
+        self.employees[m].reports.push(i);
+        self.employees[i].manager = m;
+        self.index();
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -59,6 +344,20 @@ mod tests {
     use insta::assert_yaml_snapshot;
     use quickcheck_macros::quickcheck;
 
+    fn chart() -> Vec<(u32, u32, String)> {
+        [
+            (6, 5, "acolyte1"),
+            (7, 4, "acolyte2"),
+            (2, 1, "sylvanas"),
+            (3, 1, "anubarak"),
+            (4, 2, "commander1"),
+            (1, 1, "arthas"),
+            (5, 3, "commander2"),
+        ]
+        .map(|(employee, manager, name)| (employee, manager, name.to_string()))
+        .to_vec()
+    }
+
     #[quickcheck]
     fn verify(chart: Vec<(u32, u32, String)>) -> bool {
         if chart.len() > 100 {
@@ -83,4 +382,136 @@ mod tests {
         let result = rooted_topological_sort(&chart);
         assert_yaml_snapshot!(result);
     }
+
+    #[test]
+    fn ex1() {
+        let mut chart = OrgChart::new(&chart()).unwrap();
+        assert_eq!(chart.headcount(), 7);
+        assert_eq!(chart.ceo(), 1);
+        assert_eq!(chart.name(4), Some("commander1"));
+        assert_eq!(chart.manager(4), Some(2));
+        assert_eq!(chart.manager(1), None);
+        assert_eq!(chart.subtree_size(1), Some(7));
+        assert_eq!(chart.subtree_size(2), Some(3));
+        assert_eq!(chart.chain_of_command(7), Some(vec![7, 4, 2, 1]));
+        assert_eq!(chart.lowest_common_manager(7, 6), Some(1));
+        assert_eq!(chart.lowest_common_manager(7, 4), Some(4));
+        assert_eq!(chart.lowest_common_manager(9, 4), None);
+
+        //@ Moving commander1 under commander2 brings acolyte2 along.
+        chart.reparent(4, 5).unwrap();
+        assert_eq!(chart.subtree_size(3), Some(5));
+        assert_eq!(chart.subtree_size(2), Some(1));
+        assert_eq!(chart.chain_of_command(7), Some(vec![7, 4, 5, 3, 1]));
+        assert_eq!(chart.lowest_common_manager(7, 6), Some(5));
+
+        assert_eq!(
+            chart.reparent(3, 7),
+            Err(OrgChartError::ManagerInSubtree {
+                employee: 3,
+                manager: 7
+            })
+        );
+        assert_eq!(chart.reparent(1, 2), Err(OrgChartError::MovingRoot(1)));
+        assert_eq!(
+            OrgChartError::MovingRoot(1).to_string(),
+            "employee 1 is the CEO and can't be moved"
+        );
+        assert_eq!(chart.reparent(9, 1), Err(OrgChartError::UnknownEmployee(9)));
+    }
+
+    #[test]
+    fn errors() {
+        let build = |chart: &[(u32, u32)]| {
+            let chart: Vec<(u32, u32, String)> =
+                chart.iter().map(|&(e, m)| (e, m, e.to_string())).collect();
+            OrgChart::new(&chart).err()
+        };
+        assert_eq!(build(&[]), Some(OrgChartError::NoRoot));
+        assert_eq!(
+            build(&[(1, 1), (2, 2)]),
+            Some(OrgChartError::MultipleRoots(1, 2))
+        );
+        assert_eq!(
+            build(&[(1, 1), (1, 1)]),
+            Some(OrgChartError::DuplicateEmployee(1))
+        );
+        assert_eq!(
+            build(&[(1, 1), (2, 3)]),
+            Some(OrgChartError::MissingManager {
+                employee: 2,
+                manager: 3
+            })
+        );
+
+        //@ 3 manages 2, who manages 4, who manages 3.
+        let Some(OrgChartError::Cycle(mut cycle)) = build(&[(1, 1), (2, 3), (3, 4), (4, 2)]) else {
+            panic!("expected a cycle");
+        };
+        let first = cycle.iter().position(|&id| id == 3).unwrap();
+        cycle.rotate_left(first);
+        assert_eq!(cycle, [3, 2, 4]);
+    }
+
+    //@ A cycle under the CEO used to send the traversal around it forever.
+    #[test]
+    fn cycle_has_no_order() {
+        let mut chart = chart();
+        chart[2].1 = 7;
+        assert_eq!(rooted_topological_sort(&chart), None);
+    }
+
+    //@ Random trees, where employee `i` reports to someone before them, and random moves, all
+    //@ checked against walking up the managers one at a time.
+    #[quickcheck]
+    fn matches_naive(managers: Vec<u8>, moves: Vec<(u8, u8)>) -> bool {
+        let n = managers.len().min(20) + 1;
+        let mut manager: Vec<usize> = (0..n).map(|i| i.saturating_sub(1)).collect();
+        for (i, &m) in managers.iter().take(n - 1).enumerate() {
+            manager[i + 1] = usize::from(m) % (i + 1);
+        }
+        let id = |i: usize| i as u32 * 10;
+        let rows: Vec<(u32, u32, String)> = (0..n)
+            .map(|i| (id(i), id(manager[i]), i.to_string()))
+            .collect();
+        let mut chart = OrgChart::new(&rows).unwrap();
+
+        let chain = |manager: &[usize], mut i: usize| {
+            let mut chain = vec![i];
+            while i != 0 {
+                i = manager[i];
+                chain.push(i);
+            }
+            chain
+        };
+        let check = |chart: &OrgChart, manager: &[usize]| {
+            let chains: Vec<Vec<usize>> = (0..n).map(|i| chain(manager, i)).collect();
+            (0..n).all(|a| {
+                chart.chain_of_command(id(a)) == Some(chains[a].iter().map(|&i| id(i)).collect())
+                    && chart.subtree_size(id(a))
+                        == Some(chains.iter().filter(|c| c.contains(&a)).count())
+                    && (0..n).all(|b| {
+                        let lowest = *chains[a].iter().find(|i| chains[b].contains(i)).unwrap();
+                        chart.lowest_common_manager(id(a), id(b)) == Some(id(lowest))
+                    })
+            })
+        };
+
+        check(&chart, &manager)
+            && moves.into_iter().all(|(a, b)| {
+                let (a, b) = (usize::from(a) % n, usize::from(b) % n);
+                let expected = if a == 0 {
+                    Err(OrgChartError::MovingRoot(id(a)))
+                } else if chain(&manager, b).contains(&a) {
+                    Err(OrgChartError::ManagerInSubtree {
+                        employee: id(a),
+                        manager: id(b),
+                    })
+                } else {
+                    manager[a] = b;
+                    Ok(())
+                };
+                chart.reparent(id(a), id(b)) == expected && check(&chart, &manager)
+            })
+    }
 }
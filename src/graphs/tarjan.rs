@@ -0,0 +1,147 @@
+use super::graph::{Adjacency, NodeId};
+
+//@ [Tarjan's algorithm](https://en.wikipedia.org/wiki/Tarjan%27s_strongly_connected_components_algorithm)
+//@ finds the same strongly connected components as [Kosaraju's](./kosaraju.html), with a single
+//@ depth-first search instead of two, and without reversing the graph.
+//@
+//@ Every node gets an index in the order the search reaches it, and a low link, which is the
+//@ smallest index it can reach through its descendants and at most one edge back to a node that
+//@ is still on the SCC stack. Nodes go on the SCC stack when they're reached. When a node is
+//@ finished and its low link is its own index, nothing below it reaches further up, so it's the
+//@ root of an SCC, which is everything above it on the SCC stack.
+//@
+//@ SCCs are found sinks first, since an SCC is only done once everything it reaches is done, so
+//@ they're reversed at the end to come out in topological order like Kosaraju's. The root of
+//@ every SCC comes last in it, since it went on the stack first.
+//@
+//@ The search is iterative like Kosaraju's, with the iterator over the rest of a node's
+//@ neighbors on the call stack, so it handles graphs with millions of nodes.
+pub fn tarjan<G: Adjacency>(graph: &G) -> Vec<Vec<NodeId>> {
+    const UNVISITED: usize = usize::MAX;
+    let n = graph.node_count();
+    let mut index = vec![UNVISITED; n];
+    let mut low = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = vec![];
+    let mut components = vec![];
+    let mut next_index = 0;
+
+    for start in graph.node_ids() {
+        if index[start] != UNVISITED {
+            continue;
+        }
+        let mut calls = vec![(start, graph.neighbors(start))];
+        index[start] = next_index;
+        low[start] = next_index;
+        next_index += 1;
+        stack.push(start);
+        on_stack[start] = true;
+
+        while let Some((node, neighbors)) = calls.last_mut() {
+            let node = *node;
+            //@ An unvisited neighbor is a call, and a neighbor on the stack is an edge back.
+            //@ Neighbors that are visited but not on the stack are in an SCC that's done.
+            if let Some(next) = neighbors.next() {
+                if index[next] == UNVISITED {
+                    index[next] = next_index;
+                    low[next] = next_index;
+                    next_index += 1;
+                    stack.push(next);
+                    on_stack[next] = true;
+                    calls.push((next, graph.neighbors(next)));
+                } else if on_stack[next] {
+                    low[node] = low[node].min(index[next]);
+                }
+                continue;
+            }
+
+            //@ Returning from the call passes the low link up to the parent.
+            calls.pop();
+            if let Some(&(parent, _)) = calls.last() {
+                low[parent] = low[parent].min(low[node]);
+            }
+            if low[node] == index[node] {
+                let mut component = vec![];
+                loop {
+                    let member = stack.pop().expect("the root is on the stack");
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+
+    components.reverse();
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+    use crate::graphs::graph::Graph;
+    use crate::graphs::kosaraju::strongly_connected_components;
+
+    #[test]
+    fn ex1() {
+        let edges = [
+            (0, 1),
+            (1, 2),
+            (2, 0),
+            (2, 3),
+            (3, 4),
+            (4, 5),
+            (5, 3),
+            (6, 5),
+        ];
+        let (graph, _) = Graph::from_edges(true, edges.map(|(a, b)| (a, b, ())));
+        let components: Vec<Vec<u32>> = tarjan(&graph)
+            .into_iter()
+            .map(|c| {
+                let mut c: Vec<u32> = c.into_iter().map(|id| *graph.node(id)).collect();
+                c.sort();
+                c
+            })
+            .collect();
+        assert_eq!(components, [vec![6], vec![0, 1, 2], vec![3, 4, 5]]);
+    }
+
+    //@ Every edge goes from an SCC to the same SCC or a later one.
+    fn is_topological<G: Adjacency>(graph: &G, components: &[Vec<NodeId>]) -> bool {
+        let mut component = vec![usize::MAX; graph.node_count()];
+        for (i, members) in components.iter().enumerate() {
+            for &node in members {
+                component[node] = i;
+            }
+        }
+        component.iter().all(|&c| c != usize::MAX)
+            && graph.node_ids().all(|node| {
+                graph
+                    .neighbors(node)
+                    .all(|next| component[node] <= component[next])
+            })
+    }
+
+    fn sorted(mut components: Vec<Vec<NodeId>>) -> Vec<Vec<NodeId>> {
+        components.iter_mut().for_each(|c| c.sort());
+        components.sort();
+        components
+    }
+
+    //@ Both algorithms find the same SCCs, in a topological order, although not necessarily the
+    //@ same one.
+    #[quickcheck]
+    fn matches_kosaraju(edges: Vec<(u8, u8)>) -> bool {
+        let (graph, _) =
+            Graph::from_edges(true, edges.into_iter().map(|(a, b)| (a % 32, b % 32, ())));
+        let (kosaraju, tarjan) = (strongly_connected_components(&graph), tarjan(&graph));
+        is_topological(&graph, &kosaraju)
+            && is_topological(&graph, &tarjan)
+            && sorted(kosaraju) == sorted(tarjan)
+    }
+}
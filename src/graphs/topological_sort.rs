@@ -0,0 +1,326 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use super::graph::{Adjacency, NodeId};
+
+//@ A [topological sort](https://en.wikipedia.org/wiki/Topological_sorting) orders the nodes of a
+//@ directed graph so that every edge goes forward, like build steps that have to run after the
+//@ steps they depend on. There's one exactly when the graph has no cycles, and when it does, the
+//@ most useful thing to report is one of them.
+//@
+//@ [Kahn's algorithm](https://en.wikipedia.org/wiki/Topological_sorting#Kahn's_algorithm) counts
+//@ the incoming edges of every node, and starts with the nodes that have none. Every node it
+//@ takes goes next in the order, and removes its outgoing edges, and any node that has no
+//@ incoming edges left is ready to go too. It takes O(V + E) time, and unlike a depth-first
+//@ search, it doesn't need a stack as deep as the longest path.
+//@
+//@ If it runs out of ready nodes before taking all of them, the rest are all waiting on another
+//@ node that's left, so following those edges backwards from any of them has to come back
+//@ around, which finds a cycle.
+pub fn topological_sort<G: Adjacency>(graph: &G) -> Result<Vec<NodeId>, CycleError> {
+    let mut indegrees = indegrees(graph);
+    let mut ready: VecDeque<NodeId> = graph.node_ids().filter(|&n| indegrees[n] == 0).collect();
+    let mut order = Vec::with_capacity(graph.node_count());
+    while let Some(node) = ready.pop_front() {
+        order.push(node);
+        for next in graph.neighbors(node) {
+            indegrees[next] -= 1;
+            if indegrees[next] == 0 {
+                ready.push_back(next);
+            }
+        }
+    }
+    check(graph, &indegrees)?;
+    Ok(order)
+}
+
+//@ The topological order that comes first lexicographically, which is the same every time no
+//@ matter what order the edges were added in. Kahn's algorithm always takes the smallest node
+//@ that's ready, with a min-heap, so it takes O(V log(V) + E) time.
+pub fn lexicographic_topological_sort<G: Adjacency>(graph: &G) -> Result<Vec<NodeId>, CycleError> {
+    lexicographic_topological_sort_by_key(graph, |node| node)
+}
+
+//@ Like `lexicographic_topological_sort`, but compares the nodes by `key`, like the name of a
+//@ build step, with ties broken by node id.
+pub fn lexicographic_topological_sort_by_key<G: Adjacency, K: Ord>(
+    graph: &G,
+    mut key: impl FnMut(NodeId) -> K,
+) -> Result<Vec<NodeId>, CycleError> {
+    let mut indegrees = indegrees(graph);
+    let mut ready: BinaryHeap<Reverse<(K, NodeId)>> = graph
+        .node_ids()
+        .filter(|&n| indegrees[n] == 0)
+        .map(|n| Reverse((key(n), n)))
+        .collect();
+    let mut order = Vec::with_capacity(graph.node_count());
+    while let Some(Reverse((_, node))) = ready.pop() {
+        order.push(node);
+        for next in graph.neighbors(node) {
+            indegrees[next] -= 1;
+            if indegrees[next] == 0 {
+                ready.push(Reverse((key(next), next)));
+            }
+        }
+    }
+    check(graph, &indegrees)?;
+    Ok(order)
+}
+
+//@ Splits the nodes into layers, where every node only depends on nodes in earlier layers, so
+//@ all of the steps in a layer can run in parallel once the layers before it are done. Kahn's
+//@ algorithm takes all the ready nodes at once, and the nodes they make ready are the next
+//@ layer. A node ends up in the layer of the longest path to it, so there are as few layers as
+//@ possible. The nodes of every layer are sorted by id.
+pub fn topological_layers<G: Adjacency>(graph: &G) -> Result<Vec<Vec<NodeId>>, CycleError> {
+    let mut indegrees = indegrees(graph);
+    let mut layer: Vec<NodeId> = graph.node_ids().filter(|&n| indegrees[n] == 0).collect();
+    let mut layers = vec![];
+    while !layer.is_empty() {
+        let mut next_layer = vec![];
+        for &node in &layer {
+            for next in graph.neighbors(node) {
+                indegrees[next] -= 1;
+                if indegrees[next] == 0 {
+                    next_layer.push(next);
+                }
+            }
+        }
+        next_layer.sort_unstable();
+        layers.push(layer);
+        layer = next_layer;
+    }
+    check(graph, &indegrees)?;
+    Ok(layers)
+}
+
+fn indegrees<G: Adjacency>(graph: &G) -> Vec<usize> {
+    let mut indegrees = vec![0; graph.node_count()];
+    for node in graph.node_ids() {
+        for next in graph.neighbors(node) {
+            indegrees[next] += 1;
+        }
+    }
+    indegrees
+}
+
+//@ Every node that Kahn's algorithm didn't take still has incoming edges left, from other nodes
+//@ it didn't take. Picking one of those edges for each of them, and following them backwards
+//@ from any node that's left, has to repeat a node eventually, and the nodes from its first
+//@ visit to the second are a cycle.
+fn check<G: Adjacency>(graph: &G, indegrees: &[usize]) -> Result<(), CycleError> {
+    let Some(start) = graph.node_ids().find(|&n| indegrees[n] > 0) else {
+        return Ok(());
+    };
+    let mut prev = vec![usize::MAX; graph.node_count()];
+    for node in graph.node_ids().filter(|&n| indegrees[n] > 0) {
+        for next in graph.neighbors(node) {
+            prev[next] = node;
+        }
+    }
+
+    let mut seen = vec![false; graph.node_count()];
+    let mut node = start;
+    while !seen[node] {
+        seen[node] = true;
+        node = prev[node];
+    }
+    let mut cycle = vec![node];
+    let mut back = prev[node];
+    while back != node {
+        cycle.push(back);
+        back = prev[back];
+    }
+    cycle.reverse();
+    Err(CycleError { cycle })
+}
+
+//@ The graph has a cycle, so there's no topological order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleError {
+    cycle: Vec<NodeId>,
+}
+
+impl CycleError {
+    //@ The nodes of one cycle in the graph, in the order of its edges, where the last node has an
+    //@ edge back to the first. A node with an edge to itself is a cycle on its own.
+    pub fn cycle(&self) -> &[NodeId] {
+        &self.cycle
+    }
+}
+
+impl Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "found a cycle: ")?;
+        for node in &self.cycle {
+            write!(f, "{node} -> ")?;
+        }
+        write!(f, "{}", self.cycle[0])
+    }
+}
+
+impl Error for CycleError {}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+    use crate::graphs::graph::Graph;
+    use crate::graphs::kosaraju::strongly_connected_components;
+
+    //@ Build steps, with an edge from every step to the steps that need it.
+    fn build() -> Graph<&'static str> {
+        let steps = [
+            ("fetch", "compile"),
+            ("codegen", "compile"),
+            ("fetch", "codegen"),
+            ("compile", "link"),
+            ("compile", "test"),
+            ("link", "package"),
+            ("docs", "package"),
+        ];
+        Graph::from_edges(true, steps.map(|(a, b)| (a, b, ()))).0
+    }
+
+    fn names(graph: &Graph<&'static str>, ids: Vec<NodeId>) -> Vec<&'static str> {
+        ids.into_iter().map(|id| *graph.node(id)).collect()
+    }
+
+    #[test]
+    fn ex1() {
+        let graph = build();
+        let order = lexicographic_topological_sort_by_key(&graph, |id| *graph.node(id)).unwrap();
+        assert_eq!(
+            names(&graph, order),
+            ["docs", "fetch", "codegen", "compile", "link", "package", "test"]
+        );
+        let layers: Vec<Vec<&str>> = topological_layers(&graph)
+            .unwrap()
+            .into_iter()
+            .map(|layer| {
+                let mut layer = names(&graph, layer);
+                layer.sort();
+                layer
+            })
+            .collect();
+        assert_eq!(
+            layers,
+            [
+                vec!["docs", "fetch"],
+                vec!["codegen"],
+                vec!["compile"],
+                vec!["link", "test"],
+                vec!["package"]
+            ]
+        );
+    }
+
+    //@ Making the code generator depend on linking makes a cycle through three steps.
+    #[test]
+    fn cycle() {
+        let mut graph = build();
+        let id = |name| graph.nodes().find(|&(_, &n)| n == name).unwrap().0;
+        let (link, codegen) = (id("link"), id("codegen"));
+        graph.add_edge(link, codegen, ());
+        let err = topological_sort(&graph).unwrap_err();
+        let mut cycle = names(&graph, err.cycle().to_vec());
+        let first = cycle.iter().position(|&s| s == "codegen").unwrap();
+        cycle.rotate_left(first);
+        assert_eq!(cycle, ["codegen", "compile", "link"]);
+        assert_eq!(err, topological_layers(&graph).unwrap_err());
+        //@ The ids of `link`, `codegen` and `compile`.
+        assert_eq!(err.to_string(), "found a cycle: 3 -> 2 -> 1 -> 3");
+    }
+
+    #[test]
+    fn self_loop() {
+        let (graph, _) = Graph::from_edges(true, [(0, 1, ()), (1, 1, ())]);
+        assert_eq!(topological_sort(&graph).unwrap_err().cycle(), [1]);
+    }
+
+    fn is_order<G: Adjacency>(graph: &G, order: &[NodeId]) -> bool {
+        let mut position = vec![usize::MAX; graph.node_count()];
+        for (i, &node) in order.iter().enumerate() {
+            position[node] = i;
+        }
+        order.len() == graph.node_count()
+            && graph.node_ids().all(|node| {
+                graph
+                    .neighbors(node)
+                    .all(|next| position[node] < position[next])
+            })
+    }
+
+    fn is_cycle<G: Adjacency>(graph: &G, cycle: &[NodeId]) -> bool {
+        let mut distinct = cycle.to_vec();
+        distinct.sort();
+        distinct.dedup();
+        !cycle.is_empty()
+            && distinct.len() == cycle.len()
+            && (0..cycle.len()).all(|i| {
+                graph
+                    .neighbors(cycle[i])
+                    .any(|next| next == cycle[(i + 1) % cycle.len()])
+            })
+    }
+
+    //@ Picks the smallest ready node every time, by scanning all of them.
+    fn naive_lexicographic<G: Adjacency>(graph: &G) -> Vec<NodeId> {
+        let mut indegrees = indegrees(graph);
+        let mut taken = vec![false; graph.node_count()];
+        let mut order = vec![];
+        while let Some(node) = graph.node_ids().find(|&n| !taken[n] && indegrees[n] == 0) {
+            taken[node] = true;
+            order.push(node);
+            for next in graph.neighbors(node) {
+                indegrees[next] -= 1;
+            }
+        }
+        order
+    }
+
+    //@ Every variant finds an order exactly when Kosaraju's algorithm finds no SCC with an edge
+    //@ inside it, and otherwise they all report the same real cycle.
+    #[quickcheck]
+    fn orders_or_cycles(edges: Vec<(u8, u8)>, acyclic: bool) -> bool {
+        let edges = edges
+            .into_iter()
+            .map(|(a, b)| (a % 24, b % 24))
+            .filter(|(a, b)| !acyclic || a < b);
+        let (graph, _) = Graph::from_edges(true, edges.map(|(a, b)| (a, b, ())));
+        let has_cycle = strongly_connected_components(&graph)
+            .iter()
+            .any(|c| c.len() > 1 || graph.neighbors(c[0]).any(|next| next == c[0]));
+        match (
+            topological_sort(&graph),
+            lexicographic_topological_sort(&graph),
+            topological_layers(&graph),
+        ) {
+            (Ok(order), Ok(smallest), Ok(layers)) => {
+                let flat: Vec<NodeId> = layers.iter().flatten().copied().collect();
+                let mut layer = vec![0; graph.node_count()];
+                for (i, nodes) in layers.iter().enumerate() {
+                    nodes.iter().for_each(|&n| layer[n] = i);
+                }
+                !has_cycle
+                    && is_order(&graph, &order)
+                    && smallest == naive_lexicographic(&graph)
+                    && is_order(&graph, &flat)
+                    && layers.iter().all(|l| l.windows(2).all(|w| w[0] < w[1]))
+                    && graph.node_ids().all(|n| {
+                        layer[n] == 0
+                            || graph.node_ids().any(|p| {
+                                layer[p] + 1 == layer[n] && graph.neighbors(p).any(|m| m == n)
+                            })
+                    })
+            }
+            (Err(a), Err(b), Err(c)) => {
+                has_cycle && is_cycle(&graph, a.cycle()) && a == b && b == c
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use std::ops::Not;
+
+use super::graph::{Graph, NodeId};
+use super::kosaraju::strongly_connected_components;
+
+//@ [2-SAT](https://en.wikipedia.org/wiki/2-satisfiability) asks whether there's an assignment of
+//@ boolean variables that makes a list of clauses true, where every clause is the `or` of two
+//@ literals, and a literal is a variable or its negation. Lots of constraints fit in that form:
+//@ - "`a` needs `b`" is `!a || b`.
+//@ - "`a` conflicts with `b`" is `!a || !b`.
+//@ - "`a` must be on" is `a || a`.
+//@
+//@ With three literals per clause it's NP-complete, but with two it's solvable in linear time
+//@ with [strongly connected components](./kosaraju.html).
+//@
+//@ A clause `a || b` is the same as the two implications `!a => b` and `!b => a`, so the
+//@ clauses make an implication graph with a node per literal. Every literal in an SCC of that
+//@ graph implies every other one, so they all have the same value. If a variable and its
+//@ negation are in the same SCC, it has to be both true and false, and there's no solution.
+//@
+//@ Otherwise, there always is one, and the order of the SCCs finds it. Going through the SCCs
+//@ in reverse topological order, from the ones that imply nothing else, and making every literal
+//@ in them true, never makes a literal true that implies a false one. So a variable is true if
+//@ its SCC comes after its negation's in topological order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Literal {
+    var: usize,
+    value: bool,
+}
+
+impl Literal {
+    //@ The literal that's true when `var` is.
+    pub fn pos(var: usize) -> Self {
+        Literal { var, value: true }
+    }
+
+    //@ The literal that's true when `var` isn't.
+    pub fn neg(var: usize) -> Self {
+        Literal { var, value: false }
+    }
+
+    pub fn var(&self) -> usize {
+        self.var
+    }
+
+    //@ Whether the literal is true under `assignment`.
+    pub fn eval(&self, assignment: &[bool]) -> bool {
+        assignment[self.var] == self.value
+    }
+
+    //@ The node of the literal in the implication graph, with the two literals of every variable
+    //@ next to each other.
+    fn node(&self) -> NodeId {
+        2 * self.var + usize::from(!self.value)
+    }
+}
+
+impl Not for Literal {
+    type Output = Literal;
+
+    fn not(self) -> Literal {
+        Literal {
+            var: self.var,
+            value: !self.value,
+        }
+    }
+}
+
+pub struct TwoSat {
+    vars: usize,
+    implications: Graph<Literal>,
+}
+
+impl TwoSat {
+    //@ A problem with the variables `0..vars`, and no clauses yet.
+    pub fn new(vars: usize) -> Self {
+        let mut implications = Graph::directed();
+        for var in 0..vars {
+            implications.add_node(Literal::pos(var));
+            implications.add_node(Literal::neg(var));
+        }
+        TwoSat { vars, implications }
+    }
+
+    pub fn vars(&self) -> usize {
+        self.vars
+    }
+
+    //@ Requires `a || b`.
+    pub fn add_clause(&mut self, a: Literal, b: Literal) {
+        for var in [a.var, b.var] {
+            assert!(
+                var < self.vars,
+                "variable {var} out of bounds for {} variables",
+                self.vars
+            );
+        }
+        self.implications.add_edge((!a).node(), b.node(), ());
+        self.implications.add_edge((!b).node(), a.node(), ());
+    }
+
+    //@ Requires `b` whenever `a`.
+    pub fn implies(&mut self, a: Literal, b: Literal) {
+        self.add_clause(!a, b);
+    }
+
+    //@ Requires that `a` and `b` aren't both true.
+    pub fn conflicts(&mut self, a: Literal, b: Literal) {
+        self.add_clause(!a, !b);
+    }
+
+    //@ Requires `a`.
+    pub fn require(&mut self, a: Literal) {
+        self.add_clause(a, a);
+    }
+
+    //@ Returns the value of every variable in an assignment that satisfies all the clauses, or
+    //@ `None` if there isn't one.
+    pub fn solve(&self) -> Option<Vec<bool>> {
+        //@ Kosaraju's algorithm returns the SCCs in topological order, so the index of the SCC
+        //@ of every literal is all that's needed.
+        let components = strongly_connected_components(&self.implications);
+        let mut component = vec![0; self.implications.node_count()];
+        for (id, members) in components.iter().enumerate() {
+            for &node in members {
+                component[node] = id;
+            }
+        }
+        (0..self.vars)
+            .map(|var| {
+                let (pos, neg) = (
+                    component[Literal::pos(var).node()],
+                    component[Literal::neg(var).node()],
+                );
+                (pos != neg).then_some(pos > neg)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    //@ Checking a configuration with 4 features:
+    //@ - TLS needs the crypto backend.
+    //@ - The crypto backend conflicts with the minimal build.
+    //@ - Either HTTP/2 or the minimal build is on.
+    //@ - TLS is on.
+    #[test]
+    fn ex1() {
+        const TLS: usize = 0;
+        const CRYPTO: usize = 1;
+        const MINIMAL: usize = 2;
+        const HTTP2: usize = 3;
+        let mut problem = TwoSat::new(4);
+        problem.implies(Literal::pos(TLS), Literal::pos(CRYPTO));
+        problem.conflicts(Literal::pos(CRYPTO), Literal::pos(MINIMAL));
+        problem.add_clause(Literal::pos(HTTP2), Literal::pos(MINIMAL));
+        problem.require(Literal::pos(TLS));
+        assert_eq!(problem.solve(), Some(vec![true, true, false, true]));
+
+        //@ Also requiring HTTP/2 to be off makes it impossible.
+        problem.require(Literal::neg(HTTP2));
+        assert_eq!(problem.solve(), None);
+    }
+
+    #[test]
+    fn unsatisfiable() {
+        //@ `x` implies `!x`, and `!x` implies `x`.
+        let mut problem = TwoSat::new(1);
+        problem.add_clause(Literal::pos(0), Literal::pos(0));
+        problem.add_clause(Literal::neg(0), Literal::neg(0));
+        assert_eq!(problem.solve(), None);
+    }
+
+    #[test]
+    fn no_clauses() {
+        assert_eq!(TwoSat::new(0).solve(), Some(vec![]));
+        assert_eq!(TwoSat::new(3).solve().map(|a| a.len()), Some(3));
+    }
+
+    fn satisfies(clauses: &[(Literal, Literal)], assignment: &[bool]) -> bool {
+        clauses
+            .iter()
+            .all(|(a, b)| a.eval(assignment) || b.eval(assignment))
+    }
+
+    //@ With up to 8 variables, trying every assignment shows whether there's a solution, and the
+    //@ one the solver finds has to satisfy every clause.
+    #[quickcheck]
+    fn matches_brute_force(vars: u8, clauses: Vec<((u8, bool), (u8, bool))>) -> bool {
+        let vars = usize::from(vars % 8) + 1;
+        let literal = |(var, value): (u8, bool)| Literal {
+            var: usize::from(var) % vars,
+            value,
+        };
+        let clauses: Vec<(Literal, Literal)> = clauses
+            .into_iter()
+            .map(|(a, b)| (literal(a), literal(b)))
+            .collect();
+        let mut problem = TwoSat::new(vars);
+        for &(a, b) in &clauses {
+            problem.add_clause(a, b);
+        }
+        let exists = (0..1 << vars).any(|bits: u32| {
+            let assignment: Vec<bool> = (0..vars).map(|i| bits >> i & 1 == 1).collect();
+            satisfies(&clauses, &assignment)
+        });
+        match problem.solve() {
+            Some(assignment) => assignment.len() == vars && satisfies(&clauses, &assignment),
+            None => !exists,
+        }
+    }
+}
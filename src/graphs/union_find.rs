@@ -0,0 +1,149 @@
+//@ A [union-find](https://en.wikipedia.org/wiki/Disjoint-set_data_structure), or disjoint set
+//@ forest, keeps track of a partition of the elements `0..n` into sets, and answers whether two
+//@ elements are in the same set, while merging sets together. It's what [Kruskal's
+//@ algorithm](./kruskal.html) uses to tell whether an edge would close a cycle.
+//@
+//@ Every set is a tree, where each element points to its parent, and the root represents the
+//@ set. `find` follows the parents up to the root, and `union` points one root at the other.
+//@ Two tricks keep the trees flat:
+//@ - Union by rank: the root of the shorter tree goes under the root of the taller one, so
+//@   trees only get taller when two of the same height are merged, and a tree of height `h` has
+//@   at least `2^h` elements. The rank is an upper bound on the height, which never needs to be
+//@   updated when paths get shorter.
+//@ - Path compression: after `find` reaches the root, everything on the way points straight at
+//@   it.
+//@
+//@ Together, they make every operation take O(α(n)) amortized time, where α is the inverse of
+//@ the Ackermann function, which is less than 5 for any `n` that fits in memory.
+pub struct UnionFind {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+    //@ The size of every set, which is only kept up to date at its root.
+    size: Vec<usize>,
+    sets: usize,
+}
+
+impl UnionFind {
+    //@ Every element starts in a set of its own.
+    pub fn new(n: usize) -> Self {
+        UnionFind {
+            parent: (0..n).collect(),
+            rank: vec![0; n],
+            size: vec![1; n],
+            sets: n,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    //@ The number of sets.
+    pub fn set_count(&self) -> usize {
+        self.sets
+    }
+
+    //@ The root of the set `x` is in. The path is compressed in a second pass, instead of with
+    //@ recursion, so a long chain can't overflow the stack.
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut node = x;
+        while node != root {
+            node = std::mem::replace(&mut self.parent[node], root);
+        }
+        root
+    }
+
+    //@ Merges the sets of `a` and `b`, and returns whether they were different sets.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        if self.rank[a] < self.rank[b] {
+            (a, b) = (b, a);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        if self.rank[a] == self.rank[b] {
+            self.rank[a] += 1;
+        }
+        self.sets -= 1;
+        true
+    }
+
+    pub fn same_set(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    //@ The number of elements in the set `x` is in.
+    pub fn size(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.size[root]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    #[test]
+    fn ex1() {
+        let mut sets = UnionFind::new(6);
+        assert!(sets.union(0, 1));
+        assert!(sets.union(2, 3));
+        assert!(sets.union(1, 3));
+        assert!(!sets.union(0, 2));
+        assert!(sets.same_set(0, 3));
+        assert!(!sets.same_set(0, 4));
+        assert_eq!(sets.size(2), 4);
+        assert_eq!(sets.size(5), 1);
+        assert_eq!(sets.set_count(), 3);
+        assert_eq!(sets.len(), 6);
+    }
+
+    //@ Merging a million elements into one chain, one at a time.
+    #[test]
+    fn long_chain() {
+        let n = 1_000_000;
+        let mut sets = UnionFind::new(n);
+        for i in 1..n {
+            sets.union(i - 1, i);
+        }
+        assert_eq!(sets.size(0), n);
+        assert_eq!(sets.set_count(), 1);
+    }
+
+    //@ Compared with labeling every element with its set, and relabeling a whole set on every
+    //@ union.
+    #[quickcheck]
+    fn matches_naive(unions: Vec<(u8, u8)>) -> bool {
+        let n = 32;
+        let mut sets = UnionFind::new(n);
+        let mut label: Vec<usize> = (0..n).collect();
+        unions.into_iter().all(|(a, b)| {
+            let (a, b) = (usize::from(a) % n, usize::from(b) % n);
+            let (la, lb) = (label[a], label[b]);
+            label.iter_mut().filter(|l| **l == lb).for_each(|l| *l = la);
+            let mut distinct = label.clone();
+            distinct.sort();
+            distinct.dedup();
+            sets.union(a, b) == (la != lb)
+                && sets.set_count() == distinct.len()
+                && (0..n).all(|x| {
+                    sets.size(x) == label.iter().filter(|&&l| l == label[x]).count()
+                        && sets.same_set(a, x) == (label[a] == label[x])
+                })
+        })
+    }
+}
@@ -1,17 +1,45 @@
 //@ This library shows tested implementations of common data structures and algorithms
 //@ ## Primitive
 //@ - [Parity of Bits](./primitive/parity.html)
+//@ - [Rank and Select on Bit Vectors](./primitive/bitvec.html)
 //@ ## Arrays
 //@ - [Shuffling](./arrays/shuffle.html)
 //@ - [Sampling](./arrays/sample.html)
+//@ ## Lists
+//@ - [Reversing Lists](./lists/reverse.html)
+//@ - [Merging Sorted Lists](./lists/merge.html)
+//@ - [Sorting Lists](./lists/sort.html)
+//@ - [Cycle Detection](./lists/cycle.html)
 //@ ## Strings
 //@ - [Copy on Write](./strings/copy_on_write.html)
 //@ ## Graphs
+//@ - [Graphs](./graphs/graph.html)
 //@ - [Kosaraju's Algorithm](./graphs/kosaraju.html)
-//@ - [Topological Sort](./graphs/rooted_topological_sort.html)
+//@ - [Tarjan's Algorithm](./graphs/tarjan.html)
+//@ - [2-SAT](./graphs/two_sat.html)
+//@ - [Union-Find](./graphs/union_find.html)
+//@ - [Kruskal's Algorithm](./graphs/kruskal.html)
+//@ - [Topological Sort](./graphs/topological_sort.html)
+//@ - [Rooted Topological Sort and Org Charts](./graphs/rooted_topological_sort.html)
+//@ - [Cloning and Traversing Graphs](./graphs/graph_node.html)
 //@ ## Trees
+//@ - [Binary Tree Toolkit](./trees/binary_tree.html)
 //@ - [Fenwick Trees](./trees/fenwick.html)
 //@ - [BK Trees](./trees/bk_tree.html)
+//@ - [Tries](./trees/trie.html)
+//@ - [Radix Trees](./trees/radix_tree.html)
+//@ - [K-d Trees](./trees/kdtree.html)
+//@ - [Ball Trees](./trees/ball_tree.html)
+//@ - [B-Trees](./trees/btree.html)
+//@ - [B+-Trees](./trees/bplus_tree.html)
+//@ - [Treaps](./trees/treap.html)
+//@ - [Splay Trees](./trees/splay.html)
+//@ - [Interval Trees](./trees/interval_tree.html)
+//@ - [Wavelet Trees](./trees/wavelet_tree.html)
+//@ - [Sparse Tables](./trees/sparse_table.html)
+//@ - [Sqrt Decomposition](./trees/sqrt_decomposition.html)
+//@ - [Ropes](./trees/rope.html)
+//@ - [Edit History](./trees/edit_history.html)
 #![feature(test)]
 #![allow(unused)]
 
@@ -28,6 +56,7 @@ pub mod basic;
 pub mod distances;
 pub mod graphs;
 pub mod images;
+pub mod lists;
 pub mod primitive;
 pub mod sorts;
 pub mod strings;
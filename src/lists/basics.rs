@@ -0,0 +1,81 @@
+use crate::trees::tree_node::{LLNode, ListNode};
+
+//@ The building blocks the other list algorithms share. A list is an `Option<Box<ListNode>>`,
+//@ so most of them walk a `&mut LLNode` cursor down the list: it points at the link we may want
+//@ to cut or replace, which is either the head or some node's `next`.
+
+//@ Builds a list holding the values in order. Building it back to front means every node is
+//@ complete as soon as it's created.
+pub fn from_vec(vals: Vec<i32>) -> LLNode {
+    vals.into_iter()
+        .rev()
+        .fold(None, |next, val| Some(Box::new(ListNode { val, next })))
+}
+
+pub fn to_vec(head: &LLNode) -> Vec<i32> {
+    let mut out = vec![];
+    let mut cur = head;
+    while let Some(node) = cur {
+        out.push(node.val);
+        cur = &node.next;
+    }
+    out
+}
+
+pub fn len(head: &LLNode) -> usize {
+    let mut len = 0;
+    let mut cur = head;
+    while let Some(node) = cur {
+        len += 1;
+        cur = &node.next;
+    }
+    len
+}
+
+//@ Cuts the list after its first `n` nodes and returns the rest, like `Vec::split_off`.
+//@ If the list is shorter than that, the rest is empty.
+pub fn split_off(head: &mut LLNode, n: usize) -> LLNode {
+    let mut cur = head;
+    for _ in 0..n {
+        cur = &mut cur.as_mut()?.next;
+    }
+    cur.take()
+}
+
+//@ Returns the last link of the list, which is always `None`, so that something can be appended
+//@ there.
+pub fn tail(head: &mut LLNode) -> &mut LLNode {
+    let mut cur = head;
+    while let Some(node) = cur {
+        cur = &mut node.next;
+    }
+    cur
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+    use crate::slist;
+
+    #[test]
+    fn ex1() {
+        let list = slist![1, 2, 3];
+        assert_eq!(to_vec(&list), vec![1, 2, 3]);
+        assert_eq!(from_vec(vec![1, 2, 3]), list);
+        assert_eq!(len(&list), 3);
+        assert_eq!(format!("{:?}", list.unwrap()), "[1, 2, 3]");
+    }
+
+    #[quickcheck]
+    fn split_matches_vec(vals: Vec<i32>, n: usize) -> bool {
+        let n = n % (vals.len() + 2);
+        let mut head = from_vec(vals.clone());
+        let rest = split_off(&mut head, n);
+        *tail(&mut head) = from_vec(vec![7]);
+
+        let (l, r) = vals.split_at(n.min(vals.len()));
+        to_vec(&head) == [l, &[7]].concat() && to_vec(&rest) == r && len(&rest) == r.len()
+    }
+}
@@ -0,0 +1,133 @@
+//@ A list of `Box`ed nodes owns its successor, so it can never loop back on itself. Cycles show up
+//@ in lists whose links are indices into an arena, or in sequences like `x -> x * x + 1 mod m`,
+//@ so cycle detection here works on any implicit list: a start node and a function that returns
+//@ the next node, if any.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cycle<T> {
+    //@ The first node of the list that is on the cycle.
+    pub entry: T,
+    //@ The number of nodes on the cycle.
+    pub len: usize,
+    //@ The number of nodes before the entry.
+    pub tail_len: usize,
+}
+
+//@ [Floyd's algorithm](https://en.wikipedia.org/wiki/Cycle_detection#Floyd's_tortoise_and_hare)
+//@ finds a cycle in O(n) time and O(1) space:
+//@ 1. A tortoise takes one step at a time and a hare two. If the hare reaches the end there is no
+//@    cycle, otherwise they end up meeting somewhere on the cycle.
+//@ 2. At that point, the tortoise has taken a multiple of the cycle length in steps, so a pointer
+//@    starting from the head and one from the meeting point, both taking single steps, meet
+//@    exactly at the entry.
+//@ 3. Walking around the cycle once from the entry gives its length.
+pub fn find_cycle<T: Copy + Eq>(start: T, next: impl Fn(T) -> Option<T>) -> Option<Cycle<T>> {
+    let (mut tortoise, mut hare) = (start, start);
+    loop {
+        tortoise = next(tortoise)?;
+        hare = next(next(hare)?)?;
+        if tortoise == hare {
+            break;
+        }
+    }
+
+    let (mut a, mut b) = (start, hare);
+    let mut tail_len = 0;
+    while a != b {
+        a = next(a)?;
+        b = next(b)?;
+        tail_len += 1;
+    }
+
+    let entry = a;
+    let mut len = 1;
+    let mut cur = next(entry)?;
+    while cur != entry {
+        cur = next(cur)?;
+        len += 1;
+    }
+    Some(Cycle {
+        entry,
+        len,
+        tail_len,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    //@ A list in an arena: node `i` links to `links[i]`.
+    fn arena(links: &[Option<usize>]) -> impl Fn(usize) -> Option<usize> + '_ {
+        |i| links[i]
+    }
+
+    #[test]
+    fn ex1() {
+        //@ 3 -> 2 -> 0 -> 4 -> 2, from LeetCode 142.
+        let links = [Some(4), None, Some(0), Some(2), Some(2)];
+        assert_eq!(
+            find_cycle(3, arena(&links)),
+            Some(Cycle {
+                entry: 2,
+                len: 3,
+                tail_len: 1
+            })
+        );
+        assert_eq!(find_cycle(1, arena(&links)), None);
+
+        //@ A single node pointing at itself.
+        assert_eq!(
+            find_cycle(0, |_| Some(0)),
+            Some(Cycle {
+                entry: 0,
+                len: 1,
+                tail_len: 0
+            })
+        );
+    }
+
+    //@ Pollard's rho sequence `x -> x^2 + 1 mod 1000` must cycle, since it only has 1,000 values.
+    #[test]
+    fn rho() {
+        let cycle = find_cycle(2u64, |x| Some((x * x + 1) % 1000)).unwrap();
+        let seq: Vec<u64> = std::iter::successors(Some(2u64), |x| Some((x * x + 1) % 1000))
+            .take(cycle.tail_len + cycle.len + 1)
+            .collect();
+        assert_eq!(seq[cycle.tail_len], cycle.entry);
+        assert_eq!(seq[cycle.tail_len + cycle.len], cycle.entry);
+        assert!(!seq[..cycle.tail_len].contains(&cycle.entry));
+    }
+
+    //@ The reference remembers the step at which it saw each node, so the first repeated node is
+    //@ the entry, and the difference between its two steps is the cycle length.
+    #[quickcheck]
+    fn matches_visited_set(links: Vec<Option<usize>>, start: usize) -> bool {
+        if links.is_empty() {
+            return true;
+        }
+        let n = links.len();
+        let links: Vec<Option<usize>> = links.into_iter().map(|l| l.map(|l| l % n)).collect();
+        let start = start % n;
+
+        let mut seen = HashMap::new();
+        let mut cur = Some(start);
+        let mut expected = None;
+        while let Some(i) = cur {
+            if let Some(&step) = seen.get(&i) {
+                expected = Some(Cycle {
+                    entry: i,
+                    len: seen.len() - step,
+                    tail_len: step,
+                });
+                break;
+            }
+            seen.insert(i, seen.len());
+            cur = links[i];
+        }
+        find_cycle(start, arena(&links)) == expected
+    }
+}
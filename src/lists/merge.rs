@@ -0,0 +1,89 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::trees::tree_node::LLNode;
+
+//@ Merges two sorted lists by repeatedly moving the smaller head onto the end of the output.
+//@ On ties, `a` goes first, so the merge is stable, which merge sort relies on.
+pub fn merge_two(mut a: LLNode, mut b: LLNode) -> LLNode {
+    let mut head = None;
+    let mut tail = &mut head;
+    while let (Some(x), Some(y)) = (&a, &b) {
+        let src = if x.val <= y.val { &mut a } else { &mut b };
+        let mut node = src.take().expect("both lists are non-empty");
+        *src = node.next.take();
+        tail = &mut tail.insert(node).next;
+    }
+    *tail = a.or(b);
+    head
+}
+
+//@ Merges `k` sorted lists with a min-heap of their heads, so every node costs O(log(k)) time
+//@ instead of the O(k) a linear scan over the heads would take.
+//@ The heap holds `(value, list index)` pairs rather than the nodes themselves, which keeps ties
+//@ in list order and lets the nodes stay where they are until they're taken.
+pub fn merge_k(mut lists: Vec<LLNode>) -> LLNode {
+    let mut heap: BinaryHeap<_> = lists
+        .iter()
+        .enumerate()
+        .filter_map(|(i, list)| list.as_ref().map(|node| Reverse((node.val, i))))
+        .collect();
+
+    let mut head = None;
+    let mut tail = &mut head;
+    while let Some(Reverse((_, i))) = heap.pop() {
+        let mut node = lists[i]
+            .take()
+            .expect("the heap only holds non-empty lists");
+        lists[i] = node.next.take();
+        if let Some(next) = &lists[i] {
+            heap.push(Reverse((next.val, i)));
+        }
+        tail = &mut tail.insert(node).next;
+    }
+    head
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+    use crate::{
+        lists::basics::{from_vec, to_vec},
+        slist,
+    };
+
+    #[test]
+    fn ex1() {
+        assert_eq!(
+            merge_two(slist![1, 2, 4], slist![1, 3, 4]),
+            slist![1, 1, 2, 3, 4, 4]
+        );
+        assert_eq!(merge_two(None, slist![0]), slist![0]);
+        assert_eq!(
+            merge_k(vec![slist![1, 4, 5], slist![1, 3, 4], slist![2, 6]]),
+            slist![1, 1, 2, 3, 4, 4, 5, 6]
+        );
+        assert_eq!(merge_k(vec![]), None);
+        assert_eq!(merge_k(vec![None]), None);
+    }
+
+    fn sorted(mut vals: Vec<i32>) -> Vec<i32> {
+        vals.sort();
+        vals
+    }
+
+    #[quickcheck]
+    fn merge_two_matches_vec(a: Vec<i32>, b: Vec<i32>) -> bool {
+        let (a, b) = (sorted(a), sorted(b));
+        let expected = sorted([a.clone(), b.clone()].concat());
+        to_vec(&merge_two(from_vec(a), from_vec(b))) == expected
+    }
+
+    #[quickcheck]
+    fn merge_k_matches_vec(lists: Vec<Vec<i32>>) -> bool {
+        let lists: Vec<Vec<i32>> = lists.into_iter().map(sorted).collect();
+        let expected = sorted(lists.concat());
+        to_vec(&merge_k(lists.into_iter().map(from_vec).collect())) == expected
+    }
+}
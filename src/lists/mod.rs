@@ -0,0 +1,6 @@
+pub mod basics;
+pub mod cycle;
+pub mod merge;
+pub mod reverse;
+pub mod sort;
+pub mod two_pointers;
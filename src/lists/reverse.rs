@@ -0,0 +1,78 @@
+use super::basics::{len, split_off, tail};
+use crate::trees::tree_node::LLNode;
+
+//@ Reverses a list in place by moving its nodes, one at a time, onto the front of a new list.
+pub fn reverse(mut head: LLNode) -> LLNode {
+    let mut reversed = None;
+    while let Some(mut node) = head {
+        head = node.next.take();
+        node.next = reversed;
+        reversed = Some(node);
+    }
+    reversed
+}
+
+//@ Reverses every group of `k` consecutive nodes, leaving a last group shorter than `k` as it
+//@ is, like [LeetCode 25](https://leetcode.com/problems/reverse-nodes-in-k-group/).
+//@ Every group is split off, reversed and appended to the output, so this takes O(n) time and
+//@ O(1) extra space.
+pub fn reverse_k_group(head: LLNode, k: usize) -> LLNode {
+    if k <= 1 {
+        return head;
+    }
+    let mut out = None;
+    let mut end = &mut out;
+    let mut rest = head;
+    while rest.is_some() {
+        let mut group = rest;
+        rest = split_off(&mut group, k);
+        let full = len(&group) == k;
+        *end = if full { reverse(group) } else { group };
+        end = tail(end);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+    use crate::{
+        lists::basics::{from_vec, to_vec},
+        slist,
+    };
+
+    #[test]
+    fn ex1() {
+        assert_eq!(reverse(slist![1, 2, 3]), slist![3, 2, 1]);
+        assert_eq!(reverse(None), None);
+        assert_eq!(
+            reverse_k_group(slist![1, 2, 3, 4, 5], 2),
+            slist![2, 1, 4, 3, 5]
+        );
+        assert_eq!(
+            reverse_k_group(slist![1, 2, 3, 4, 5], 3),
+            slist![3, 2, 1, 4, 5]
+        );
+    }
+
+    #[quickcheck]
+    fn reverse_matches_vec(vals: Vec<i32>) -> bool {
+        let mut expected = vals.clone();
+        expected.reverse();
+        to_vec(&reverse(from_vec(vals))) == expected
+    }
+
+    #[quickcheck]
+    fn reverse_k_group_matches_vec(vals: Vec<i32>, k: u8) -> bool {
+        let k = k as usize % 8;
+        let mut expected = vals.clone();
+        if k > 1 {
+            for chunk in expected.chunks_exact_mut(k) {
+                chunk.reverse();
+            }
+        }
+        to_vec(&reverse_k_group(from_vec(vals), k)) == expected
+    }
+}
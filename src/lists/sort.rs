@@ -0,0 +1,55 @@
+use super::{
+    basics::{len, split_off, tail},
+    merge::merge_two,
+};
+use crate::trees::tree_node::LLNode;
+
+//@ Sorts a list with bottom-up merge sort, in O(n log(n)) time and O(1) extra space.
+//@ Top-down merge sort recurses O(log(n)) deep; bottom-up sort instead makes passes over the
+//@ whole list, merging neighbouring runs of `width` nodes into runs of `2 * width`, until a
+//@ single run is left. Each pass splits the two runs off the front of the rest, merges them and
+//@ appends the result, so all it needs is a few cursors.
+//@
+//@ Merging is stable, so equal values keep their order.
+pub fn sort(mut head: LLNode) -> LLNode {
+    let n = len(&head);
+    let mut width = 1;
+    while width < n {
+        let mut rest = head.take();
+        let mut end = &mut head;
+        while rest.is_some() {
+            let mut left = rest;
+            let mut right = split_off(&mut left, width);
+            rest = split_off(&mut right, width);
+            *end = merge_two(left, right);
+            end = tail(end);
+        }
+        width *= 2;
+    }
+    head
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+    use crate::{
+        lists::basics::{from_vec, to_vec},
+        slist,
+    };
+
+    #[test]
+    fn ex1() {
+        assert_eq!(sort(slist![4, 2, 1, 3]), slist![1, 2, 3, 4]);
+        assert_eq!(sort(slist![-1, 5, 3, 4, 0]), slist![-1, 0, 3, 4, 5]);
+        assert_eq!(sort(None), None);
+    }
+
+    #[quickcheck]
+    fn sort_matches_vec(vals: Vec<i32>) -> bool {
+        let mut expected = vals.clone();
+        expected.sort();
+        to_vec(&sort(from_vec(vals))) == expected
+    }
+}
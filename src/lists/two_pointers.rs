@@ -0,0 +1,76 @@
+use super::basics::{len, split_off, tail};
+use crate::trees::tree_node::{LLNode, ListNode};
+
+//@ Finds the middle node with a slow and a fast pointer: the fast one takes two steps for every
+//@ step of the slow one, so when it runs off the end, the slow one is halfway. For an even
+//@ length this returns the second of the two middle nodes, like
+//@ [LeetCode 876](https://leetcode.com/problems/middle-of-the-linked-list/).
+pub fn middle(head: &LLNode) -> Option<&ListNode> {
+    let mut slow = head.as_deref()?;
+    let mut fast = head.as_deref();
+    while let Some(next) = fast.and_then(|node| node.next.as_deref()) {
+        slow = slow.next.as_deref().expect("slow is behind fast");
+        fast = next.next.as_deref();
+    }
+    Some(slow)
+}
+
+//@ Removes the `n`th node from the end, where `n = 1` is the last node. Out of range values of
+//@ `n` leave the list as it is.
+//@ The classic one-pass solution keeps a pointer `n` nodes ahead of the one that does the
+//@ removal, but with `Box`ed nodes we can't hold a shared and a mutable reference into the
+//@ same list at once. Counting the nodes first is still O(n), with two passes instead of one.
+pub fn remove_nth_from_end(mut head: LLNode, n: usize) -> LLNode {
+    let len = len(&head);
+    if n == 0 || n > len {
+        return head;
+    }
+    let mut rest = split_off(&mut head, len - n);
+    let after = rest.as_mut().and_then(|node| node.next.take());
+    *tail(&mut head) = after;
+    head
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+    use crate::{
+        lists::basics::{from_vec, to_vec},
+        slist,
+    };
+
+    #[test]
+    fn ex1() {
+        assert_eq!(middle(&slist![1, 2, 3, 4, 5]).map(|node| node.val), Some(3));
+        assert_eq!(
+            middle(&slist![1, 2, 3, 4, 5, 6]).map(|node| node.val),
+            Some(4)
+        );
+        assert_eq!(middle(&None), None);
+
+        assert_eq!(
+            remove_nth_from_end(slist![1, 2, 3, 4, 5], 2),
+            slist![1, 2, 3, 5]
+        );
+        assert_eq!(remove_nth_from_end(slist![1], 1), None);
+        assert_eq!(remove_nth_from_end(slist![1, 2], 3), slist![1, 2]);
+    }
+
+    #[quickcheck]
+    fn middle_matches_vec(vals: Vec<i32>) -> bool {
+        let list = from_vec(vals.clone());
+        middle(&list).map(|node| node.val) == vals.get(vals.len() / 2).copied()
+    }
+
+    #[quickcheck]
+    fn remove_nth_matches_vec(vals: Vec<i32>, n: usize) -> bool {
+        let n = n % (vals.len() + 2);
+        let mut expected = vals.clone();
+        if (1..=vals.len()).contains(&n) {
+            expected.remove(vals.len() - n);
+        }
+        to_vec(&remove_nth_from_end(from_vec(vals), n)) == expected
+    }
+}
@@ -0,0 +1,334 @@
+use std::ops::Range;
+
+//@ A bit vector that answers two questions quickly:
+//@ - `rank1(i)`: how many ones are there before position `i`?
+//@ - `select1(k)`: where is the `k`th one?
+//@
+//@ along with `rank0` and `select0` for zeros. They're the building blocks of succinct data
+//@ structures, like [wavelet trees](../trees/wavelet_tree.html), which store their data in
+//@ close to the minimum number of bits, and still answer queries about it without unpacking it.
+//@
+//@ Counting the ones before `i` by looping over the bits is O(n), and even with `count_ones` on
+//@ whole words, which is the fastest way to count bits from [parity](./parity.html), it's O(n /
+//@ 64). Storing the rank at every position would make it O(1), but takes 64 times more space
+//@ than the bits themselves. The trick is to store counts at two granularities:
+//@ - Every superblock of 2^16 bits stores the number of ones before it, in a `usize`.
+//@ - Every block of 512 bits stores the number of ones before it in its superblock, which
+//@   always fits in a `u16`.
+//@
+//@ Then the rank is the sum of the two counts, plus the `count_ones` of the up to 8 words in the
+//@ block before `i`, which is O(1) and only reads one cache line of bits. The counts take about
+//@ 3% more space than the bits.
+//@
+//@ `select` binary searches the superblocks, then the blocks in that superblock, and then scans
+//@ the words of the block, so it takes O(log(n)) time.
+pub struct RankSelect {
+    words: Vec<u64>,
+    len: usize,
+    ones: usize,
+    superblocks: Vec<usize>,
+    blocks: Vec<u16>,
+}
+
+const WORD: usize = 64;
+const BLOCK: usize = 512;
+const SUPERBLOCK: usize = 1 << 16;
+const WORDS_PER_BLOCK: usize = BLOCK / WORD;
+const BLOCKS_PER_SUPERBLOCK: usize = SUPERBLOCK / BLOCK;
+
+impl RankSelect {
+    //@ Takes the first `len` bits of `words`, with bit `i` at `words[i / 64] >> (i % 64)`.
+    pub fn new(mut words: Vec<u64>, len: usize) -> Self {
+        assert!(words.len() * WORD >= len, "not enough words for {len} bits");
+        words.truncate(len.div_ceil(WORD));
+        if !len.is_multiple_of(WORD) {
+            words[len / WORD] &= (1 << (len % WORD)) - 1;
+        }
+
+        //@ There's a block for position `len` too, so that `rank1(len)` doesn't need a special
+        //@ case.
+        let (mut superblocks, mut blocks) = (vec![], vec![]);
+        let mut ones = 0;
+        for block in 0..=len / BLOCK {
+            if block % BLOCKS_PER_SUPERBLOCK == 0 {
+                superblocks.push(ones);
+            }
+            blocks.push((ones - superblocks[superblocks.len() - 1]) as u16);
+            ones += words[Self::block_words(block, words.len())]
+                .iter()
+                .map(|w| w.count_ones() as usize)
+                .sum::<usize>();
+        }
+        RankSelect {
+            words,
+            len,
+            ones,
+            superblocks,
+            blocks,
+        }
+    }
+
+    fn block_words(block: usize, words: usize) -> Range<usize> {
+        words.min(block * WORDS_PER_BLOCK)..words.min((block + 1) * WORDS_PER_BLOCK)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.ones
+    }
+
+    pub fn count_zeros(&self) -> usize {
+        self.len - self.ones
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        assert!(
+            i < self.len,
+            "index {i} out of bounds for {} bits",
+            self.len
+        );
+        self.words[i / WORD] >> (i % WORD) & 1 == 1
+    }
+
+    //@ The number of ones in `0..i`.
+    pub fn rank1(&self, i: usize) -> usize {
+        assert!(
+            i <= self.len,
+            "index {i} out of bounds for {} bits",
+            self.len
+        );
+        let block = i / BLOCK;
+        let word = i / WORD;
+        let mut rank = self.superblocks[i / SUPERBLOCK] + self.blocks[block] as usize;
+        rank += self.words[block * WORDS_PER_BLOCK..word]
+            .iter()
+            .map(|w| w.count_ones() as usize)
+            .sum::<usize>();
+        if !i.is_multiple_of(WORD) {
+            rank += (self.words[word] & ((1 << (i % WORD)) - 1)).count_ones() as usize;
+        }
+        rank
+    }
+
+    //@ The number of zeros in `0..i`.
+    pub fn rank0(&self, i: usize) -> usize {
+        i - self.rank1(i)
+    }
+
+    //@ The position of the `k`th one, counting from zero, so that `rank1(select1(k)) == k`.
+    pub fn select1(&self, k: usize) -> Option<usize> {
+        self.select(k, true)
+    }
+
+    //@ The position of the `k`th zero, counting from zero.
+    pub fn select0(&self, k: usize) -> Option<usize> {
+        self.select(k, false)
+    }
+
+    //@ Selecting zeros works the same way as selecting ones, with the counts of zeros worked out
+    //@ from the counts of ones and the number of bits before each superblock and block.
+    fn select(&self, mut k: usize, bit: bool) -> Option<usize> {
+        let total = if bit { self.ones } else { self.len - self.ones };
+        if k >= total {
+            return None;
+        }
+        let in_superblock = |s: usize| {
+            let ones = self.superblocks[s];
+            if bit {
+                ones
+            } else {
+                s * SUPERBLOCK - ones
+            }
+        };
+        let superblock = partition_point(0..self.superblocks.len(), |s| in_superblock(s) <= k) - 1;
+        k -= in_superblock(superblock);
+
+        let first = superblock * BLOCKS_PER_SUPERBLOCK;
+        let in_block = |b: usize| {
+            let ones = self.blocks[b] as usize;
+            if bit {
+                ones
+            } else {
+                (b - first) * BLOCK - ones
+            }
+        };
+        let last = self.blocks.len().min(first + BLOCKS_PER_SUPERBLOCK);
+        let block = partition_point(first..last, |b| in_block(b) <= k) - 1;
+        k -= in_block(block);
+
+        for i in Self::block_words(block, self.words.len()) {
+            let word = if bit { self.words[i] } else { !self.words[i] };
+            let count = word.count_ones() as usize;
+            if k < count {
+                return Some(i * WORD + select_in_word(word, k));
+            }
+            k -= count;
+        }
+        unreachable!("the block has the {k}th bit")
+    }
+}
+
+//@ The first `i` in `range` where `pred(i)` is false, if `pred` is true for a prefix of `range`.
+fn partition_point(range: Range<usize>, pred: impl Fn(usize) -> bool) -> usize {
+    let (mut lo, mut hi) = (range.start, range.end);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+//@ Clears the lowest `k` ones with the `x & (x - 1)` trick from parity, and then the lowest one
+//@ left is the `k`th.
+fn select_in_word(mut word: u64, k: usize) -> usize {
+    for _ in 0..k {
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
+
+impl FromIterator<bool> for RankSelect {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut words = vec![];
+        let mut len: usize = 0;
+        for bit in iter {
+            if len.is_multiple_of(WORD) {
+                words.push(0);
+            }
+            words[len / WORD] |= u64::from(bit) << (len % WORD);
+            len += 1;
+        }
+        RankSelect::new(words, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use test::{black_box, Bencher};
+
+    use super::*;
+
+    #[test]
+    fn ex1() {
+        let bits: RankSelect = "1011000111".chars().map(|c| c == '1').collect();
+        assert_eq!(bits.len(), 10);
+        assert_eq!(bits.count_ones(), 6);
+        assert_eq!(bits.rank1(4), 3);
+        assert_eq!(bits.rank0(4), 1);
+        assert_eq!(bits.rank1(10), 6);
+        assert_eq!(bits.select1(3), Some(7));
+        assert_eq!(bits.select0(2), Some(5));
+        assert_eq!(bits.select1(6), None);
+        assert_eq!(bits.select0(4), None);
+    }
+
+    //@ Bits past `len` are ignored.
+    #[test]
+    fn trailing_bits() {
+        let bits = RankSelect::new(vec![u64::MAX, u64::MAX], 70);
+        assert_eq!(bits.count_ones(), 70);
+        assert_eq!(bits.select1(69), Some(69));
+        assert_eq!(bits.select1(70), None);
+        assert_eq!(bits.select0(0), None);
+    }
+
+    fn check(bits: &[bool]) -> bool {
+        let rs: RankSelect = bits.iter().copied().collect();
+        let ones: Vec<usize> = (0..bits.len()).filter(|&i| bits[i]).collect();
+        let zeros: Vec<usize> = (0..bits.len()).filter(|&i| !bits[i]).collect();
+        let mut rank = 0;
+        for (i, &bit) in bits.iter().enumerate() {
+            if rs.rank1(i) != rank || rs.get(i) != bit {
+                return false;
+            }
+            rank += usize::from(bit);
+        }
+        rs.rank1(bits.len()) == rank
+            && (0..=ones.len()).all(|k| rs.select1(k) == ones.get(k).copied())
+            && (0..=zeros.len()).all(|k| rs.select0(k) == zeros.get(k).copied())
+    }
+
+    #[quickcheck]
+    fn matches_naive(bits: Vec<bool>) -> bool {
+        check(&bits)
+    }
+
+    //@ Long runs and a few superblocks, with some of the bits dense and some sparse.
+    #[test]
+    fn superblocks() {
+        let mut rng = StdRng::seed_from_u64(44);
+        let bits: Vec<bool> = (0..5 * SUPERBLOCK + 123)
+            .map(|i| match i / 40_000 % 3 {
+                0 => rng.gen_bool(0.5),
+                1 => rng.gen_bool(0.001),
+                _ => true,
+            })
+            .collect();
+        assert!(check(&bits));
+    }
+
+    //@ A million random queries on 2^24 random bits take 29ms for `rank1` and 245ms for
+    //@ `select1`, most of which is cache misses in its binary searches. Counting the ones up to
+    //@ each position with `count_ones` takes 149ms for only a thousand queries.
+    const N: usize = 1 << 24;
+
+    fn random(rng: &mut StdRng) -> RankSelect {
+        RankSelect::new((0..N / WORD).map(|_| rng.gen()).collect(), N)
+    }
+
+    #[bench]
+    fn bench_rank1(b: &mut Bencher) {
+        let mut rng = StdRng::seed_from_u64(44);
+        let bits = random(&mut rng);
+        let queries: Vec<usize> = (0..1_000_000).map(|_| rng.gen_range(0..N)).collect();
+        b.iter(|| queries.iter().map(|&i| bits.rank1(i)).sum::<usize>());
+    }
+
+    #[bench]
+    fn bench_select1(b: &mut Bencher) {
+        let mut rng = StdRng::seed_from_u64(44);
+        let bits = random(&mut rng);
+        let queries: Vec<usize> = (0..1_000_000)
+            .map(|_| rng.gen_range(0..bits.count_ones()))
+            .collect();
+        b.iter(|| {
+            queries
+                .iter()
+                .map(|&k| bits.select1(k).unwrap())
+                .sum::<usize>()
+        });
+    }
+
+    #[bench]
+    fn bench_rank1_count_words(b: &mut Bencher) {
+        let mut rng = StdRng::seed_from_u64(44);
+        let bits = random(&mut rng);
+        let queries: Vec<usize> = (0..1_000).map(|_| rng.gen_range(0..N)).collect();
+        b.iter(|| {
+            queries
+                .iter()
+                .map(|&i| {
+                    let full: usize = bits.words[..i / WORD]
+                        .iter()
+                        .map(|w| w.count_ones() as usize)
+                        .sum();
+                    black_box(full)
+                })
+                .sum::<usize>()
+        });
+    }
+}
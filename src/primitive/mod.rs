@@ -1 +1,2 @@
+pub mod bitvec;
 pub mod parity;
@@ -1,57 +1,85 @@
 use std::cmp::Ordering;
 
-pub fn quickselect(arr: &mut [i32], k: usize) -> i32 {
+pub fn quickselect<T: Ord + Clone>(arr: &mut [T], k: usize) -> T {
+    quickselect_by(arr, k, &mut T::cmp).clone()
+}
+
+//@ Like `quickselect`, but with a comparison function, so it can select on floats with
+//@ `f64::total_cmp`, or on one coordinate of a point. Afterwards, `arr[k]` is the element that
+//@ would be there if `arr` was sorted, everything before it is less than or equal to it, and
+//@ everything after it is greater than or equal to it, so `arr` is partitioned around its median
+//@ when `k` is `arr.len() / 2`.
+pub fn quickselect_by<'a, T, F: FnMut(&T, &T) -> Ordering>(
+    arr: &'a mut [T],
+    k: usize,
+    cmp: &mut F,
+) -> &'a mut T {
     if arr.len() <= 5 {
-        arr.sort(); // Use sort for small sizes for simplicity
-        return arr[k];
+        arr.sort_by(|a, b| cmp(a, b)); // Use sort for small sizes for simplicity
+        return &mut arr[k];
     }
 
-    let median_of_medians = select_median_of_medians(arr);
-    let pivot_index = partition(arr, median_of_medians);
+    let median_of_medians = select_median_of_medians(arr, cmp);
+    let (less, greater) = partition(arr, median_of_medians, cmp);
 
-    match k.cmp(&pivot_index) {
-        Ordering::Less => quickselect(&mut arr[..pivot_index], k),
-        Ordering::Equal => arr[pivot_index],
-        Ordering::Greater => quickselect(&mut arr[pivot_index + 1..], k - pivot_index - 1),
+    if k < less {
+        quickselect_by(&mut arr[..less], k, cmp)
+    } else if k < greater {
+        &mut arr[k]
+    } else {
+        quickselect_by(&mut arr[greater..], k - greater, cmp)
     }
 }
 
-fn select_median_of_medians(arr: &mut [i32]) -> i32 {
-    let mut medians: Vec<i32> = arr
-        .chunks_mut(5)
-        .map(|chunk| {
-            chunk.sort();
-            chunk[chunk.len() / 2] // Get the median of the chunk
-        })
-        .collect();
+//@ Sorts every chunk of 5 and moves its median to the front of `arr`, then finds the median of
+//@ those medians in place, and returns its index.
+fn select_median_of_medians<T, F: FnMut(&T, &T) -> Ordering>(arr: &mut [T], cmp: &mut F) -> usize {
+    let mut medians = 0;
+    for start in (0..arr.len()).step_by(5) {
+        let end = arr.len().min(start + 5);
+        arr[start..end].sort_by(|a, b| cmp(a, b));
+        arr.swap(medians, start + (end - start) / 2); // Get the median of the chunk
+        medians += 1;
+    }
 
-    let mid = medians.len() / 2;
-    quickselect(&mut medians, mid) // Recursively find the median of medians
+    let mid = medians / 2;
+    quickselect_by(&mut arr[..medians], mid, cmp); // Recursively find the median of medians
+    mid
 }
 
-fn partition(arr: &mut [i32], pivot: i32) -> usize {
-    let mut i = 0;
-    let mut j = arr.len() - 1;
-
+//@ A three-way partition around `arr[pivot]`, which returns where the elements equal to the pivot
+//@ start and end. Putting the equal elements in the middle keeps inputs with lots of duplicates,
+//@ like points on a grid, from taking quadratic time.
+fn partition<T, F: FnMut(&T, &T) -> Ordering>(
+    arr: &mut [T],
+    pivot: usize,
+    cmp: &mut F,
+) -> (usize, usize) {
     // Move pivot to the end
-    let pivot_index = arr.iter().position(|&x| x == pivot).unwrap();
-    arr.swap(pivot_index, j);
+    let last = arr.len() - 1;
+    arr.swap(pivot, last);
+    let (rest, pivot) = arr.split_at_mut(last);
+    let pivot = &pivot[0];
 
-    while i < j {
-        while i < j && arr[i] < pivot {
-            i += 1;
-        }
-        while i < j && arr[j] >= pivot {
-            j -= 1;
-        }
-        if i < j {
-            arr.swap(i, j);
+    let (mut less, mut i, mut greater) = (0, 0, rest.len());
+    while i < greater {
+        match cmp(&rest[i], pivot) {
+            Ordering::Less => {
+                rest.swap(less, i);
+                less += 1;
+                i += 1;
+            }
+            Ordering::Equal => i += 1,
+            Ordering::Greater => {
+                greater -= 1;
+                rest.swap(i, greater);
+            }
         }
     }
 
     // Swap back the pivot to its correct place
-    arr.swap(i, arr.len() - 1);
-    i
+    arr.swap(greater, last);
+    (less, greater + 1)
 }
 
 #[cfg(test)]
@@ -76,4 +104,21 @@ mod tests {
         }
         true
     }
+
+    //@ Selecting on floats by their absolute value, with lots of duplicates, also partitions the
+    //@ slice around the `k`th element.
+    #[quickcheck]
+    fn quickcheck_quickselect_by(xs: Vec<i8>, k: usize) -> bool {
+        if xs.is_empty() {
+            return true;
+        }
+        let mut xs: Vec<f64> = xs.into_iter().map(|x| f64::from(x % 8) / 2.0).collect();
+        let k = k % xs.len();
+        let mut sorted_copy = xs.clone();
+        sorted_copy.sort_by(|a, b| a.abs().total_cmp(&b.abs()));
+        let kth = *quickselect_by(&mut xs, k, &mut |a, b| a.abs().total_cmp(&b.abs()));
+        kth.abs() == sorted_copy[k].abs()
+            && xs[..k].iter().all(|x| x.abs() <= kth.abs())
+            && xs[k + 1..].iter().all(|x| x.abs() >= kth.abs())
+    }
 }
@@ -1,3 +1,45 @@
+//@ A polynomial rolling hash over a fixed-size window of bytes. Sliding the window by one byte
+//@ removes the leftmost byte's term and appends the new byte in O(1) time, which is what lets
+//@ Rabin-Karp compare every window of the text against the pattern cheaply.
+//@ It only ever looks at one byte at a time, so the bytes can come from anywhere, like the
+//@ chunks of a [rope](../trees/rope.html).
+#[derive(Clone, Copy, Debug)]
+pub struct RollingHash {
+    hash: usize,
+    //@ Highest power of base used for leftmost character
+    highest_pow: usize,
+}
+
+impl RollingHash {
+    const BASE: usize = 257; //@ A prime base for hashing
+    const MODULUS: usize = 1_000_000_007; //@ A large prime modulus
+
+    //@ Hashes the first window, and precomputes the highest power of base needed to roll it.
+    pub fn of(window: &[u8]) -> Self {
+        let mut highest_pow = 1;
+        for _ in 1..window.len() {
+            highest_pow = highest_pow * Self::BASE % Self::MODULUS;
+        }
+        let hash = window.iter().fold(0, |hash, &b| {
+            (hash * Self::BASE + b as usize) % Self::MODULUS
+        });
+        RollingHash { hash, highest_pow }
+    }
+
+    pub fn hash(&self) -> usize {
+        self.hash
+    }
+
+    //@ Slides the window by one byte: `out` leaves it on the left and `next` enters on the right.
+    //@ `out * highest_pow` is less than `256 * MODULUS`, so adding that much first keeps the
+    //@ subtraction from underflowing, and a single reduction at the end is enough. This is the
+    //@ inner loop of every search, so saving the other two divisions matters.
+    pub fn roll(&mut self, out: u8, next: u8) {
+        let hash = self.hash + 256 * Self::MODULUS - out as usize * self.highest_pow;
+        self.hash = (hash * Self::BASE + next as usize) % Self::MODULUS;
+    }
+}
+
 pub fn rabin_karp(text: &str, pattern: &str) -> bool {
     if text.is_empty() && pattern.is_empty() {
         return true;
@@ -12,31 +54,13 @@ pub fn rabin_karp(text: &str, pattern: &str) -> bool {
         return false;
     }
 
-    let base = 257; //@ A prime base for hashing
-    let modulus = 1_000_000_007; //@ A large prime modulus
-
-    let mut pattern_hash = 0;
-    let mut text_hash = 0;
-    //@ Highest power of base used for leftmost character
-    let mut highest_pow = 1;
-
-    //@ Precompute the highest power of base needed
-    for _ in 0..p_len - 1 {
-        highest_pow = highest_pow * base % modulus;
-    }
-
-    let pattern_bytes = pattern.as_bytes();
+    let pattern_hash = RollingHash::of(pattern.as_bytes()).hash();
     let text_bytes = text.as_bytes();
-
-    //@ Calculate the hash of the pattern and the first window of the text
-    for i in 0..p_len {
-        pattern_hash = (pattern_hash * base + pattern_bytes[i] as usize) % modulus;
-        text_hash = (text_hash * base + text_bytes[i] as usize) % modulus;
-    }
+    let mut window = RollingHash::of(&text_bytes[..p_len]);
 
     //@ Slide over the text to compare hashes and if needed, check for actual matches
     for s in 0..=t_len - p_len {
-        if pattern_hash == text_hash {
+        if pattern_hash == window.hash() {
             // Verify this is not a hash collision
             if &text[s..s + p_len] == pattern {
                 return true;
@@ -45,9 +69,7 @@ pub fn rabin_karp(text: &str, pattern: &str) -> bool {
 
         //@ Compute the hash of the next window
         if s < t_len - p_len {
-            text_hash =
-                (text_hash + modulus - text_bytes[s] as usize * highest_pow % modulus) % modulus;
-            text_hash = (text_hash * base + text_bytes[s + p_len] as usize) % modulus;
+            window.roll(text_bytes[s], text_bytes[s + p_len]);
         }
     }
 
@@ -66,6 +88,23 @@ mod tests {
         s.contains(&t) == rabin_karp(&s, &t)
     }
 
+    //@ Rolling over a text gives the same hash as hashing each window from scratch.
+    #[quickcheck]
+    fn rolling_matches_fresh(text: Vec<u8>, len: usize) -> bool {
+        let len = len % (text.len() + 1);
+        if len == 0 {
+            return true;
+        }
+        let mut hash = RollingHash::of(&text[..len]);
+        text.windows(len).enumerate().all(|(i, window)| {
+            let fresh = RollingHash::of(window).hash() == hash.hash();
+            if i + len < text.len() {
+                hash.roll(text[i], text[i + len]);
+            }
+            fresh
+        })
+    }
+
     #[test]
     fn match_case() {
         let text = "hello world";
@@ -1,5 +1,70 @@
 use std::cmp::Ordering;
 
+//@ An [AVL tree](https://en.wikipedia.org/wiki/AVL_tree) keeps the heights of the two subtrees
+//@ of every node within one of each other, by rotating on the way back up after an insertion.
+//@
+//@ The rotations only need to get at a node's children and to recompute what the node caches
+//@ about its subtree, so they're written against the `Balanced` trait. That lets augmented trees
+//@ that cache more than the height, like the [interval tree](./interval_tree.html), reuse them.
+pub(crate) type Link<N> = Option<Box<N>>;
+
+pub(crate) trait Balanced: Sized {
+    fn left(&self) -> &Link<Self>;
+    fn right(&self) -> &Link<Self>;
+    fn left_mut(&mut self) -> &mut Link<Self>;
+    fn right_mut(&mut self) -> &mut Link<Self>;
+    fn height(&self) -> i32;
+    //@ Recomputes the height, and anything else the node caches, from its children.
+    fn update(&mut self);
+}
+
+pub(crate) fn height<N: Balanced>(node: &Link<N>) -> i32 {
+    node.as_ref().map_or(0, |node| node.height())
+}
+
+fn balance_factor<N: Balanced>(node: &N) -> i32 {
+    height(node.left()) - height(node.right())
+}
+
+pub(crate) fn rotate_right<N: Balanced>(mut node: Box<N>) -> Box<N> {
+    let mut new_root = node.left_mut().take().unwrap();
+    *node.left_mut() = new_root.right_mut().take();
+    node.update();
+    *new_root.right_mut() = Some(node);
+    new_root.update();
+    new_root
+}
+
+pub(crate) fn rotate_left<N: Balanced>(mut node: Box<N>) -> Box<N> {
+    let mut new_root = node.right_mut().take().unwrap();
+    *node.right_mut() = new_root.left_mut().take();
+    node.update();
+    *new_root.left_mut() = Some(node);
+    new_root.update();
+    new_root
+}
+
+//@ Updates a node whose children may have changed, and rotates it if they're now out of balance.
+pub(crate) fn balance<N: Balanced>(mut node: Box<N>) -> Box<N> {
+    node.update();
+    let balance = balance_factor(&*node);
+    if balance > 1 {
+        if balance_factor(&**node.left().as_ref().unwrap()) < 0 {
+            let left = node.left_mut().take().unwrap();
+            *node.left_mut() = Some(rotate_left(left));
+        }
+        rotate_right(node)
+    } else if balance < -1 {
+        if balance_factor(&**node.right().as_ref().unwrap()) > 0 {
+            let right = node.right_mut().take().unwrap();
+            *node.right_mut() = Some(rotate_right(right));
+        }
+        rotate_left(node)
+    } else {
+        node
+    }
+}
+
 #[derive(Debug, Clone)]
 struct Node<T> {
     value: T,
@@ -8,64 +73,46 @@ struct Node<T> {
     right: Option<Box<Node<T>>>,
 }
 
-impl<T: Ord> Node<T> {
-    fn new(value: T) -> Self {
-        Node {
-            value,
-            height: 1,
-            left: None,
-            right: None,
-        }
+impl<T> Balanced for Node<T> {
+    fn left(&self) -> &Link<Self> {
+        &self.left
     }
 
-    fn height(node: &Option<Box<Node<T>>>) -> i32 {
-        node.as_ref().map_or(0, |node| node.height)
+    fn right(&self) -> &Link<Self> {
+        &self.right
     }
 
-    fn update_height(&mut self) {
-        self.height = i32::max(Self::height(&self.left), Self::height(&self.right)) + 1;
+    fn left_mut(&mut self) -> &mut Link<Self> {
+        &mut self.left
     }
 
-    fn balance_factor(&self) -> i32 {
-        Self::height(&self.left) - Self::height(&self.right)
+    fn right_mut(&mut self) -> &mut Link<Self> {
+        &mut self.right
     }
 
-    fn rotate_right(mut self) -> Box<Node<T>> {
-        let mut new_root = self.left.take().unwrap();
-        self.left = new_root.right.take();
-        self.update_height();
-        new_root.right = Some(Box::new(self));
-        new_root.update_height();
-        new_root
+    fn height(&self) -> i32 {
+        self.height
     }
 
-    fn rotate_left(mut self) -> Box<Node<T>> {
-        let mut new_root = self.right.take().unwrap();
-        self.right = new_root.left.take();
-        self.update_height();
-        new_root.left = Some(Box::new(self));
-        new_root.update_height();
-        new_root
+    fn update(&mut self) {
+        self.height = i32::max(height(&self.left), height(&self.right)) + 1;
     }
+}
 
-    fn balance(mut self) -> Box<Node<T>> {
-        self.update_height();
-        let balance = self.balance_factor();
-        if balance > 1 {
-            if self.left.as_ref().unwrap().balance_factor() < 0 {
-                self.left = Some(self.left.take().unwrap().rotate_left());
-            }
-            self.rotate_right()
-        } else if balance < -1 {
-            if self.right.as_ref().unwrap().balance_factor() > 0 {
-                self.right = Some(self.right.take().unwrap().rotate_right());
-            }
-            self.rotate_left()
-        } else {
-            Box::new(self)
+impl<T: Ord> Node<T> {
+    fn new(value: T) -> Self {
+        Node {
+            value,
+            height: 1,
+            left: None,
+            right: None,
         }
     }
 
+    fn height(node: &Option<Box<Node<T>>>) -> i32 {
+        height(node)
+    }
+
     fn insert(self, value: T) -> Box<Node<T>> {
         let mut node = Box::new(self);
         match value.cmp(&node.value) {
@@ -78,7 +125,7 @@ impl<T: Ord> Node<T> {
                 None => node.right = Some(Box::new(Node::new(value))),
             },
         }
-        node.balance()
+        balance(node)
     }
 
     fn contains(&self, value: &T) -> bool {
@@ -97,23 +144,23 @@ impl<T: Ord> Node<T> {
 }
 
 #[derive(Debug, Clone)]
-struct AVLTree<T> {
+pub(crate) struct AVLTree<T> {
     root: Option<Box<Node<T>>>,
 }
 
 impl<T: Ord> AVLTree<T> {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         AVLTree { root: None }
     }
 
-    fn insert(&mut self, value: T) {
+    pub(crate) fn insert(&mut self, value: T) {
         match self.root.take() {
             Some(root) => self.root = Some(root.insert(value)),
             None => self.root = Some(Box::new(Node::new(value))),
         }
     }
 
-    fn contains(&self, value: &T) -> bool {
+    pub(crate) fn contains(&self, value: &T) -> bool {
         match &self.root {
             Some(root) => root.contains(value),
             None => false,
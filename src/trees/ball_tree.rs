@@ -0,0 +1,297 @@
+use std::ops::Range;
+
+use super::kdtree::{Nearest, Neighbors, Within};
+
+//@ A [Ball Tree](https://en.wikipedia.org/wiki/Ball_tree) finds nearest neighbors with any
+//@ metric, like great-circle distances between places on a map, or the edit distance between
+//@ strings. A [k-d tree](./kdtree.html) needs coordinates to split space with planes, but all a
+//@ ball tree needs is the distance function, as long as it's a metric: symmetric, zero only
+//@ between equal items, and satisfying the triangle inequality. The [BK tree](./bk_tree.html)
+//@ works on any metric too, but only on discrete ones.
+//@
+//@ Every node is a ball: a center, which is one of the items, and a radius that covers all of
+//@ the items in its subtree. By the triangle inequality, nothing in a ball is closer to a query
+//@ than its distance to the center minus the radius, so a search can skip any ball where that's
+//@ farther than what it's looking for.
+//@
+//@ A ball is split by finding the item farthest from its center, which is also its radius, and
+//@ giving every item to whichever of the two it's closer to. The center stays the center of the
+//@ first half, and the far item becomes the center of the second. The halves don't have to be
+//@ the same size, so the tree can get deep, and is built and searched with explicit stacks.
+//@
+//@ Like in the k-d tree, the items of every subtree sit next to each other in a single `Vec`,
+//@ and a ball's center is the first of them. Small balls are leaves, which are scanned.
+pub struct BallTree<T> {
+    items: Vec<T>,
+    balls: Vec<Ball>,
+    dist: Box<MetricFn<T>>,
+}
+
+//@ A definition of a metric.
+pub type MetricFn<T> = dyn Fn(&T, &T) -> f64;
+
+struct Ball {
+    radius: f64,
+    items: Range<usize>,
+    children: Option<(usize, usize)>,
+}
+
+//@ Balls with this many items or fewer aren't split.
+const LEAF_SIZE: usize = 8;
+
+impl<T> BallTree<T> {
+    pub fn new(mut items: Vec<T>, dist: impl Fn(&T, &T) -> f64 + 'static) -> Self {
+        let (mut balls, mut stack) = (vec![], vec![]);
+        if !items.is_empty() {
+            stack.push(0);
+            balls.push(Ball {
+                radius: 0.0,
+                items: 0..items.len(),
+                children: None,
+            });
+        }
+        while let Some(id) = stack.pop() {
+            let range = balls[id].items.clone();
+            let (radius, split) = split(&mut items[range.clone()], &dist);
+            balls[id].radius = radius;
+            let Some(split) = split else {
+                continue;
+            };
+            let mid = range.start + split;
+            for items in [range.start..mid, mid..range.end] {
+                stack.push(balls.len());
+                balls.push(Ball {
+                    radius: 0.0,
+                    items,
+                    children: None,
+                });
+            }
+            balls[id].children = Some((balls.len() - 2, balls.len() - 1));
+        }
+        BallTree {
+            items,
+            balls,
+            dist: Box::new(dist),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    //@ The `k` items closest to `query`, with their distances, from closest to farthest.
+    pub fn nearest(&self, query: &T, k: usize) -> Vec<(&T, f64)> {
+        let mut nearest = Nearest::new(k);
+        self.search(query, &mut nearest);
+        self.found(nearest.into_sorted())
+    }
+
+    //@ All items at most `radius` away from `query`, from closest to farthest.
+    pub fn within(&self, query: &T, radius: f64) -> Vec<(&T, f64)> {
+        assert!(radius >= 0.0, "radius must be non-negative, got {radius}");
+        let mut within = Within::new(radius);
+        self.search(query, &mut within);
+        self.found(within.into_sorted())
+    }
+
+    fn found(&self, found: Vec<(f64, usize)>) -> Vec<(&T, f64)> {
+        found
+            .into_iter()
+            .map(|(dist, i)| (&self.items[i], dist))
+            .collect()
+    }
+
+    //@ The stack holds balls along with the distance from the query to their centers. The first
+    //@ half of a ball has the same center, so only the second half's distance is new. The closer
+    //@ half is searched first, since it's more likely to shrink the bound.
+    fn search(&self, query: &T, found: &mut impl Neighbors) {
+        if self.balls.is_empty() {
+            return;
+        }
+        let mut stack = vec![(0, (self.dist)(query, &self.items[0]))];
+        while let Some((id, to_center)) = stack.pop() {
+            let ball = &self.balls[id];
+            if to_center - ball.radius > found.bound() {
+                continue;
+            }
+            let Some((first, second)) = ball.children else {
+                for i in ball.items.clone() {
+                    found.offer((self.dist)(query, &self.items[i]), i);
+                }
+                continue;
+            };
+            let second_center = &self.items[self.balls[second].items.start];
+            let halves = [
+                (first, to_center),
+                (second, (self.dist)(query, second_center)),
+            ];
+            let [closer, farther] = if halves[0].1 - self.balls[first].radius
+                <= halves[1].1 - self.balls[second].radius
+            {
+                halves
+            } else {
+                [halves[1], halves[0]]
+            };
+            stack.push(farther);
+            stack.push(closer);
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items.iter()
+    }
+}
+
+//@ Splits the items of a ball, whose center is the first of them. Returns the radius, and if the
+//@ ball isn't a leaf, where its second half starts, which is where the far item ends up. A ball
+//@ with a radius of zero only has copies of its center, so it's never split.
+fn split<T>(items: &mut [T], dist: &impl Fn(&T, &T) -> f64) -> (f64, Option<usize>) {
+    let mut to_center: Vec<f64> = items.iter().map(|item| dist(&items[0], item)).collect();
+    let (far, radius) = to_center
+        .iter()
+        .copied()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .expect("balls aren't empty");
+    if items.len() <= LEAF_SIZE || radius == 0.0 {
+        return (radius, None);
+    }
+    let last = items.len() - 1;
+    items.swap(far, last);
+    to_center.swap(far, last);
+    let (rest, far) = items.split_at_mut(last);
+    let far = &far[0];
+    let mut mid = 1;
+    for i in 1..rest.len() {
+        if to_center[i] <= dist(&rest[i], far) {
+            rest.swap(mid, i);
+            to_center.swap(mid, i);
+            mid += 1;
+        }
+    }
+    items.swap(mid, last);
+    (radius, Some(mid))
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+    use crate::distances::{haversine::haversine_distance, levenshtein::levenshtein_distance};
+    use crate::trees::bk_tree::DICTIONARY;
+
+    //@ Great-circle distances, where a k-d tree on latitude and longitude would put Fiji and
+    //@ Samoa on opposite sides of the map.
+    #[test]
+    fn ex1() {
+        let cities = vec![
+            ("Paris", [48.8566, 2.3522]),
+            ("London", [51.5074, -0.1278]),
+            ("Brussels", [50.8503, 4.3517]),
+            ("Berlin", [52.5200, 13.4050]),
+            ("Madrid", [40.4168, -3.7038]),
+            ("Rome", [41.9028, 12.4964]),
+            ("Suva", [-18.1248, 178.4501]),
+            ("Apia", [-13.8507, -171.7514]),
+            ("Tokyo", [35.6762, 139.6503]),
+            ("Sydney", [-33.8688, 151.2093]),
+            ("Auckland", [-36.8485, 174.7633]),
+        ];
+        let tree = BallTree::new(cities, |a: &(&str, [f64; 2]), b| {
+            haversine_distance(&a.1, &b.1)
+        });
+        let near = |query: [f64; 2], k| -> Vec<&str> {
+            tree.nearest(&("", query), k)
+                .into_iter()
+                .map(|((name, _), _)| *name)
+                .collect()
+        };
+        let lille = [50.6292, 3.0573];
+        assert_eq!(near(lille, 3), ["Brussels", "Paris", "London"]);
+        let tonga = [-21.1394, -175.2049];
+        assert_eq!(near(tonga, 2), ["Suva", "Apia"]);
+
+        let close_to_paris: Vec<_> = tree
+            .within(&("", [48.8566, 2.3522]), 400.0)
+            .into_iter()
+            .map(|((name, _), _)| *name)
+            .collect();
+        assert_eq!(close_to_paris, ["Paris", "Brussels", "London"]);
+    }
+
+    //@ Edit distance is a metric too, so a ball tree can do the fuzzy matching a BK tree does.
+    #[test]
+    fn strings() {
+        let words = DICTIONARY.iter().map(|w| w.to_string()).collect();
+        let tree = BallTree::new(words, |a: &String, b| levenshtein_distance(a, b) as f64);
+        let suggestions: Vec<_> = tree
+            .within(&"cok".to_string(), 1.0)
+            .into_iter()
+            .map(|(word, _)| word.as_str())
+            .collect();
+        assert_eq!(suggestions, ["cook"]);
+        let (closest, dist) = tree.nearest(&"bok".to_string(), 1)[0];
+        assert_eq!(dist, 1.0);
+        assert!(["book", "boo"].contains(&closest.as_str()));
+    }
+
+    #[test]
+    #[should_panic(expected = "radius must be non-negative")]
+    fn nan_radius() {
+        let tree = BallTree::new(vec![0.0], |a: &f64, b| (a - b).abs());
+        tree.within(&0.0, f64::NAN);
+    }
+
+    //@ Checks that every ball covers its items, and that its halves split them.
+    fn check<T>(tree: &BallTree<T>, id: usize) {
+        let ball = &tree.balls[id];
+        let center = &tree.items[ball.items.start];
+        for item in &tree.items[ball.items.clone()] {
+            assert!((tree.dist)(center, item) <= ball.radius);
+        }
+        if let Some((first, second)) = ball.children {
+            let (first_items, second_items) = (&tree.balls[first].items, &tree.balls[second].items);
+            assert_eq!(first_items.start, ball.items.start);
+            assert_eq!(first_items.end, second_items.start);
+            assert_eq!(second_items.end, ball.items.end);
+            check(tree, first);
+            check(tree, second);
+        }
+    }
+
+    //@ Manhattan distances on a small grid, compared with a scan.
+    #[quickcheck]
+    fn matches_brute_force(points: Vec<(i8, i8)>, query: (i8, i8), k: u8) -> bool {
+        let manhattan = |a: &(i8, i8), b: &(i8, i8)| {
+            f64::from(
+                (i32::from(a.0) - i32::from(b.0)).abs() + (i32::from(a.1) - i32::from(b.1)).abs(),
+            )
+        };
+        let points: Vec<_> = points.into_iter().map(|(x, y)| (x % 10, y % 10)).collect();
+        let query = (query.0 % 10, query.1 % 10);
+        let mut expected: Vec<f64> = points.iter().map(|p| manhattan(p, &query)).collect();
+        expected.sort_by(f64::total_cmp);
+        let tree = BallTree::new(points, manhattan);
+        if !tree.is_empty() {
+            check(&tree, 0);
+        }
+        let k = k % 16;
+        let radius = f64::from(k);
+        let k = usize::from(k);
+        let nearest: Vec<f64> = tree.nearest(&query, k).iter().map(|n| n.1).collect();
+        let within: Vec<f64> = tree.within(&query, radius).iter().map(|n| n.1).collect();
+        nearest == expected[..k.min(expected.len())]
+            && within
+                == expected
+                    .iter()
+                    .copied()
+                    .take_while(|&d| d <= radius)
+                    .collect::<Vec<_>>()
+    }
+}
@@ -0,0 +1,543 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    fmt::{self, Display, Write},
+    rc::Rc,
+    str::FromStr,
+};
+
+use super::tree_node::{BSTNode, TreeNode};
+
+//@ A toolkit of the usual binary tree helpers on `BSTNode`, the `Option<Rc<RefCell<TreeNode>>>`
+//@ shape that LeetCode uses for its tree problems.
+//@
+//@ Everything except the pretty-printer is iterative, so it also works on degenerate trees that
+//@ are thousands of levels deep, where the recursive versions would overflow the stack.
+type Link<T> = Rc<RefCell<TreeNode<T>>>;
+
+//@ ## Traversals
+//@ Pre-order visits a node before its children. With a stack, pushing the right child before the
+//@ left one makes the left subtree come out first.
+pub fn pre_order<T: Clone>(root: &BSTNode<T>) -> Vec<T> {
+    let mut out = vec![];
+    let mut stack: Vec<Link<T>> = root.iter().cloned().collect();
+    while let Some(node) = stack.pop() {
+        let node = node.borrow();
+        out.push(node.val.clone());
+        stack.extend(node.right.clone());
+        stack.extend(node.left.clone());
+    }
+    out
+}
+
+//@ In-order with an explicit stack: walk down the left spine, and every time we pop a node, visit
+//@ it and continue with the left spine of its right subtree.
+pub fn in_order_iterative<T: Clone>(root: &BSTNode<T>) -> Vec<T> {
+    let mut out = vec![];
+    let mut stack: Vec<Link<T>> = vec![];
+    let mut cur = root.clone();
+    loop {
+        while let Some(node) = cur {
+            cur = node.borrow().left.clone();
+            stack.push(node);
+        }
+        let Some(node) = stack.pop() else {
+            return out;
+        };
+        out.push(node.borrow().val.clone());
+        cur = node.borrow().right.clone();
+    }
+}
+
+//@ Post-order visits a node after its children. Visiting in "node, right, left" order, which is
+//@ just pre-order with the children swapped, gives exactly the reverse of that.
+fn post_order_nodes<T>(root: &BSTNode<T>) -> Vec<Link<T>> {
+    let mut out = vec![];
+    let mut stack: Vec<Link<T>> = root.iter().cloned().collect();
+    while let Some(node) = stack.pop() {
+        stack.extend(node.borrow().left.clone());
+        stack.extend(node.borrow().right.clone());
+        out.push(node);
+    }
+    out.reverse();
+    out
+}
+
+pub fn post_order<T: Clone>(root: &BSTNode<T>) -> Vec<T> {
+    post_order_nodes(root)
+        .iter()
+        .map(|node| node.borrow().val.clone())
+        .collect()
+}
+
+fn levels<T>(root: &BSTNode<T>) -> Vec<Vec<Link<T>>> {
+    let mut levels = vec![];
+    let mut level: Vec<Link<T>> = root.iter().cloned().collect();
+    while !level.is_empty() {
+        let next = level
+            .iter()
+            .flat_map(|node| {
+                let node = node.borrow();
+                [node.left.clone(), node.right.clone()]
+            })
+            .flatten()
+            .collect();
+        levels.push(level);
+        level = next;
+    }
+    levels
+}
+
+//@ Level-order (breadth first) returns the values of every level from left to right.
+pub fn level_order<T: Clone>(root: &BSTNode<T>) -> Vec<Vec<T>> {
+    levels(root)
+        .iter()
+        .map(|level| level.iter().map(|node| node.borrow().val.clone()).collect())
+        .collect()
+}
+
+//@ ## Morris Traversals
+//@ [Morris traversal](https://en.wikipedia.org/wiki/Tree_traversal#Morris_in-order_traversal_using_threading)
+//@ walks a tree in O(1) extra space, without a stack. Before going down into a left subtree, it
+//@ makes the rightmost node of that subtree (the in-order predecessor) point back up to the
+//@ current node. When the walk comes back up through that thread, we know the left subtree is
+//@ done, so we remove the thread and go right.
+//@
+//@ Every thread is removed again, so the tree is left exactly as it was. The only difference
+//@ between pre-order and in-order is whether a node is visited when its thread is made or when
+//@ it's removed.
+fn morris<T: Clone>(root: &BSTNode<T>, pre: bool) -> Vec<T> {
+    let mut out = vec![];
+    let mut cur = root.clone();
+    while let Some(node) = cur {
+        let Some(left) = node.borrow().left.clone() else {
+            out.push(node.borrow().val.clone());
+            cur = node.borrow().right.clone();
+            continue;
+        };
+
+        let mut pred = Rc::clone(&left);
+        loop {
+            let next = pred.borrow().right.clone();
+            match next {
+                Some(next) if !Rc::ptr_eq(&next, &node) => pred = next,
+                _ => break,
+            }
+        }
+
+        if pred.borrow().right.is_none() {
+            if pre {
+                out.push(node.borrow().val.clone());
+            }
+            pred.borrow_mut().right = Some(Rc::clone(&node));
+            cur = Some(left);
+        } else {
+            pred.borrow_mut().right = None;
+            if !pre {
+                out.push(node.borrow().val.clone());
+            }
+            cur = node.borrow().right.clone();
+        }
+    }
+    out
+}
+
+pub fn morris_pre_order<T: Clone>(root: &BSTNode<T>) -> Vec<T> {
+    morris(root, true)
+}
+
+pub fn morris_in_order<T: Clone>(root: &BSTNode<T>) -> Vec<T> {
+    morris(root, false)
+}
+
+//@ ## Properties
+//@ The height is the number of nodes on the longest path from the root down to a leaf, so an
+//@ empty tree has height 0.
+pub fn height<T>(root: &BSTNode<T>) -> usize {
+    levels(root).len()
+}
+
+//@ The diameter is the number of edges on the longest path between any two nodes. That path
+//@ bends at some node, and goes down as far as possible on both sides of it, so we compute the
+//@ height of every subtree bottom up, in post-order.
+pub fn diameter<T>(root: &BSTNode<T>) -> usize {
+    let mut heights: HashMap<*const RefCell<TreeNode<T>>, usize> = HashMap::new();
+    let mut best = 0;
+    for node in post_order_nodes(root) {
+        let height_of = |child: &BSTNode<T>| child.as_ref().map_or(0, |c| heights[&Rc::as_ptr(c)]);
+        let (l, r) = {
+            let node = node.borrow();
+            (height_of(&node.left), height_of(&node.right))
+        };
+        best = best.max(l + r);
+        heights.insert(Rc::as_ptr(&node), l.max(r) + 1);
+    }
+    best
+}
+
+//@ A tree is a valid binary search tree if its in-order traversal is strictly increasing, which
+//@ is equivalent to every node being larger than everything on its left and smaller than
+//@ everything on its right.
+pub fn is_valid_bst<T: Ord + Clone>(root: &BSTNode<T>) -> bool {
+    in_order_iterative(root).windows(2).all(|w| w[0] < w[1])
+}
+
+//@ Returns the path of nodes from the root to the first node holding `val`, in pre-order.
+//@ When the DFS pops a node at depth `d`, everything in the path past its parent belongs to a
+//@ subtree we're done with, so we cut the path back to `d` nodes before adding it.
+fn path_to<T: PartialEq>(root: &BSTNode<T>, val: &T) -> Option<Vec<Link<T>>> {
+    let mut path = vec![];
+    let mut stack: Vec<(Link<T>, usize)> = root.iter().map(|node| (node.clone(), 0)).collect();
+    while let Some((node, depth)) = stack.pop() {
+        path.truncate(depth);
+        path.push(Rc::clone(&node));
+        let node = node.borrow();
+        if node.val == *val {
+            return Some(path);
+        }
+        stack.extend(node.right.clone().map(|right| (right, depth + 1)));
+        stack.extend(node.left.clone().map(|left| (left, depth + 1)));
+    }
+    None
+}
+
+//@ The lowest common ancestor of two values is the deepest node that has both of them in its
+//@ subtree, where a node counts as being in its own subtree. It's the last node shared by the
+//@ paths from the root to each of them. This works on any binary tree, not just search trees,
+//@ and returns `None` if either value isn't in the tree. If a value appears more than once, its
+//@ first occurrence in pre-order is used.
+pub fn lowest_common_ancestor<T: PartialEq>(root: &BSTNode<T>, p: &T, q: &T) -> BSTNode<T> {
+    let a = path_to(root, p)?;
+    let b = path_to(root, q)?;
+    a.into_iter()
+        .zip(b)
+        .take_while(|(x, y)| Rc::ptr_eq(x, y))
+        .last()
+        .map(|(node, _)| node)
+}
+
+//@ ## Serialization
+//@ LeetCode writes trees as their level-order traversal, with `null` for a missing child and the
+//@ trailing `null`s left out: `[1,null,2,3]` is a root with only a right child, which has a
+//@ left child of its own.
+pub fn serialize<T: Display>(root: &BSTNode<T>) -> String {
+    let mut tokens = vec![];
+    let mut queue = VecDeque::from([root.clone()]);
+    while let Some(node) = queue.pop_front() {
+        match node {
+            Some(node) => {
+                let node = node.borrow();
+                tokens.push(node.val.to_string());
+                queue.push_back(node.left.clone());
+                queue.push_back(node.right.clone());
+            }
+            None => tokens.push("null".to_string()),
+        }
+    }
+    while tokens.last().is_some_and(|token| token == "null") {
+        tokens.pop();
+    }
+    format!("[{}]", tokens.join(","))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseTreeError {
+    MissingBrackets,
+    InvalidValue(String),
+    //@ There are more values than there are children left to fill.
+    TooManyValues,
+}
+
+impl Display for ParseTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseTreeError::MissingBrackets => write!(f, "a tree must be wrapped in brackets"),
+            ParseTreeError::InvalidValue(token) => write!(f, "invalid value: {token}"),
+            ParseTreeError::TooManyValues => write!(f, "more values than free children"),
+        }
+    }
+}
+
+impl std::error::Error for ParseTreeError {}
+
+//@ Builds a tree from its level-order values. Every node that is created takes the next two
+//@ values as its children, in the same order `serialize` wrote them, while `None`s take up a
+//@ slot without creating anything.
+pub fn from_level_order<T>(values: Vec<Option<T>>) -> Result<BSTNode<T>, ParseTreeError> {
+    let mut values = values.into_iter();
+    let Some(Some(val)) = values.next() else {
+        return match values.next() {
+            None => Ok(None),
+            Some(_) => Err(ParseTreeError::TooManyValues),
+        };
+    };
+    let root = Rc::new(RefCell::new(TreeNode::new(val)));
+    let mut parents = VecDeque::from([Rc::clone(&root)]);
+    let mut left = true;
+    for val in values {
+        //@ A parent stays at the front of the queue until its right child is filled in.
+        let parent = match left {
+            true => parents.front().cloned(),
+            false => parents.pop_front(),
+        };
+        let Some(parent) = parent else {
+            return Err(ParseTreeError::TooManyValues);
+        };
+        let child = val.map(|val| Rc::new(RefCell::new(TreeNode::new(val))));
+        if let Some(child) = &child {
+            parents.push_back(Rc::clone(child));
+        }
+        match left {
+            true => parent.borrow_mut().left = child,
+            false => parent.borrow_mut().right = child,
+        }
+        left = !left;
+    }
+    Ok(Some(root))
+}
+
+//@ Parses the format written by `serialize`. Whitespace around the values is ignored.
+pub fn deserialize<T: FromStr>(s: &str) -> Result<BSTNode<T>, ParseTreeError> {
+    let inner = s
+        .trim()
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .ok_or(ParseTreeError::MissingBrackets)?;
+    if inner.trim().is_empty() {
+        return Ok(None);
+    }
+    let values = inner
+        .split(',')
+        .map(str::trim)
+        .map(|token| match token {
+            "null" => Ok(None),
+            _ => token
+                .parse()
+                .map(Some)
+                .map_err(|_| ParseTreeError::InvalidValue(token.to_string())),
+        })
+        .collect::<Result<_, _>>()?;
+    from_level_order(values)
+}
+
+//@ ## Pretty Printing
+//@ Draws the tree sideways with ASCII branches, one node per line, left child first:
+//@ ```text
+//@ 1
+//@ +-- 2
+//@ |   +-- null
+//@ |   `-- 4
+//@ `-- 3
+//@ ```
+//@ A missing child is drawn as `null` when its sibling exists, so left and right children can
+//@ always be told apart.
+pub fn pretty_print<T: Display>(root: &BSTNode<T>) -> String {
+    let mut out = String::new();
+    if let Some(node) = root {
+        writeln!(out, "{}", node.borrow().val).unwrap();
+        print_children(node, "", &mut out);
+    }
+    out
+}
+
+fn print_children<T: Display>(node: &Link<T>, prefix: &str, out: &mut String) {
+    let node = node.borrow();
+    if node.left.is_none() && node.right.is_none() {
+        return;
+    }
+    for (child, last) in [(&node.left, false), (&node.right, true)] {
+        let (branch, indent) = match last {
+            true => ("`-- ", "    "),
+            false => ("+-- ", "|   "),
+        };
+        match child {
+            Some(child) => {
+                writeln!(out, "{prefix}{branch}{}", child.borrow().val).unwrap();
+                print_children(child, &format!("{prefix}{indent}"), out);
+            }
+            None => writeln!(out, "{prefix}{branch}null").unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+    use crate::{btree, trees::in_order::in_order};
+
+    #[test]
+    fn traversals() {
+        let tree = btree![1, 2, 3, 4, 5, null, 6];
+        assert_eq!(pre_order(&tree), vec![1, 2, 4, 5, 3, 6]);
+        assert_eq!(in_order_iterative(&tree), vec![4, 2, 5, 1, 3, 6]);
+        assert_eq!(post_order(&tree), vec![4, 5, 2, 6, 3, 1]);
+        assert_eq!(level_order(&tree), vec![vec![1], vec![2, 3], vec![4, 5, 6]]);
+        assert_eq!(morris_pre_order(&tree), pre_order(&tree));
+        assert_eq!(morris_in_order(&tree), in_order(&tree));
+        assert_eq!(serialize(&tree), "[1,2,3,4,5,null,6]");
+    }
+
+    #[test]
+    fn height_and_diameter() {
+        let tree = btree![1, 2, 3, 4, 5];
+        assert_eq!((height(&tree), diameter(&tree)), (3, 3));
+
+        //@ The longest path doesn't have to go through the root.
+        let tree = btree![1, 2, null, 3, 4, 5, null, null, 6, 7, null, null, 8];
+        assert_eq!((height(&tree), diameter(&tree)), (5, 6));
+
+        assert_eq!((height::<i32>(&None), diameter::<i32>(&None)), (0, 0));
+    }
+
+    #[test]
+    fn valid_bst() {
+        assert!(is_valid_bst(&btree![2, 1, 3]));
+        assert!(!is_valid_bst(&btree![5, 1, 4, null, null, 3, 6]));
+        assert!(!is_valid_bst(&btree![2, 2, 2]));
+        assert!(is_valid_bst::<i32>(&btree![]));
+    }
+
+    #[test]
+    fn lca() {
+        let tree = btree![3, 5, 1, 6, 2, 0, 8, null, null, 7, 4];
+        let lca = |p, q| lowest_common_ancestor(&tree, &p, &q).map(|node| node.borrow().val);
+        assert_eq!(lca(5, 1), Some(3));
+        assert_eq!(lca(5, 4), Some(5));
+        assert_eq!(lca(7, 8), Some(3));
+        assert_eq!(lca(6, 6), Some(6));
+        assert_eq!(lca(6, 9), None);
+    }
+
+    #[test]
+    fn serialize_roundtrip() {
+        let s = "[3,5,1,6,2,0,8,null,null,7,4]";
+        let tree: BSTNode = deserialize(s).unwrap();
+        assert_eq!(tree, btree![3, 5, 1, 6, 2, 0, 8, null, null, 7, 4]);
+        assert_eq!(serialize(&tree), s);
+
+        let words: BSTNode<String> = deserialize("[ b, a , null, c ]").unwrap();
+        assert_eq!(serialize(&words), "[b,a,null,c]");
+        assert_eq!(deserialize::<i32>("[]"), Ok(None));
+        assert_eq!(deserialize::<i32>("[null]"), Ok(None));
+    }
+
+    #[test]
+    fn deserialize_errors() {
+        use ParseTreeError::*;
+        assert_eq!(deserialize::<i32>("1,2"), Err(MissingBrackets));
+        assert_eq!(
+            deserialize::<i32>("[1,x]"),
+            Err(InvalidValue("x".to_string()))
+        );
+        assert_eq!(deserialize::<i32>("[1,null,null,2]"), Err(TooManyValues));
+        assert_eq!(deserialize::<i32>("[null,1]"), Err(TooManyValues));
+    }
+
+    #[test]
+    fn pretty() {
+        let tree = btree![1, 2, 3, null, 4, 5];
+        let expected = "\
+1
++-- 2
+|   +-- null
+|   `-- 4
+`-- 3
+    +-- 5
+    `-- null
+";
+        assert_eq!(pretty_print(&tree), expected);
+        assert_eq!(pretty_print::<i32>(&None), "");
+    }
+
+    //@ A 10,000 level deep chain would overflow the stack of recursive helpers.
+    #[test]
+    fn deep_tree() {
+        let n = 10_000;
+        let values = (1..n).flat_map(|i| [Some(i), None]);
+        let tree = from_level_order([Some(0)].into_iter().chain(values).collect()).unwrap();
+        assert_eq!(height(&tree), n as usize);
+        assert_eq!(diameter(&tree), n as usize - 1);
+        assert_eq!(pre_order(&tree), (0..n).collect::<Vec<_>>());
+        assert_eq!(morris_in_order(&tree), (0..n).rev().collect::<Vec<_>>());
+        assert_eq!(post_order(&tree), in_order_iterative(&tree));
+
+        //@ Dropping a chain this deep recurses too, so we take it apart by hand.
+        let mut cur = tree;
+        while let Some(node) = cur {
+            cur = node.borrow_mut().left.take();
+        }
+    }
+
+    //@ The recursive definitions, which the iterative versions are checked against.
+    fn reference<T: Clone>(root: &BSTNode<T>, out: &mut (Vec<T>, Vec<T>)) -> usize {
+        let Some(node) = root else { return 0 };
+        let node = node.borrow();
+        out.0.push(node.val.clone());
+        let l = reference(&node.left, out);
+        let r = reference(&node.right, out);
+        out.1.push(node.val.clone());
+        l.max(r) + 1
+    }
+
+    //@ Random trees are built from arbitrary level-order values, with the values that don't fit
+    //@ cut off.
+    fn tree<T: Clone>(mut values: Vec<Option<T>>) -> BSTNode<T> {
+        loop {
+            match from_level_order(values.clone()) {
+                Ok(tree) => return tree,
+                Err(_) => values.pop(),
+            };
+        }
+    }
+
+    #[quickcheck]
+    fn traversals_match_recursive(values: Vec<Option<u8>>) -> bool {
+        let tree = tree(values);
+        let before = serialize(&tree);
+        let mut expected = (vec![], vec![]);
+        let h = reference(&tree, &mut expected);
+
+        pre_order(&tree) == expected.0
+            && morris_pre_order(&tree) == expected.0
+            && post_order(&tree) == expected.1
+            && in_order_iterative(&tree) == in_order(&tree)
+            && morris_in_order(&tree) == in_order(&tree)
+            && level_order(&tree).concat().len() == expected.0.len()
+            && height(&tree) == h
+            && serialize(&tree) == before
+            && serialize(&deserialize::<u8>(&before).unwrap()) == before
+    }
+
+    //@ The diameter is checked against the longest distance between any pair of nodes, using the
+    //@ depths of their lowest common ancestor.
+    #[quickcheck]
+    fn diameter_matches_pairs(values: Vec<Option<u8>>) -> bool {
+        let values: Vec<Option<usize>> = values
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| v.map(|_| i))
+            .collect();
+        let tree = tree(values);
+        let nodes = pre_order(&tree);
+        let depth = |v: &usize| path_to(&tree, v).unwrap().len() - 1;
+        let mut best = 0;
+        for a in &nodes {
+            for b in &nodes {
+                let lca = lowest_common_ancestor(&tree, a, b).unwrap();
+                let lca = depth(&lca.borrow().val);
+                best = best.max(depth(a) + depth(b) - 2 * lca);
+            }
+        }
+        diameter(&tree) == best
+    }
+
+    #[quickcheck]
+    fn bst_matches_sorted(values: Vec<Option<u8>>) -> bool {
+        let tree = tree(values);
+        let in_order = in_order(&tree);
+        let sorted = in_order.windows(2).all(|w| w[0] < w[1]);
+        is_valid_bst(&tree) == sorted
+    }
+}
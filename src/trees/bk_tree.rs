@@ -11,6 +11,11 @@ struct Node<T> {
     children: Vec<(usize, Node<T>)>,
 }
 
+//@ A small dictionary of similar words, which the tests and the autocomplete example index.
+pub const DICTIONARY: &[&str] = &[
+    "book", "books", "boo", "boon", "cook", "cake", "cape", "cart",
+];
+
 //@ A definition of a distance function.
 pub type DistanceFn<T> = dyn Fn(&T, &T) -> usize;
 
@@ -123,11 +128,8 @@ mod tests {
     #[test]
     fn levenshtein_distance_test() {
         let mut bk = BkTree::new(levenshtein_distance);
-        let words = vec![
-            "book", "books", "boo", "boon", "cook", "cake", "cape", "cart",
-        ];
-        for word in words {
-            bk.insert(word);
+        for word in DICTIONARY {
+            bk.insert(*word);
         }
         let (words, dists): (Vec<&str>, Vec<usize>) = bk.find(&"bo", 2).into_iter().unzip();
         assert_eq!(words, ["book", "boo", "boon"]);
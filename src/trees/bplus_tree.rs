@@ -0,0 +1,543 @@
+use std::{
+    mem,
+    ops::{Bound, RangeBounds},
+};
+
+use super::btree::{before_start, past_end};
+
+//@ A [B+-tree](https://en.wikipedia.org/wiki/B%2B_tree) is a [B-tree](./btree.html) that keeps
+//@ all of its entries in the leaves. Internal nodes only hold copies of keys, which guide the
+//@ search, and every leaf links to the next one, so a range scan finds its first entry in
+//@ O(log(n)) time and then just walks along the leaves without ever going back up the tree.
+//@ That's why databases and file systems index with B+-trees.
+//@
+//@ The leaves link to each other, which doesn't fit Rust's ownership model when every node is a
+//@ separate `Box`, so like the pairing and Fibonacci heaps, the nodes live in an arena and link to
+//@ each other by index. Freed nodes are kept on a free list until they're reused.
+//@
+//@ `B` is the maximum number of children of an internal node and also one more than the maximum
+//@ number of entries in a leaf. Every node other than the root is at least half full.
+pub struct BPlusTree<K, V, const B: usize = 16> {
+    nodes: Vec<Node<K, V>>,
+    free: Vec<usize>,
+    root: usize,
+    len: usize,
+}
+
+//@ An internal node with `k` keys has `k + 1` children, and `keys[i]` separates them: every key
+//@ in `children[i]` is smaller, and every key in `children[i + 1]` is at least as large.
+//@ Separators stay put when the key they were copied from is removed, so they don't have to be
+//@ keys that are still in the tree, they only have to keep separating.
+enum Node<K, V> {
+    Internal {
+        keys: Vec<K>,
+        children: Vec<usize>,
+    },
+    Leaf {
+        keys: Vec<K>,
+        vals: Vec<V>,
+        next: Option<usize>,
+    },
+}
+
+impl<K, V> Node<K, V> {
+    fn empty_leaf() -> Self {
+        Node::Leaf {
+            keys: vec![],
+            vals: vec![],
+            next: None,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Node::Internal { keys, .. } | Node::Leaf { keys, .. } => keys.len(),
+        }
+    }
+}
+
+impl<K: Ord + Clone, V, const B: usize> Default for BPlusTree<K, V, B> {
+    fn default() -> Self {
+        BPlusTree::new()
+    }
+}
+
+//@ The leftmost leaf is always node 0: a split keeps the left half where it is and creates a new
+//@ node for the right half, and a merge keeps the left node and frees the right one.
+const FIRST_LEAF: usize = 0;
+
+impl<K: Ord + Clone, V, const B: usize> BPlusTree<K, V, B> {
+    const MIN: usize = B.div_ceil(2) - 1;
+
+    pub fn new() -> Self {
+        const { assert!(B >= 3, "a B+-tree needs an order of at least 3") };
+        BPlusTree {
+            nodes: vec![Node::empty_leaf()],
+            free: vec![],
+            root: FIRST_LEAF,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    //@ Every search goes all the way down to a leaf, even if the key shows up as a separator on
+    //@ the way, because only leaves hold values.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut id = self.root;
+        loop {
+            match &self.nodes[id] {
+                Node::Internal { keys, children } => {
+                    id = children[keys.partition_point(|sep| sep <= key)]
+                }
+                Node::Leaf { keys, vals, .. } => {
+                    return keys.binary_search(key).ok().map(|i| &vals[i])
+                }
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    //@ Returns the old value if the key was already there.
+    pub fn insert(&mut self, key: K, val: V) -> Option<V> {
+        let (old, split) = self.insert_at(self.root, key, val);
+        if old.is_none() {
+            self.len += 1;
+        }
+        if let Some((sep, right)) = split {
+            self.root = self.alloc(Node::Internal {
+                keys: vec![sep],
+                children: vec![self.root, right],
+            });
+        }
+        old
+    }
+
+    //@ Inserts into the subtree under `id`. If that node overflows, it's split, and the new right
+    //@ half is returned along with the separator the parent needs for it.
+    fn insert_at(&mut self, id: usize, key: K, val: V) -> (Option<V>, Option<(K, usize)>) {
+        let (i, child) = match &mut self.nodes[id] {
+            Node::Leaf { keys, vals, .. } => {
+                match keys.binary_search(&key) {
+                    Ok(i) => return (Some(mem::replace(&mut vals[i], val)), None),
+                    Err(i) => {
+                        keys.insert(i, key);
+                        vals.insert(i, val);
+                    }
+                }
+                let full = keys.len() == B;
+                return (None, full.then(|| self.split(id)));
+            }
+            Node::Internal { keys, children } => {
+                let i = keys.partition_point(|sep| *sep <= key);
+                (i, children[i])
+            }
+        };
+        let (old, split) = self.insert_at(child, key, val);
+        let Some((sep, right)) = split else {
+            return (old, None);
+        };
+        let Node::Internal { keys, children } = &mut self.nodes[id] else {
+            unreachable!("the node has children")
+        };
+        keys.insert(i, sep);
+        children.insert(i + 1, right);
+        let full = keys.len() == B;
+        (old, full.then(|| self.split(id)))
+    }
+
+    //@ A full leaf keeps its first `B / 2` entries, and the first key of the other half is copied
+    //@ up as the separator. A full internal node moves its middle key up instead, like a B-tree
+    //@ node does.
+    fn split(&mut self, id: usize) -> (K, usize) {
+        let mid = B / 2;
+        let right_id = self.free.last().copied().unwrap_or(self.nodes.len());
+        let (sep, right) = match &mut self.nodes[id] {
+            Node::Leaf { keys, vals, next } => {
+                let keys = keys.split_off(mid);
+                let sep = keys[0].clone();
+                let vals = vals.split_off(mid);
+                let next = next.replace(right_id);
+                (sep, Node::Leaf { keys, vals, next })
+            }
+            Node::Internal { keys, children } => {
+                let right = Node::Internal {
+                    keys: keys.split_off(mid + 1),
+                    children: children.split_off(mid + 1),
+                };
+                (keys.pop().expect("the node is full"), right)
+            }
+        };
+        assert_eq!(self.alloc(right), right_id);
+        (sep, right_id)
+    }
+
+    fn alloc(&mut self, node: Node<K, V>) -> usize {
+        match self.free.pop() {
+            Some(id) => {
+                self.nodes[id] = node;
+                id
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    //@ Takes a node out of the arena, leaving an empty leaf in its place, so that we can work on
+    //@ it while also changing its siblings.
+    fn take(&mut self, id: usize) -> Node<K, V> {
+        mem::replace(&mut self.nodes[id], Node::empty_leaf())
+    }
+
+    //@ Like in the B-tree, a node that drops below `MIN` keys borrows from a sibling or gets
+    //@ merged with one, and when the root is left with a single child, that child becomes the
+    //@ root.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let val = self.remove_at(self.root, key)?;
+        self.len -= 1;
+        if let Node::Internal { keys, children } = &self.nodes[self.root] {
+            if keys.is_empty() {
+                let child = children[0];
+                self.take(self.root);
+                self.free.push(self.root);
+                self.root = child;
+            }
+        }
+        Some(val)
+    }
+
+    fn remove_at(&mut self, id: usize, key: &K) -> Option<V> {
+        let i = match &mut self.nodes[id] {
+            Node::Leaf { keys, vals, .. } => {
+                let i = keys.binary_search(key).ok()?;
+                keys.remove(i);
+                return Some(vals.remove(i));
+            }
+            Node::Internal { keys, .. } => keys.partition_point(|sep| sep <= key),
+        };
+        let val = self.remove_at(self.children(id)[i], key)?;
+        self.fix_child(id, i);
+        Some(val)
+    }
+
+    fn children(&self, id: usize) -> &[usize] {
+        match &self.nodes[id] {
+            Node::Internal { children, .. } => children,
+            Node::Leaf { .. } => &[],
+        }
+    }
+
+    //@ Borrowing an entry from a sibling leaf moves it across and copies the new first key of the
+    //@ right-hand leaf into the separator. Borrowing between internal nodes rotates a key through
+    //@ the separator, like in a B-tree. Merging two leaves drops their separator and unlinks the
+    //@ right leaf, while merging two internal nodes pulls the separator down between their keys.
+    fn fix_child(&mut self, parent: usize, i: usize) {
+        let children = self.children(parent);
+        if self.nodes[children[i]].len() >= Self::MIN {
+            return;
+        }
+        let spare = |j: usize| children.get(j).map(|&c| self.nodes[c].len() > Self::MIN);
+        let (l, borrow) = if i > 0 && spare(i - 1) == Some(true) {
+            (i - 1, true)
+        } else if spare(i + 1) == Some(true) {
+            (i, true)
+        } else if i > 0 {
+            (i - 1, false)
+        } else {
+            (i, false)
+        };
+        let (left_id, right_id) = (children[l], children[l + 1]);
+
+        let mut parent_node = self.take(parent);
+        let Node::Internal {
+            keys: seps,
+            children,
+        } = &mut parent_node
+        else {
+            unreachable!("the parent has children")
+        };
+        let mut left = self.take(left_id);
+        let mut right = self.take(right_id);
+        match (&mut left, &mut right) {
+            (
+                Node::Leaf {
+                    keys: lk,
+                    vals: lv,
+                    next,
+                },
+                Node::Leaf {
+                    keys: rk,
+                    vals: rv,
+                    next: rnext,
+                },
+            ) => {
+                if !borrow {
+                    lk.append(rk);
+                    lv.append(rv);
+                    *next = *rnext;
+                } else if l == i {
+                    lk.push(rk.remove(0));
+                    lv.push(rv.remove(0));
+                    seps[l] = rk[0].clone();
+                } else {
+                    rk.insert(0, lk.pop().expect("the sibling has keys to spare"));
+                    rv.insert(0, lv.pop().expect("the sibling has keys to spare"));
+                    seps[l] = rk[0].clone();
+                }
+            }
+            (
+                Node::Internal {
+                    keys: lk,
+                    children: lc,
+                },
+                Node::Internal {
+                    keys: rk,
+                    children: rc,
+                },
+            ) => {
+                if !borrow {
+                    lk.push(seps[l].clone());
+                    lk.append(rk);
+                    lc.append(rc);
+                } else if l == i {
+                    lk.push(mem::replace(&mut seps[l], rk.remove(0)));
+                    lc.push(rc.remove(0));
+                } else {
+                    let key = lk.pop().expect("the sibling has keys to spare");
+                    rk.insert(0, mem::replace(&mut seps[l], key));
+                    rc.insert(0, lc.pop().expect("the sibling has keys to spare"));
+                }
+            }
+            _ => unreachable!("siblings are on the same level"),
+        }
+        if !borrow {
+            seps.remove(l);
+            children.remove(l + 1);
+            self.free.push(right_id);
+        } else {
+            self.nodes[right_id] = right;
+        }
+        self.nodes[left_id] = left;
+        self.nodes[parent] = parent_node;
+    }
+
+    //@ ## Range Scans
+    //@ Seeking to the start of the range goes down to the leaf where the first key that isn't
+    //@ before it would be, and from there the scan just follows the `next` links.
+    pub fn range<'a, R: RangeBounds<K> + 'a>(
+        &'a self,
+        range: R,
+    ) -> impl Iterator<Item = (&'a K, &'a V)> + 'a {
+        let mut id = self.root;
+        let pos = loop {
+            match &self.nodes[id] {
+                Node::Internal { keys, children } => {
+                    id = children[match range.start_bound() {
+                        Bound::Included(start) | Bound::Excluded(start) => {
+                            keys.partition_point(|sep| sep <= start)
+                        }
+                        Bound::Unbounded => 0,
+                    }]
+                }
+                Node::Leaf { keys, .. } => {
+                    break keys.partition_point(|k| before_start(range.start_bound(), k))
+                }
+            }
+        };
+        let iter = Iter {
+            nodes: &self.nodes,
+            leaf: Some(id),
+            pos,
+        };
+        iter.take_while(move |(k, _)| !past_end(range.end_bound(), k))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        Iter {
+            nodes: &self.nodes,
+            leaf: Some(FIRST_LEAF),
+            pos: 0,
+        }
+    }
+}
+
+struct Iter<'a, K, V> {
+    nodes: &'a [Node<K, V>],
+    leaf: Option<usize>,
+    pos: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Node::Leaf { keys, vals, next } = &self.nodes[self.leaf?] else {
+                unreachable!("leaves only link to leaves")
+            };
+            if self.pos < keys.len() {
+                self.pos += 1;
+                return Some((&keys[self.pos - 1], &vals[self.pos - 1]));
+            }
+            self.leaf = *next;
+            self.pos = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    #[test]
+    fn ex1() {
+        let mut tree: BPlusTree<i32, &str, 3> = BPlusTree::new();
+        for (k, v) in [
+            (5, "five"),
+            (1, "one"),
+            (3, "three"),
+            (9, "nine"),
+            (7, "seven"),
+        ] {
+            assert_eq!(tree.insert(k, v), None);
+        }
+        assert_eq!(tree.insert(3, "drei"), Some("three"));
+        assert_eq!(tree.len(), 5);
+        assert_eq!(tree.get(&3), Some(&"drei"));
+        assert!(!tree.contains_key(&4));
+
+        let keys: Vec<_> = tree.range(2..=7).map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![3, 5, 7]);
+
+        assert_eq!(tree.remove(&5), Some("five"));
+        assert_eq!(tree.remove(&5), None);
+        let keys: Vec<_> = tree.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![1, 3, 7, 9]);
+    }
+
+    //@ Checks the node sizes, that every key is within the bounds its ancestors' separators
+    //@ give it, and that all leaves are on the same level. Returns the depth and appends the
+    //@ leaves in order, so that the caller can compare them with the linked list.
+    fn check<K: Ord + Clone, V, const B: usize>(
+        tree: &BPlusTree<K, V, B>,
+        id: usize,
+        lo: Option<&K>,
+        hi: Option<&K>,
+        leaves: &mut Vec<usize>,
+    ) -> usize {
+        let node = &tree.nodes[id];
+        assert!(node.len() < B);
+        assert!(id == tree.root || node.len() >= BPlusTree::<K, V, B>::MIN);
+        match node {
+            Node::Leaf { keys, vals, .. } => {
+                assert_eq!(keys.len(), vals.len());
+                assert!(keys.windows(2).all(|w| w[0] < w[1]));
+                assert!(keys.iter().all(|k| lo.is_none_or(|lo| k >= lo)));
+                assert!(keys.iter().all(|k| hi.is_none_or(|hi| k < hi)));
+                leaves.push(id);
+                1
+            }
+            Node::Internal { keys, children } => {
+                assert_eq!(children.len(), keys.len() + 1);
+                assert!(keys.windows(2).all(|w| w[0] < w[1]));
+                let depths: Vec<_> = children
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &c)| {
+                        let lo = if i > 0 { Some(&keys[i - 1]) } else { lo };
+                        let hi = keys.get(i).or(hi);
+                        check(tree, c, lo, hi, leaves)
+                    })
+                    .collect();
+                assert!(depths.windows(2).all(|w| w[0] == w[1]));
+                depths[0] + 1
+            }
+        }
+    }
+
+    fn check_links<K: Ord + Clone, V, const B: usize>(tree: &BPlusTree<K, V, B>) {
+        let mut leaves = vec![];
+        check(tree, tree.root, None, None, &mut leaves);
+        let mut linked = vec![];
+        let mut leaf = Some(FIRST_LEAF);
+        while let Some(id) = leaf {
+            linked.push(id);
+            let Node::Leaf { next, .. } = &tree.nodes[id] else {
+                panic!("{id} is not a leaf");
+            };
+            leaf = *next;
+        }
+        assert_eq!(leaves, linked);
+    }
+
+    //@ Applies the same inserts (`true`) and removes (`false`) to a `BPlusTree` and a `BTreeMap`,
+    //@ checking the invariants and the leaf links after every operation.
+    fn matches_std<const B: usize>(ops: Vec<(bool, u8)>) -> bool {
+        let mut tree: BPlusTree<u8, usize, B> = BPlusTree::new();
+        let mut expected = BTreeMap::new();
+        for (i, (insert, key)) in ops.into_iter().enumerate() {
+            let key = key % 64;
+            let same = if insert {
+                tree.insert(key, i) == expected.insert(key, i)
+            } else {
+                tree.remove(&key) == expected.remove(&key)
+            };
+            check_links(&tree);
+            if !same || tree.len() != expected.len() {
+                return false;
+            }
+        }
+        tree.iter().eq(expected.iter()) && (0..64).all(|k| tree.get(&k) == expected.get(&k))
+    }
+
+    #[quickcheck]
+    fn order_3_matches_std(ops: Vec<(bool, u8)>) -> bool {
+        matches_std::<3>(ops)
+    }
+
+    #[quickcheck]
+    fn order_4_matches_std(ops: Vec<(bool, u8)>) -> bool {
+        matches_std::<4>(ops)
+    }
+
+    #[quickcheck]
+    fn order_7_matches_std(ops: Vec<(bool, u8)>) -> bool {
+        matches_std::<7>(ops)
+    }
+
+    #[quickcheck]
+    fn range_matches_std(keys: Vec<u16>, removed: Vec<u16>, lo: u16, hi: u16) -> bool {
+        let (lo, hi) = (lo.min(hi), lo.max(hi));
+        let mut tree: BPlusTree<u16, (), 4> = BPlusTree::new();
+        let mut expected = BTreeMap::new();
+        for &k in &keys {
+            tree.insert(k, ());
+            expected.insert(k, ());
+        }
+        for k in &removed {
+            tree.remove(k);
+            expected.remove(k);
+        }
+        let bounds = (Bound::Excluded(lo), Bound::Included(hi));
+        tree.range(lo..hi).eq(expected.range(lo..hi))
+            && tree.range(lo..=hi).eq(expected.range(lo..=hi))
+            && tree.range(bounds).eq(expected.range(bounds))
+            && tree.range(..hi).eq(expected.range(..hi))
+    }
+}
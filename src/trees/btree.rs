@@ -0,0 +1,545 @@
+use std::{
+    mem,
+    ops::{Bound, RangeBounds},
+};
+
+//@ A [B-tree](https://en.wikipedia.org/wiki/B-tree) is a balanced search tree whose nodes hold
+//@ up to `B - 1` sorted keys and `B` children, instead of the one key and two children of a
+//@ binary tree. `B` is the order, or branching factor, and is a const generic so that every
+//@ order gets its own monomorphized code.
+//@
+//@ - `get`, `insert` and `remove` take O(log(n)) time.
+//@ - `range` takes O(log(n) + k) time to yield `k` entries.
+//@
+//@ Asymptotically that's no better than an AVL tree, but the constant factors are very different.
+//@ An AVL tree is about `1.44 * log2(n)` levels deep, and every level is a separate allocation
+//@ somewhere on the heap, so a lookup in a large tree pays a cache miss for almost every
+//@ comparison. A B-tree is only `log_B(n)` levels deep, and the keys of a node sit next to each
+//@ other in memory, so a binary search within a node mostly hits cache lines that have already
+//@ been loaded. The benchmarks at the end of the file show how much that matters.
+//@
+//@ Every node other than the root holds at least `MIN = ceil(B / 2) - 1` keys, and all leaves are
+//@ on the same level. Insertion keeps that true by splitting a node that overflows to `B` keys
+//@ around its median, which moves the median up into the parent. That's also the only way the
+//@ tree gets deeper: when the root splits.
+pub struct BTree<K, V, const B: usize = 16> {
+    root: Node<K, V, B>,
+    len: usize,
+}
+
+//@ A leaf has no children, and an internal node with `k` keys has `k + 1` children, where all
+//@ keys in `children[i]` sort between `keys[i - 1]` and `keys[i]`.
+struct Node<K, V, const B: usize> {
+    keys: Vec<K>,
+    vals: Vec<V>,
+    children: Vec<Node<K, V, B>>,
+}
+
+impl<K: Ord, V, const B: usize> Default for BTree<K, V, B> {
+    fn default() -> Self {
+        BTree::new()
+    }
+}
+
+impl<K: Ord, V, const B: usize> BTree<K, V, B> {
+    pub fn new() -> Self {
+        const { assert!(B >= 3, "a B-tree needs an order of at least 3") };
+        BTree {
+            root: Node::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    //@ Leaves have no children, so `children.get(i)` is what ends the search.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut node = &self.root;
+        loop {
+            match node.keys.binary_search(key) {
+                Ok(i) => return Some(&node.vals[i]),
+                Err(i) => node = node.children.get(i)?,
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    //@ Returns the old value if the key was already there.
+    pub fn insert(&mut self, key: K, val: V) -> Option<V> {
+        let old = self.root.insert(key, val);
+        if old.is_none() {
+            self.len += 1;
+        }
+        if self.root.keys.len() == B {
+            let root = mem::replace(&mut self.root, Node::new());
+            self.root.children.push(root);
+            self.root.split_child(0);
+        }
+        old
+    }
+
+    //@ Removal can leave the root without keys but with a single child, which then becomes the
+    //@ new root. That's the only way the tree gets shallower.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (_, val) = self.root.remove(key)?;
+        self.len -= 1;
+        if self.root.keys.is_empty() {
+            if let Some(child) = self.root.children.pop() {
+                self.root = child;
+            }
+        }
+        Some(val)
+    }
+
+    //@ ## Range Scans
+    //@ The iterator keeps the path from the root to the next entry on a stack, with the index of
+    //@ the next key to yield in each node, so it takes O(log(n)) extra space. Seeking to the start
+    //@ of the range pushes the first key that isn't before it on every level.
+    pub fn range<'a, R: RangeBounds<K> + 'a>(
+        &'a self,
+        range: R,
+    ) -> impl Iterator<Item = (&'a K, &'a V)> + 'a {
+        let mut iter = Iter { stack: vec![] };
+        let mut node = &self.root;
+        loop {
+            let i = node
+                .keys
+                .partition_point(|k| before_start(range.start_bound(), k));
+            iter.stack.push((node, i));
+            match node.children.get(i) {
+                Some(child) => node = child,
+                None => break,
+            }
+        }
+        iter.take_while(move |(k, _)| !past_end(range.end_bound(), k))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.range(..)
+    }
+}
+
+//@ Whether `key` comes before the start of a range, or after its end. The B+-tree uses these too.
+pub(super) fn before_start<K: Ord>(start: Bound<&K>, key: &K) -> bool {
+    match start {
+        Bound::Included(start) => key < start,
+        Bound::Excluded(start) => key <= start,
+        Bound::Unbounded => false,
+    }
+}
+
+pub(super) fn past_end<K: Ord>(end: Bound<&K>, key: &K) -> bool {
+    match end {
+        Bound::Included(end) => key > end,
+        Bound::Excluded(end) => key >= end,
+        Bound::Unbounded => false,
+    }
+}
+
+struct Iter<'a, K, V, const B: usize> {
+    stack: Vec<(&'a Node<K, V, B>, usize)>,
+}
+
+//@ After yielding `keys[i]`, the next entry is the leftmost one in `children[i + 1]`, so we push
+//@ the left spine of that subtree. A node whose keys are all used up is popped, which brings us
+//@ back to its parent.
+impl<'a, K, V, const B: usize> Iterator for Iter<'a, K, V, B> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (node, next) = self.stack.last_mut()?;
+            let (node, i) = (*node, *next);
+            *next += 1;
+            if i == node.keys.len() {
+                self.stack.pop();
+                continue;
+            }
+            let mut child = node.children.get(i + 1);
+            while let Some(next) = child {
+                self.stack.push((next, 0));
+                child = next.children.first();
+            }
+            return Some((&node.keys[i], &node.vals[i]));
+        }
+    }
+}
+
+impl<K: Ord, V, const B: usize> Node<K, V, B> {
+    const MIN: usize = B.div_ceil(2) - 1;
+
+    fn new() -> Self {
+        Node {
+            keys: Vec::with_capacity(B),
+            vals: Vec::with_capacity(B),
+            children: vec![],
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    //@ Inserts into the subtree and splits the child we went through if it overflowed. The node
+    //@ itself may be left with `B` keys, which its parent (or `BTree::insert`, for the root)
+    //@ takes care of.
+    fn insert(&mut self, key: K, val: V) -> Option<V> {
+        match self.keys.binary_search(&key) {
+            Ok(i) => Some(mem::replace(&mut self.vals[i], val)),
+            Err(i) if self.is_leaf() => {
+                self.keys.insert(i, key);
+                self.vals.insert(i, val);
+                None
+            }
+            Err(i) => {
+                let old = self.children[i].insert(key, val);
+                if self.children[i].keys.len() == B {
+                    self.split_child(i);
+                }
+                old
+            }
+        }
+    }
+
+    //@ Splits a child with `B` keys into one with `B / 2` keys and one with `ceil(B / 2) - 1`
+    //@ keys, both of which are at least `MIN`, and moves the key in between up into this node.
+    fn split_child(&mut self, i: usize) {
+        let child = &mut self.children[i];
+        let mid = B / 2;
+        let mut right = Node::new();
+        right.keys.extend(child.keys.drain(mid + 1..));
+        right.vals.extend(child.vals.drain(mid + 1..));
+        if !child.is_leaf() {
+            right.children = child.children.split_off(mid + 1);
+        }
+        let key = child.keys.pop().expect("the child is full");
+        let val = child.vals.pop().expect("the child is full");
+        self.keys.insert(i, key);
+        self.vals.insert(i, val);
+        self.children.insert(i + 1, right);
+    }
+
+    //@ Removing a key from an internal node would leave its two neighbouring subtrees without a
+    //@ separator, so it's swapped with its predecessor, the largest key of the left subtree,
+    //@ which is always in a leaf. Either way, a child we removed something from may now be one
+    //@ key short, and gets fixed on the way back up.
+    fn remove(&mut self, key: &K) -> Option<(K, V)> {
+        let i = match self.keys.binary_search(key) {
+            Ok(i) if self.is_leaf() => return Some((self.keys.remove(i), self.vals.remove(i))),
+            Ok(i) => {
+                let (k, v) = self.children[i].pop_last();
+                let removed = (
+                    mem::replace(&mut self.keys[i], k),
+                    mem::replace(&mut self.vals[i], v),
+                );
+                self.fix_child(i);
+                return Some(removed);
+            }
+            Err(_) if self.is_leaf() => return None,
+            Err(i) => i,
+        };
+        let removed = self.children[i].remove(key)?;
+        self.fix_child(i);
+        Some(removed)
+    }
+
+    fn pop_last(&mut self) -> (K, V) {
+        if self.is_leaf() {
+            let key = self.keys.pop().expect("non-root nodes are never empty");
+            let val = self.vals.pop().expect("non-root nodes are never empty");
+            return (key, val);
+        }
+        let last = self.children.len() - 1;
+        let entry = self.children[last].pop_last();
+        self.fix_child(last);
+        entry
+    }
+
+    //@ A child with fewer than `MIN` keys borrows one from a sibling that can spare it, by
+    //@ rotating it through the separator in this node. If neither sibling can, both siblings
+    //@ have exactly `MIN` keys, so the child can be merged with one of them and the separator
+    //@ between them into a node of at most `2 * MIN <= B - 1` keys.
+    fn fix_child(&mut self, i: usize) {
+        if self.children[i].keys.len() >= Self::MIN {
+            return;
+        }
+        if i > 0 && self.children[i - 1].keys.len() > Self::MIN {
+            let (left, right) = self.children.split_at_mut(i);
+            let (left, child) = (&mut left[i - 1], &mut right[0]);
+            let key = left.keys.pop().expect("the sibling has keys to spare");
+            let val = left.vals.pop().expect("the sibling has keys to spare");
+            child
+                .keys
+                .insert(0, mem::replace(&mut self.keys[i - 1], key));
+            child
+                .vals
+                .insert(0, mem::replace(&mut self.vals[i - 1], val));
+            if let Some(grandchild) = left.children.pop() {
+                child.children.insert(0, grandchild);
+            }
+        } else if i + 1 < self.children.len() && self.children[i + 1].keys.len() > Self::MIN {
+            let (left, right) = self.children.split_at_mut(i + 1);
+            let (child, right) = (&mut left[i], &mut right[0]);
+            child
+                .keys
+                .push(mem::replace(&mut self.keys[i], right.keys.remove(0)));
+            child
+                .vals
+                .push(mem::replace(&mut self.vals[i], right.vals.remove(0)));
+            if !right.is_leaf() {
+                child.children.push(right.children.remove(0));
+            }
+        } else {
+            let i = if i > 0 { i - 1 } else { i };
+            let right = self.children.remove(i + 1);
+            let left = &mut self.children[i];
+            left.keys.push(self.keys.remove(i));
+            left.vals.push(self.vals.remove(i));
+            left.keys.extend(right.keys);
+            left.vals.extend(right.vals);
+            left.children.extend(right.children);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use quickcheck_macros::quickcheck;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use test::{black_box, Bencher};
+
+    use super::*;
+    use crate::trees::{avl::AVLTree, bplus_tree::BPlusTree};
+
+    #[test]
+    fn ex1() {
+        let mut tree: BTree<i32, &str, 3> = BTree::new();
+        for (k, v) in [
+            (5, "five"),
+            (1, "one"),
+            (3, "three"),
+            (9, "nine"),
+            (7, "seven"),
+        ] {
+            assert_eq!(tree.insert(k, v), None);
+        }
+        assert_eq!(tree.insert(3, "drei"), Some("three"));
+        assert_eq!(tree.len(), 5);
+        assert_eq!(tree.get(&3), Some(&"drei"));
+        assert!(!tree.contains_key(&4));
+
+        let keys: Vec<_> = tree.range(2..=7).map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![3, 5, 7]);
+        let keys: Vec<_> = tree
+            .range((Bound::Excluded(5), Bound::Unbounded))
+            .map(|(k, _)| *k)
+            .collect();
+        assert_eq!(keys, vec![7, 9]);
+
+        assert_eq!(tree.remove(&5), Some("five"));
+        assert_eq!(tree.remove(&5), None);
+        let keys: Vec<_> = tree.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![1, 3, 7, 9]);
+    }
+
+    //@ Checks the key counts, the ordering and that every leaf is on the same level, and returns
+    //@ the depth of the leaves.
+    fn check<K: Ord, V, const B: usize>(node: &Node<K, V, B>, is_root: bool) -> usize {
+        assert!(node.keys.len() < B);
+        assert!(is_root || node.keys.len() >= Node::<K, V, B>::MIN);
+        assert_eq!(node.keys.len(), node.vals.len());
+        assert!(node.keys.windows(2).all(|w| w[0] < w[1]));
+        if node.is_leaf() {
+            return 1;
+        }
+        assert_eq!(node.children.len(), node.keys.len() + 1);
+        let depths: Vec<_> = node.children.iter().map(|c| check(c, false)).collect();
+        assert!(depths.windows(2).all(|w| w[0] == w[1]));
+        for (i, key) in node.keys.iter().enumerate() {
+            assert!(node.children[i].keys.iter().all(|k| k < key));
+            assert!(node.children[i + 1].keys.iter().all(|k| k > key));
+        }
+        depths[0] + 1
+    }
+
+    //@ Applies the same inserts (`true`) and removes (`false`) to a `BTree` and to the standard
+    //@ library's `BTreeMap`, checking the invariants after every operation. Keys are kept small
+    //@ so that removes actually hit something.
+    fn matches_std<const B: usize>(ops: Vec<(bool, u8)>) -> bool {
+        let mut tree: BTree<u8, usize, B> = BTree::new();
+        let mut expected = BTreeMap::new();
+        for (i, (insert, key)) in ops.into_iter().enumerate() {
+            let key = key % 64;
+            let same = if insert {
+                tree.insert(key, i) == expected.insert(key, i)
+            } else {
+                tree.remove(&key) == expected.remove(&key)
+            };
+            check(&tree.root, true);
+            if !same || tree.len() != expected.len() {
+                return false;
+            }
+        }
+        tree.iter().eq(expected.iter()) && (0..64).all(|k| tree.get(&k) == expected.get(&k))
+    }
+
+    #[quickcheck]
+    fn order_3_matches_std(ops: Vec<(bool, u8)>) -> bool {
+        matches_std::<3>(ops)
+    }
+
+    #[quickcheck]
+    fn order_4_matches_std(ops: Vec<(bool, u8)>) -> bool {
+        matches_std::<4>(ops)
+    }
+
+    #[quickcheck]
+    fn order_7_matches_std(ops: Vec<(bool, u8)>) -> bool {
+        matches_std::<7>(ops)
+    }
+
+    #[quickcheck]
+    fn range_matches_std(keys: Vec<u16>, lo: u16, hi: u16) -> bool {
+        let (lo, hi) = (lo.min(hi), lo.max(hi));
+        let tree: BTree<u16, (), 4> = keys.iter().fold(BTree::new(), |mut tree, k| {
+            tree.insert(*k, ());
+            tree
+        });
+        let expected: BTreeMap<u16, ()> = keys.iter().map(|k| (*k, ())).collect();
+        let bounds = (Bound::Excluded(lo), Bound::Included(hi));
+        tree.range(lo..hi).eq(expected.range(lo..hi))
+            && tree.range(lo..=hi).eq(expected.range(lo..=hi))
+            && tree.range(bounds).eq(expected.range(bounds))
+            && tree.range(..hi).eq(expected.range(..hi))
+    }
+
+    //@ ## Benchmarks
+    //@ All benchmarks work on the same 256k random `u64` keys, which take a few megabytes in any
+    //@ of these trees: more than fits in L2 cache.
+    const N: usize = 1 << 18;
+
+    fn keys() -> Vec<u64> {
+        let mut rng = StdRng::seed_from_u64(39);
+        (0..N).map(|_| rng.gen()).collect()
+    }
+
+    fn btree<const B: usize>(keys: &[u64]) -> BTree<u64, u64, B> {
+        let mut tree = BTree::new();
+        for &k in keys {
+            tree.insert(k, k);
+        }
+        tree
+    }
+
+    //@ Lookups of 10,000 keys that are in the tree, in random order. An order of 3 is slower
+    //@ than the AVL tree: the tree isn't much shallower, and every node is three separate
+    //@ allocations, for its keys, values and children. From there, a larger fanout means fewer
+    //@ levels and fewer cache misses, and an order of 64 is about three times as fast as the AVL
+    //@ tree. The B+-tree always has to go down to a leaf and goes through the arena on every
+    //@ level, which costs it a little against the B-tree of the same order:
+    //@ `bench_lookup_avl       ... bench: 3,368,170 ns/iter (+/- 1,408,288)`
+    //@ `bench_lookup_btree_3   ... bench: 4,574,626 ns/iter (+/- 1,267,978)`
+    //@ `bench_lookup_btree_16  ... bench: 1,544,619 ns/iter (+/- 947,455)`
+    //@ `bench_lookup_btree_64  ... bench: 1,163,651 ns/iter (+/- 490,974)`
+    //@ `bench_lookup_bplus_16  ... bench: 2,442,665 ns/iter (+/- 1,288,601)`
+    fn bench_lookup(b: &mut Bencher, contains: impl Fn(&u64) -> bool) {
+        let keys = keys();
+        b.iter(|| {
+            keys[..10_000]
+                .iter()
+                .filter(|k| contains(black_box(k)))
+                .count()
+        });
+    }
+
+    #[bench]
+    fn bench_lookup_avl(b: &mut Bencher) {
+        let mut tree = AVLTree::new();
+        for k in keys() {
+            tree.insert(k);
+        }
+        bench_lookup(b, |k| tree.contains(k));
+    }
+
+    #[bench]
+    fn bench_lookup_btree_3(b: &mut Bencher) {
+        let tree = btree::<3>(&keys());
+        bench_lookup(b, |k| tree.contains_key(k));
+    }
+
+    #[bench]
+    fn bench_lookup_btree_16(b: &mut Bencher) {
+        let tree = btree::<16>(&keys());
+        bench_lookup(b, |k| tree.contains_key(k));
+    }
+
+    #[bench]
+    fn bench_lookup_btree_64(b: &mut Bencher) {
+        let tree = btree::<64>(&keys());
+        bench_lookup(b, |k| tree.contains_key(k));
+    }
+
+    #[bench]
+    fn bench_lookup_bplus_16(b: &mut Bencher) {
+        let mut tree: BPlusTree<u64, u64, 16> = BPlusTree::new();
+        for k in keys() {
+            tree.insert(k, k);
+        }
+        bench_lookup(b, |k| tree.contains_key(k));
+    }
+
+    //@ Building the trees from scratch. The AVL tree allocates a node per key and moves the
+    //@ boxed nodes around on every level on the way down and back up, while the B-tree mostly
+    //@ shifts a few keys within a leaf, which makes it about eight times as fast:
+    //@ `bench_insert_avl       ... bench: 740,395,570 ns/iter (+/- 197,709,739)`
+    //@ `bench_insert_btree_16  ... bench:  92,582,255 ns/iter (+/- 47,858,344)`
+    #[bench]
+    fn bench_insert_avl(b: &mut Bencher) {
+        let keys = keys();
+        b.iter(|| {
+            let mut tree = AVLTree::new();
+            for &k in &keys {
+                tree.insert(k);
+            }
+            tree
+        });
+    }
+
+    #[bench]
+    fn bench_insert_btree_16(b: &mut Bencher) {
+        let keys = keys();
+        b.iter(|| btree::<16>(&keys));
+    }
+
+    //@ Scanning every entry in order. The B-tree iterator has to climb back up to a parent after
+    //@ every leaf, while the B+-tree just follows the link to the next leaf. With an order of 16,
+    //@ most entries are in leaves either way, so the B+-tree only wins by a little:
+    //@ `bench_scan_btree_16    ... bench: 2,105,396 ns/iter (+/- 797,564)`
+    //@ `bench_scan_bplus_16    ... bench: 1,930,635 ns/iter (+/- 399,668)`
+    #[bench]
+    fn bench_scan_btree_16(b: &mut Bencher) {
+        let tree = btree::<16>(&keys());
+        b.iter(|| tree.iter().map(|(_, v)| *v).fold(0, u64::wrapping_add));
+    }
+
+    #[bench]
+    fn bench_scan_bplus_16(b: &mut Bencher) {
+        let mut tree: BPlusTree<u64, u64, 16> = BPlusTree::new();
+        for k in keys() {
+            tree.insert(k, k);
+        }
+        b.iter(|| tree.iter().map(|(_, v)| *v).fold(0, u64::wrapping_add));
+    }
+}
@@ -0,0 +1,339 @@
+use std::{collections::VecDeque, mem};
+
+use super::rope::{Node, Rope, MAX_LEAF};
+
+//@ An undo/redo history for a [Rope](./rope.html).
+//@ Since ropes are persistent, the history doesn't have to record how to reverse an edit: it
+//@ just keeps the versions of the document from before and after it. Each version shares all but
+//@ O(log(n)) nodes with its neighbours, so a long history costs about as much memory as the text
+//@ that was actually typed or deleted, not a copy of the document per edit.
+//@
+//@ Edits are recorded as transactions. Like in most editors, a run of consecutive edits of the
+//@ same kind is grouped into one transaction, so that a single undo takes back a typed word
+//@ instead of a single char:
+//@ - an insert continues the transaction if it starts where the previous insert ended, and
+//@ - a delete continues it if it ends where the previous delete started (backspacing) or starts
+//@   there (deleting forwards).
+//@
+//@ `break_group` ends the current transaction early, for example when the cursor moves.
+pub struct EditHistory {
+    current: Rope,
+    undo: VecDeque<Transaction>,
+    redo: Vec<Transaction>,
+    budget: usize,
+    used: usize,
+    grouping: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Insert,
+    Delete,
+}
+
+//@ `cursor` is the char offset where the next edit has to happen to join this transaction, and
+//@ `cost` is its estimated size in bytes.
+struct Transaction {
+    before: Rope,
+    after: Rope,
+    kind: Kind,
+    cursor: usize,
+    cost: usize,
+}
+
+//@ The default memory budget of a history, in bytes.
+pub const DEFAULT_BUDGET: usize = 16 << 20;
+
+impl EditHistory {
+    pub fn new(rope: Rope) -> Self {
+        EditHistory::with_budget(rope, DEFAULT_BUDGET)
+    }
+
+    //@ The budget bounds the memory taken by the recorded transactions. Once it's exceeded, the
+    //@ oldest transactions are forgotten, so they can't be undone anymore.
+    pub fn with_budget(rope: Rope, budget: usize) -> Self {
+        EditHistory {
+            current: rope,
+            undo: VecDeque::new(),
+            redo: vec![],
+            budget,
+            used: 0,
+            grouping: false,
+        }
+    }
+
+    pub fn rope(&self) -> &Rope {
+        &self.current
+    }
+
+    pub fn into_rope(self) -> Rope {
+        self.current
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    //@ Inserts `s` at char offset `offset`, like `Rope::insert`.
+    pub fn insert(&mut self, s: &str, offset: usize) {
+        if s.is_empty() {
+            return;
+        }
+        let before = self.current.clone();
+        self.current = self.current.clone().insert(s, offset);
+        let joins = |last: &Transaction| last.kind == Kind::Insert && last.cursor == offset;
+        let cursor = offset + s.chars().count();
+        self.record(before, Kind::Insert, joins, cursor, s.len());
+    }
+
+    //@ Deletes the chars from `start` to `end`, inclusive, like `Rope::delete`.
+    pub fn delete(&mut self, start: usize, end: usize) {
+        if start > end || end >= self.current.len() {
+            return;
+        }
+        let before = self.current.clone();
+        self.current = self.current.clone().delete(start, end);
+        let joins = |last: &Transaction| {
+            last.kind == Kind::Delete && (last.cursor == end + 1 || last.cursor == start)
+        };
+        let bytes = before.len_bytes() - self.current.len_bytes();
+        self.record(before, Kind::Delete, joins, start, bytes);
+    }
+
+    //@ We can't cheaply tell how much of a snapshot is shared with its neighbours, so the cost of
+    //@ an edit is estimated as the text it changed, plus the leaf and the path of nodes it had to
+    //@ copy.
+    fn record(
+        &mut self,
+        before: Rope,
+        kind: Kind,
+        joins: impl Fn(&Transaction) -> bool,
+        cursor: usize,
+        bytes: usize,
+    ) {
+        let cost = bytes + MAX_LEAF + self.current.depth() * mem::size_of::<Node>();
+        for t in self.redo.drain(..) {
+            self.used -= t.cost;
+        }
+
+        match self.undo.back_mut() {
+            Some(last) if self.grouping && joins(last) => {
+                last.after = self.current.clone();
+                last.cursor = cursor;
+                last.cost += cost;
+            }
+            _ => self.undo.push_back(Transaction {
+                before,
+                after: self.current.clone(),
+                kind,
+                cursor,
+                cost,
+            }),
+        }
+        self.used += cost;
+        self.grouping = true;
+
+        while self.used > self.budget {
+            match self.undo.pop_front() {
+                Some(t) => self.used -= t.cost,
+                None => break,
+            }
+        }
+    }
+
+    //@ Makes the next edit start a new transaction.
+    pub fn break_group(&mut self) {
+        self.grouping = false;
+    }
+
+    //@ Restores the document from before the last transaction. Returns `false` if there's
+    //@ nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(t) = self.undo.pop_back() else {
+            return false;
+        };
+        self.current = t.before.clone();
+        self.redo.push(t);
+        self.grouping = false;
+        true
+    }
+
+    //@ Reapplies the last undone transaction. Returns `false` if there's nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(t) = self.redo.pop() else {
+            return false;
+        };
+        self.current = t.after.clone();
+        self.undo.push_back(t);
+        self.grouping = false;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    #[test]
+    fn undo_redo() {
+        let mut history = EditHistory::new(Rope::new("Hello, World!"));
+        history.insert(" Cruel", 6);
+        history.break_group();
+        history.delete(0, 4);
+        assert_eq!(*history.rope(), ", Cruel World!");
+
+        assert!(history.undo());
+        assert_eq!(*history.rope(), "Hello, Cruel World!");
+        assert!(history.undo());
+        assert_eq!(*history.rope(), "Hello, World!");
+        assert!(!history.undo());
+
+        assert!(history.redo());
+        assert_eq!(*history.rope(), "Hello, Cruel World!");
+        history.insert("!", 19);
+        assert!(!history.redo());
+        assert_eq!(history.into_rope(), "Hello, Cruel World!!");
+    }
+
+    //@ Typing a word char by char is undone in one step, and so is backspacing over it.
+    #[test]
+    fn groups_consecutive_edits() {
+        let mut history = EditHistory::new(Rope::new("ab"));
+        for (i, c) in ["x", "y", "z"].into_iter().enumerate() {
+            history.insert(c, 1 + i);
+        }
+        assert_eq!(*history.rope(), "axyzb");
+
+        history.delete(3, 3);
+        history.delete(2, 2);
+        assert_eq!(*history.rope(), "axb");
+
+        assert!(history.undo());
+        assert_eq!(*history.rope(), "axyzb");
+        assert!(history.undo());
+        assert_eq!(*history.rope(), "ab");
+        assert!(!history.can_undo());
+    }
+
+    //@ An insert somewhere else, or after `break_group`, starts a new transaction.
+    #[test]
+    fn breaks_groups() {
+        let mut history = EditHistory::new(Rope::new(""));
+        history.insert("a", 0);
+        history.insert("b", 0);
+        history.insert("c", 1);
+        history.break_group();
+        history.insert("d", 2);
+        assert_eq!(*history.rope(), "bcda");
+
+        history.undo();
+        assert_eq!(*history.rope(), "bca");
+        history.undo();
+        assert_eq!(*history.rope(), "a");
+    }
+
+    //@ Each edit costs at least `MAX_LEAF`, so a budget of a few leaves only keeps the most
+    //@ recent transactions.
+    #[test]
+    fn evicts_oldest_transactions() {
+        let mut history = EditHistory::with_budget(Rope::new(""), 3 * MAX_LEAF + 100);
+        for i in 0..10 {
+            history.break_group();
+            history.insert(&i.to_string(), i);
+        }
+
+        let mut undone = 0;
+        while history.undo() {
+            undone += 1;
+        }
+        assert_eq!(undone, 3);
+        assert_eq!(*history.rope(), "0123456");
+    }
+
+    //@ A history of 1,000 edits on a 1MB document keeps every version around, and each of them
+    //@ still matches what it was.
+    #[test]
+    fn large_document() {
+        let text = "lorem ipsum\n".repeat(1 << 16);
+        let mut history = EditHistory::new(Rope::new(&text));
+        for i in 0..1_000 {
+            history.break_group();
+            history.insert("x", i * 700);
+        }
+        for _ in 0..1_000 {
+            assert!(history.undo());
+        }
+        assert_eq!(*history.rope(), text.as_str());
+    }
+
+    //@ Without grouping, every edit is its own transaction, so undoing and redoing walks through
+    //@ the list of every version of the document.
+    #[quickcheck]
+    fn undo_walks_versions(ops: Vec<(u8, usize, usize, String)>) -> bool {
+        let mut history = EditHistory::new(Rope::new(""));
+        let mut versions = vec![String::new()];
+        let mut at = 0;
+        for (op, a, b, s) in ops {
+            let string = &versions[at];
+            let len = string.chars().count();
+            match op % 4 {
+                0 | 1 if !s.is_empty() => {
+                    let offset = a % (len + 1);
+                    let mut next = string.clone();
+                    next.insert_str(
+                        string
+                            .char_indices()
+                            .nth(offset)
+                            .map_or(next.len(), |(i, _)| i),
+                        &s,
+                    );
+                    history.break_group();
+                    history.insert(&s, offset);
+                    versions.truncate(at + 1);
+                    versions.push(next);
+                    at += 1;
+                }
+                2 => {
+                    let undone = history.undo();
+                    if undone != (at > 0) {
+                        return false;
+                    }
+                    at = at.saturating_sub(1);
+                }
+                3 => {
+                    let redone = history.redo();
+                    if redone != (at + 1 < versions.len()) {
+                        return false;
+                    }
+                    at = (at + 1).min(versions.len() - 1);
+                }
+                _ if len > 0 => {
+                    let (start, end) = (a % len, b % len);
+                    let (start, end) = (start.min(end), start.max(end));
+                    let next: String = string
+                        .chars()
+                        .enumerate()
+                        .filter(|&(i, _)| i < start || i > end)
+                        .map(|(_, c)| c)
+                        .collect();
+                    history.break_group();
+                    history.delete(start, end);
+                    versions.truncate(at + 1);
+                    versions.push(next);
+                    at += 1;
+                }
+                _ => {}
+            }
+            if *history.rope() != versions[at].as_str() {
+                return false;
+            }
+        }
+        true
+    }
+}
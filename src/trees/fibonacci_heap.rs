@@ -0,0 +1,581 @@
+//@ A [Fibonacci Heap](https://en.wikipedia.org/wiki/Fibonacci_heap) is a collection of
+//@ heap-ordered trees whose roots sit in a circular doubly linked list, with a pointer to the
+//@ smallest root.
+//@
+//@ - `push`, `peek` and `decrease_key` take O(1) amortized time.
+//@ - `pop` and `delete` take O(log(n)) amortized time.
+//@ - `meld` takes O(m) time, where `m` is the size of the other heap, since its nodes are copied
+//@   into this heap's arena.
+//@
+//@ Apart from `meld`, which is O(1) in the textbook version that links nodes with pointers
+//@ instead of keeping them in an arena, these are the best known bounds for a comparison-based
+//@ heap, and they bring Dijkstra's algorithm down to O(E + V log(V)).
+//@
+//@ The trick is laziness: pushes just add a root to the list, and all the tidying up is deferred
+//@ to `pop`, which consolidates roots of equal degree until every root has a distinct degree.
+//@
+//@ Like the pairing heap, nodes live in an arena and a `Handle` is an index into it, along with
+//@ the generation of its slot. A handle stays valid until its item is popped or deleted, and a
+//@ stale one is caught in the same way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: usize,
+    generation: u32,
+}
+
+//@ Every node is part of a circular list of siblings (`left` and `right`), and knows its parent,
+//@ one of its children, how many children it has, and whether it has lost a child since it
+//@ became a child itself (`marked`).
+struct Node<T> {
+    item: T,
+    parent: Option<usize>,
+    child: Option<usize>,
+    left: usize,
+    right: usize,
+    degree: usize,
+    marked: bool,
+}
+
+pub struct FibonacciHeap<T> {
+    nodes: Vec<Option<Node<T>>>,
+    generations: Vec<u32>,
+    free: Vec<usize>,
+    min: Option<usize>,
+    len: usize,
+}
+
+impl<T: Ord> Default for FibonacciHeap<T> {
+    fn default() -> Self {
+        FibonacciHeap::new()
+    }
+}
+
+impl<T: Ord> FibonacciHeap<T> {
+    pub fn new() -> Self {
+        FibonacciHeap {
+            nodes: vec![],
+            generations: vec![],
+            free: vec![],
+            min: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn node(&self, i: usize) -> &Node<T> {
+        self.nodes[i]
+            .as_ref()
+            .expect("handle refers to a removed item")
+    }
+
+    fn node_mut(&mut self, i: usize) -> &mut Node<T> {
+        self.nodes[i]
+            .as_mut()
+            .expect("handle refers to a removed item")
+    }
+
+    //@ The slot of a handle's item, if it is still in the heap.
+    fn slot(&self, handle: Handle) -> Option<usize> {
+        (self.generations.get(handle.index) == Some(&handle.generation)).then_some(handle.index)
+    }
+
+    //@ Returns the item behind a handle, if it is still in the heap.
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        Some(&self.node(self.slot(handle)?).item)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.min.map(|min| &self.node(min).item)
+    }
+
+    //@ Splices the circular list containing `b` into the one containing `a`, right after `a`.
+    fn splice(&mut self, a: usize, b: usize) {
+        let a_right = self.node(a).right;
+        let b_left = self.node(b).left;
+        self.node_mut(a).right = b;
+        self.node_mut(b).left = a;
+        self.node_mut(b_left).right = a_right;
+        self.node_mut(a_right).left = b_left;
+    }
+
+    //@ Removes a node from its sibling list, leaving it in a list of its own.
+    fn unlink(&mut self, i: usize) {
+        let Node { left, right, .. } = *self.node(i);
+        self.node_mut(left).right = right;
+        self.node_mut(right).left = left;
+        let node = self.node_mut(i);
+        node.left = i;
+        node.right = i;
+    }
+
+    //@ Adds a lone node to the root list, updating the minimum if needed.
+    fn add_root(&mut self, i: usize) {
+        match self.min {
+            Some(min) => {
+                self.splice(min, i);
+                if self.node(i).item < self.node(min).item {
+                    self.min = Some(i);
+                }
+            }
+            None => self.min = Some(i),
+        }
+    }
+
+    //@ Collects every node of the circular list that `first` is part of.
+    fn siblings(&self, first: usize) -> Vec<usize> {
+        let mut list = vec![first];
+        let mut cur = self.node(first).right;
+        while cur != first {
+            list.push(cur);
+            cur = self.node(cur).right;
+        }
+        list
+    }
+
+    //@ Pushing just adds a new single node tree to the root list.
+    pub fn push(&mut self, item: T) -> Handle {
+        let i = self.free.pop().unwrap_or(self.nodes.len());
+        let node = Some(Node {
+            item,
+            parent: None,
+            child: None,
+            left: i,
+            right: i,
+            degree: 0,
+            marked: false,
+        });
+        if i == self.nodes.len() {
+            self.nodes.push(node);
+            self.generations.push(0);
+        } else {
+            self.nodes[i] = node;
+        }
+        self.add_root(i);
+        self.len += 1;
+        Handle {
+            index: i,
+            generation: self.generations[i],
+        }
+    }
+
+    //@ Popping removes the minimum root and promotes all of its children to roots.
+    //@ Then, the root list is consolidated.
+    pub fn pop(&mut self) -> Option<T> {
+        let min = self.min?;
+        if let Some(child) = self.node_mut(min).child.take() {
+            for c in self.siblings(child) {
+                let node = self.node_mut(c);
+                node.parent = None;
+                node.marked = false;
+            }
+            self.splice(min, child);
+        }
+
+        let next = self.node(min).right;
+        self.unlink(min);
+        if next == min {
+            self.min = None;
+        } else {
+            self.min = Some(next);
+            self.consolidate();
+        }
+
+        let node = self.nodes[min]
+            .take()
+            .expect("handle refers to a removed item");
+        self.generations[min] = self.generations[min].wrapping_add(1);
+        self.free.push(min);
+        self.len -= 1;
+        Some(node.item)
+    }
+
+    //@ Consolidation links roots with the same degree (making the larger one a child of the
+    //@ smaller) until all degrees are distinct. Degrees are bounded by O(log(n)), so there are at
+    //@ most O(log(n)) roots left afterwards.
+    fn consolidate(&mut self) {
+        let roots = self.siblings(self.min.expect("consolidate needs a root"));
+        let mut by_degree: Vec<Option<usize>> = vec![];
+        for mut root in roots {
+            self.unlink(root);
+            loop {
+                let degree = self.node(root).degree;
+                if by_degree.len() <= degree {
+                    by_degree.resize(degree + 1, None);
+                }
+                match by_degree[degree].take() {
+                    Some(other) => root = self.link(root, other),
+                    None => {
+                        by_degree[degree] = Some(root);
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.min = None;
+        for root in by_degree.into_iter().flatten() {
+            self.add_root(root);
+        }
+    }
+
+    fn link(&mut self, a: usize, b: usize) -> usize {
+        let (parent, child) = if self.node(b).item < self.node(a).item {
+            (b, a)
+        } else {
+            (a, b)
+        };
+        let node = self.node_mut(child);
+        node.parent = Some(parent);
+        node.marked = false;
+        match self.node(parent).child {
+            Some(first) => self.splice(first, child),
+            None => self.node_mut(parent).child = Some(child),
+        }
+        self.node_mut(parent).degree += 1;
+        parent
+    }
+
+    //@ Cutting moves a node out of its parent's children into the root list.
+    fn cut(&mut self, i: usize, parent: usize) {
+        if self.node(parent).child == Some(i) {
+            let right = self.node(i).right;
+            self.node_mut(parent).child = if right == i { None } else { Some(right) };
+        }
+        self.unlink(i);
+        self.node_mut(parent).degree -= 1;
+        let node = self.node_mut(i);
+        node.parent = None;
+        node.marked = false;
+        self.add_root(i);
+    }
+
+    //@ A node that loses a second child is cut as well, and so on up the tree. This keeps every
+    //@ subtree large relative to its degree, which is where the heap gets its name: a node of
+    //@ degree `k` has at least `F(k + 2)` descendants.
+    fn cascading_cut(&mut self, mut i: usize) {
+        while let Some(parent) = self.node(i).parent {
+            if !self.node(i).marked {
+                self.node_mut(i).marked = true;
+                break;
+            }
+            self.cut(i, parent);
+            i = parent;
+        }
+    }
+
+    //@ Decreasing a key only needs work if it breaks heap order with the node's parent, in which
+    //@ case the node is cut to the root list.
+    pub fn decrease_key(&mut self, handle: Handle, item: T) {
+        let i = self.slot(handle).expect("handle refers to a removed item");
+        assert!(
+            item <= self.node(i).item,
+            "decrease_key can't increase an item"
+        );
+        self.node_mut(i).item = item;
+        if let Some(parent) = self.node(i).parent {
+            if self.node(i).item < self.node(parent).item {
+                self.cut(i, parent);
+                self.cascading_cut(parent);
+            }
+        }
+        if self.node(i).item < self.node(self.min.unwrap()).item {
+            self.min = Some(i);
+        }
+    }
+
+    //@ Deleting a node cuts it to the root list, pretends it's the minimum, and pops it.
+    pub fn delete(&mut self, handle: Handle) -> Option<T> {
+        let i = self.slot(handle)?;
+        if let Some(parent) = self.node(i).parent {
+            self.cut(i, parent);
+            self.cascading_cut(parent);
+        }
+        self.min = Some(i);
+        self.pop()
+    }
+
+    //@ Melding moves `other`'s nodes into this heap's arena in O(m) time and splices the two root
+    //@ lists together. It returns a function that translates `other`'s handles into handles of
+    //@ this heap.
+    pub fn meld(&mut self, mut other: FibonacciHeap<T>) -> impl Fn(Handle) -> Handle {
+        let offset = self.nodes.len();
+        for node in other.nodes.iter_mut().flatten() {
+            node.parent = node.parent.map(|i| i + offset);
+            node.child = node.child.map(|i| i + offset);
+            node.left += offset;
+            node.right += offset;
+        }
+        self.nodes.append(&mut other.nodes);
+        self.generations.append(&mut other.generations);
+        self.free.extend(other.free.iter().map(|i| i + offset));
+        self.len += other.len;
+        if let Some(min) = other.min {
+            self.add_root(min + offset);
+        }
+        move |handle| Handle {
+            index: handle.index + offset,
+            generation: handle.generation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cmp::Reverse, collections::BinaryHeap};
+
+    use quickcheck_macros::quickcheck;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use test::{black_box, Bencher};
+
+    use super::*;
+    use crate::trees::{leftist_heap::LeftistHeap, pairing_heap::PairingHeap};
+
+    #[test]
+    fn push_pop() {
+        let mut heap = FibonacciHeap::new();
+        for i in [5, 3, 8, 1, 9, 2, 7] {
+            heap.push(i);
+        }
+        assert_eq!(heap.peek(), Some(&1));
+        assert_eq!(heap.len(), 7);
+
+        let popped: Vec<_> = std::iter::from_fn(|| heap.pop()).collect();
+        assert_eq!(popped, vec![1, 2, 3, 5, 7, 8, 9]);
+    }
+
+    #[test]
+    fn decrease_key_and_delete() {
+        let mut heap = FibonacciHeap::new();
+        let handles: Vec<_> = (0..16).map(|i| heap.push(i * 10)).collect();
+        //@ Popping consolidates the remaining 15 nodes into trees, so later operations have to
+        //@ cut nodes out of them.
+        assert_eq!(heap.pop(), Some(0));
+
+        heap.decrease_key(handles[15], 5);
+        assert_eq!(heap.peek(), Some(&5));
+        heap.decrease_key(handles[14], 1);
+        assert_eq!(heap.get(handles[14]), Some(&1));
+
+        assert_eq!(heap.delete(handles[3]), Some(30));
+        assert_eq!(heap.delete(handles[3]), None);
+        assert_eq!(heap.pop(), Some(1));
+        assert_eq!(heap.pop(), Some(5));
+        assert_eq!(heap.pop(), Some(10));
+        assert_eq!(heap.len(), 11);
+    }
+
+    #[test]
+    fn meld_heaps() {
+        let mut a = FibonacciHeap::new();
+        a.push(3);
+        let mut b = FibonacciHeap::new();
+        let handle = b.push(7);
+        b.push(1);
+
+        let translate = a.meld(b);
+        a.decrease_key(translate(handle), 0);
+        assert_eq!(a.len(), 3);
+        assert_eq!(a.pop(), Some(0));
+        assert_eq!(a.pop(), Some(1));
+        assert_eq!(a.pop(), Some(3));
+    }
+
+    #[test]
+    fn stale_handle() {
+        let mut heap = FibonacciHeap::new();
+        let stale = heap.push(1);
+        assert_eq!(heap.pop(), Some(1));
+        let fresh = heap.push(2);
+        assert_eq!(heap.get(stale), None);
+        assert_eq!(heap.delete(stale), None);
+        assert_eq!(heap.get(fresh), Some(&2));
+    }
+
+    #[test]
+    #[should_panic(expected = "handle refers to a removed item")]
+    fn decrease_stale_handle() {
+        let mut heap = FibonacciHeap::new();
+        let stale = heap.push(1);
+        heap.pop();
+        heap.push(2);
+        heap.decrease_key(stale, 0);
+    }
+
+    //@ The same random operation sequence as for the pairing heap, checked against a plain vec.
+    #[quickcheck]
+    fn matches_vec(ops: Vec<(u8, usize, i32)>) -> bool {
+        let mut heap = FibonacciHeap::new();
+        let mut reference: Vec<(Handle, (i32, usize))> = vec![];
+        for (id, (op, idx, val)) in ops.into_iter().enumerate() {
+            match op % 4 {
+                0 => reference.push((heap.push((val, id)), (val, id))),
+                1 => {
+                    let min = reference.iter().map(|&(_, item)| item).min();
+                    if heap.pop() != min {
+                        return false;
+                    }
+                    reference.retain(|&(_, item)| Some(item) != min);
+                }
+                2 if !reference.is_empty() => {
+                    let idx = idx % reference.len();
+                    let (handle, (old, id)) = reference[idx];
+                    let new = (old.saturating_sub((val.unsigned_abs() / 2) as i32), id);
+                    heap.decrease_key(handle, new);
+                    reference[idx].1 = new;
+                }
+                3 if !reference.is_empty() => {
+                    let (handle, old) = reference.swap_remove(idx % reference.len());
+                    if heap.delete(handle) != Some(old) {
+                        return false;
+                    }
+                }
+                _ => {}
+            }
+            if heap.len() != reference.len()
+                || heap.peek() != reference.iter().map(|(_, item)| item).min()
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    //@ ## Benchmarks
+    //@ To compare the heaps on a realistic workload, we run Dijkstra's algorithm on a random
+    //@ sparse graph. `BinaryHeap` and `LeftistHeap` don't support `decrease_key`, so they push
+    //@ duplicate entries and skip stale ones when popped. The pairing and Fibonacci heaps keep a
+    //@ handle per node and decrease its key instead.
+    type Graph = Vec<Vec<(usize, u64)>>;
+
+    fn random_graph(nodes: usize, edges: usize) -> Graph {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut graph = vec![vec![]; nodes];
+        for _ in 0..edges {
+            let (u, v) = (rng.gen_range(0..nodes), rng.gen_range(0..nodes));
+            graph[u].push((v, rng.gen_range(1..1000)));
+        }
+        graph
+    }
+
+    fn dijkstra_binary_heap(graph: &Graph) -> Vec<u64> {
+        let mut dist = vec![u64::MAX; graph.len()];
+        let mut heap = BinaryHeap::new();
+        dist[0] = 0;
+        heap.push(Reverse((0, 0)));
+        while let Some(Reverse((d, u))) = heap.pop() {
+            if d > dist[u] {
+                continue;
+            }
+            for &(v, w) in &graph[u] {
+                if d + w < dist[v] {
+                    dist[v] = d + w;
+                    heap.push(Reverse((d + w, v)));
+                }
+            }
+        }
+        dist
+    }
+
+    fn dijkstra_leftist_heap(graph: &Graph) -> Vec<u64> {
+        let mut dist = vec![u64::MAX; graph.len()];
+        let mut heap = LeftistHeap::new();
+        dist[0] = 0;
+        heap.push((0, 0));
+        while let Some((d, u)) = heap.pop() {
+            if d > dist[u] {
+                continue;
+            }
+            for &(v, w) in &graph[u] {
+                if d + w < dist[v] {
+                    dist[v] = d + w;
+                    heap.push((d + w, v));
+                }
+            }
+        }
+        dist
+    }
+
+    //@ Both addressable heaps run the same loop, so it's shared through a macro.
+    macro_rules! dijkstra_decrease_key {
+        ($graph:expr, $heap:ty) => {{
+            let graph: &Graph = $graph;
+            let mut dist = vec![u64::MAX; graph.len()];
+            let mut handles = vec![None; graph.len()];
+            let mut heap = <$heap>::new();
+            dist[0] = 0;
+            handles[0] = Some(heap.push((0, 0)));
+            while let Some((d, u)) = heap.pop() {
+                for &(v, w) in &graph[u] {
+                    if d + w < dist[v] {
+                        dist[v] = d + w;
+                        match handles[v] {
+                            Some(handle) => heap.decrease_key(handle, (d + w, v)),
+                            None => handles[v] = Some(heap.push((d + w, v))),
+                        }
+                    }
+                }
+            }
+            dist
+        }};
+    }
+
+    fn dijkstra_pairing_heap(graph: &Graph) -> Vec<u64> {
+        dijkstra_decrease_key!(graph, PairingHeap<(u64, usize)>)
+    }
+
+    fn dijkstra_fibonacci_heap(graph: &Graph) -> Vec<u64> {
+        dijkstra_decrease_key!(graph, FibonacciHeap<(u64, usize)>)
+    }
+
+    #[test]
+    fn dijkstra_agrees() {
+        let graph = random_graph(1_000, 5_000);
+        let expected = dijkstra_binary_heap(&graph);
+        assert_eq!(dijkstra_leftist_heap(&graph), expected);
+        assert_eq!(dijkstra_pairing_heap(&graph), expected);
+        assert_eq!(dijkstra_fibonacci_heap(&graph), expected);
+    }
+
+    //@ On a graph with 10,000 nodes and 100,000 edges, the binary heap still wins thanks to its
+    //@ cache-friendly array layout, even though it does extra pushes. Among the pointer-based
+    //@ heaps, the pairing heap is the fastest; the Fibonacci heap's better bounds don't pay off
+    //@ at this size:
+    //@ `bench_dijkstra_binary_heap    ... bench:   3,411,671 ns/iter (+/- 1,188,803)`
+    //@ `bench_dijkstra_leftist_heap   ... bench:  15,838,611 ns/iter (+/- 6,566,989)`
+    //@ `bench_dijkstra_pairing_heap   ... bench:   7,886,969 ns/iter (+/- 35,217,645)`
+    //@ `bench_dijkstra_fibonacci_heap ... bench:  18,459,461 ns/iter (+/- 7,052,988)`
+    const NODES: usize = 10_000;
+    const EDGES: usize = 100_000;
+
+    #[bench]
+    fn bench_dijkstra_binary_heap(b: &mut Bencher) {
+        let graph = random_graph(NODES, EDGES);
+        b.iter(|| black_box(dijkstra_binary_heap(&graph)))
+    }
+
+    #[bench]
+    fn bench_dijkstra_leftist_heap(b: &mut Bencher) {
+        let graph = random_graph(NODES, EDGES);
+        b.iter(|| black_box(dijkstra_leftist_heap(&graph)))
+    }
+
+    #[bench]
+    fn bench_dijkstra_pairing_heap(b: &mut Bencher) {
+        let graph = random_graph(NODES, EDGES);
+        b.iter(|| black_box(dijkstra_pairing_heap(&graph)))
+    }
+
+    #[bench]
+    fn bench_dijkstra_fibonacci_heap(b: &mut Bencher) {
+        let graph = random_graph(NODES, EDGES);
+        b.iter(|| black_box(dijkstra_fibonacci_heap(&graph)))
+    }
+}
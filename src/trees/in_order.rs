@@ -1,10 +1,10 @@
 use super::tree_node::BSTNode;
 
-pub fn in_order(root: &BSTNode) -> Vec<i32> {
+pub fn in_order<T: Clone>(root: &BSTNode<T>) -> Vec<T> {
     match root {
         Some(node) => {
             let borrowed_node = node.borrow();
-            let val = borrowed_node.val;
+            let val = borrowed_node.val.clone();
             let mut left = in_order(&borrowed_node.left);
             left.push(val);
             let right = in_order(&borrowed_node.right);
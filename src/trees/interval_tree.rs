@@ -0,0 +1,356 @@
+use std::{
+    cmp::Ordering,
+    ops::{Bound, Range},
+};
+
+use super::avl::{self, Balanced, Link};
+
+type Tree<T, V> = Link<Node<T, V>>;
+
+//@ An [Interval Tree](https://en.wikipedia.org/wiki/Interval_tree) stores intervals, like the
+//@ start and end times of meetings, or the positions of genes on a chromosome, and finds all of
+//@ the intervals that overlap a point or another interval.
+//@
+//@ This is the augmented tree version from CLRS: an [AVL tree](./avl.html) ordered by the start
+//@ of the intervals, where every node also caches the largest end of any interval in its subtree.
+//@ A search can skip a whole subtree if that largest end is before the query starts, and
+//@ everything to the right of a node whose interval starts after the query ends.
+//@
+//@ - `insert` and `remove` take O(log(n)) time.
+//@ - `overlapping` and `stabbing` take O(min(n, k * log(n))) time to find `k` intervals.
+//@
+//@ Intervals are half-open, like Rust ranges, so `9..10` and `10..11` don't overlap, which is
+//@ what we want for back-to-back meetings. They can't be empty, since an empty interval would
+//@ never overlap anything. The same interval can be stored more than once.
+pub struct IntervalTree<T, V> {
+    root: Tree<T, V>,
+    len: usize,
+}
+
+struct Node<T, V> {
+    interval: Range<T>,
+    val: V,
+    max_end: T,
+    height: i32,
+    left: Tree<T, V>,
+    right: Tree<T, V>,
+}
+
+//@ The rotations in `avl` call `update` on every node whose children they change, which is all
+//@ it takes to keep `max_end` right.
+impl<T: Ord + Copy, V> Balanced for Node<T, V> {
+    fn left(&self) -> &Link<Self> {
+        &self.left
+    }
+
+    fn right(&self) -> &Link<Self> {
+        &self.right
+    }
+
+    fn left_mut(&mut self) -> &mut Link<Self> {
+        &mut self.left
+    }
+
+    fn right_mut(&mut self) -> &mut Link<Self> {
+        &mut self.right
+    }
+
+    fn height(&self) -> i32 {
+        self.height
+    }
+
+    fn update(&mut self) {
+        self.height = i32::max(avl::height(&self.left), avl::height(&self.right)) + 1;
+        self.max_end = [&self.left, &self.right]
+            .into_iter()
+            .flatten()
+            .map(|child| child.max_end)
+            .fold(self.interval.end, T::max);
+    }
+}
+
+//@ Intervals are ordered by start, then by end.
+fn cmp<T: Ord>(a: &Range<T>, b: &Range<T>) -> Ordering {
+    (&a.start, &a.end).cmp(&(&b.start, &b.end))
+}
+
+fn insert<T: Ord + Copy, V>(link: Tree<T, V>, interval: Range<T>, val: V) -> Box<Node<T, V>> {
+    let Some(mut node) = link else {
+        return Box::new(Node {
+            max_end: interval.end,
+            interval,
+            val,
+            height: 1,
+            left: None,
+            right: None,
+        });
+    };
+    if cmp(&interval, &node.interval).is_lt() {
+        node.left = Some(insert(node.left.take(), interval, val));
+    } else {
+        node.right = Some(insert(node.right.take(), interval, val));
+    }
+    avl::balance(node)
+}
+
+//@ Removing a node with two children swaps in the first interval of its right subtree, so every
+//@ node on both paths gets rebalanced on the way up.
+fn remove<T: Ord + Copy, V>(link: &mut Tree<T, V>, interval: &Range<T>) -> Option<V> {
+    let mut node = link.take()?;
+    let removed = match cmp(interval, &node.interval) {
+        Ordering::Less => remove(&mut node.left, interval),
+        Ordering::Greater => remove(&mut node.right, interval),
+        Ordering::Equal => {
+            let (left, right) = (node.left.take(), node.right.take());
+            let Some(right) = right else {
+                *link = left;
+                return Some(node.val);
+            };
+            let (mut first, right) = pop_first(right);
+            first.left = left;
+            first.right = right;
+            *link = Some(avl::balance(first));
+            return Some(node.val);
+        }
+    };
+    *link = Some(avl::balance(node));
+    removed
+}
+
+//@ Detaches the leftmost node of a subtree, and returns it along with what's left of the subtree.
+fn pop_first<T: Ord + Copy, V>(mut node: Box<Node<T, V>>) -> (Box<Node<T, V>>, Tree<T, V>) {
+    match node.left.take() {
+        None => {
+            let right = node.right.take();
+            (node, right)
+        }
+        Some(left) => {
+            let (first, left) = pop_first(left);
+            node.left = left;
+            (first, Some(avl::balance(node)))
+        }
+    }
+}
+
+impl<T: Ord + Copy, V> Default for IntervalTree<T, V> {
+    fn default() -> Self {
+        IntervalTree::new()
+    }
+}
+
+impl<T: Ord + Copy, V> IntervalTree<T, V> {
+    pub fn new() -> Self {
+        IntervalTree { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, interval: Range<T>, val: V) {
+        assert!(interval.start < interval.end, "intervals can't be empty");
+        self.root = Some(insert(self.root.take(), interval, val));
+        self.len += 1;
+    }
+
+    //@ Removes one copy of `interval`, and returns its value.
+    pub fn remove(&mut self, interval: &Range<T>) -> Option<V> {
+        let removed = remove(&mut self.root, interval)?;
+        self.len -= 1;
+        Some(removed)
+    }
+
+    //@ All intervals that share at least one point with `range`, ordered by start.
+    pub fn overlapping(&self, range: Range<T>) -> Overlapping<'_, T, V> {
+        let mut iter = Overlapping {
+            stack: vec![],
+            after: Some(range.start),
+            before: Bound::Excluded(range.end),
+        };
+        if range.start < range.end {
+            iter.push_left_spine(&self.root);
+        }
+        iter
+    }
+
+    //@ All intervals that contain `point`, ordered by start.
+    pub fn stabbing(&self, point: T) -> Overlapping<'_, T, V> {
+        let mut iter = Overlapping {
+            stack: vec![],
+            after: Some(point),
+            before: Bound::Included(point),
+        };
+        iter.push_left_spine(&self.root);
+        iter
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Range<T>, &V)> + '_ {
+        let mut iter = Overlapping {
+            stack: vec![],
+            after: None,
+            before: Bound::Unbounded,
+        };
+        iter.push_left_spine(&self.root);
+        iter
+    }
+}
+
+//@ ## Queries
+//@ An interval overlaps the query if it ends after the query starts, and starts before the query
+//@ ends. For a range query, the end of the query is excluded, and for a point query, it's
+//@ included. Iterating over the whole tree is a query without any limits.
+//@
+//@ This is an in-order traversal with an explicit stack, which only descends into a subtree if
+//@ its `max_end` is after the query's start. Once it reaches an interval that starts after the
+//@ query ends, every later interval does too, so it stops.
+pub struct Overlapping<'a, T, V> {
+    stack: Vec<&'a Node<T, V>>,
+    after: Option<T>,
+    before: Bound<T>,
+}
+
+impl<'a, T: Ord + Copy, V> Overlapping<'a, T, V> {
+    fn push_left_spine(&mut self, mut link: &'a Tree<T, V>) {
+        while let Some(node) = link {
+            if !self.ends_after_start(node.max_end) {
+                return;
+            }
+            self.stack.push(node);
+            link = &node.left;
+        }
+    }
+
+    fn ends_after_start(&self, end: T) -> bool {
+        self.after.is_none_or(|start| end > start)
+    }
+
+    fn starts_before_end(&self, start: T) -> bool {
+        match self.before {
+            Bound::Included(end) => start <= end,
+            Bound::Excluded(end) => start < end,
+            Bound::Unbounded => true,
+        }
+    }
+}
+
+impl<'a, T: Ord + Copy, V> Iterator for Overlapping<'a, T, V> {
+    type Item = (&'a Range<T>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.pop() {
+            if !self.starts_before_end(node.interval.start) {
+                self.stack.clear();
+                return None;
+            }
+            self.push_left_spine(&node.right);
+            if self.ends_after_start(node.interval.end) {
+                return Some((&node.interval, &node.val));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    #[test]
+    fn ex1() {
+        let mut calendar = IntervalTree::new();
+        calendar.insert(9..10, "standup");
+        calendar.insert(10..12, "design review");
+        calendar.insert(11..13, "lunch");
+        calendar.insert(14..15, "1:1");
+        calendar.insert(9..17, "on call");
+
+        let conflicts: Vec<_> = calendar.overlapping(10..11).map(|(_, v)| *v).collect();
+        assert_eq!(conflicts, vec!["on call", "design review"]);
+        let at_noon: Vec<_> = calendar.stabbing(12).map(|(_, v)| *v).collect();
+        assert_eq!(at_noon, vec!["on call", "lunch"]);
+        assert_eq!(calendar.overlapping(17..18).count(), 0);
+        assert_eq!(calendar.overlapping(12..12).count(), 0);
+
+        assert_eq!(calendar.remove(&(9..17)), Some("on call"));
+        assert_eq!(calendar.remove(&(9..17)), None);
+        assert_eq!(calendar.stabbing(12).count(), 1);
+        assert_eq!(calendar.len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "empty")]
+    fn empty_interval() {
+        IntervalTree::new().insert(3..3, ());
+    }
+
+    //@ Checks the AVL balance, the cached heights and `max_end`s, and the ordering.
+    fn check<T: Ord + Copy + std::fmt::Debug, V>(link: &Tree<T, V>) -> i32 {
+        let Some(node) = link else {
+            return 0;
+        };
+        let (left, right) = (check(&node.left), check(&node.right));
+        assert!((left - right).abs() <= 1);
+        assert_eq!(node.height, left.max(right) + 1);
+        let ends = [&node.left, &node.right].into_iter().flatten();
+        assert_eq!(
+            node.max_end,
+            ends.fold(node.interval.end, |m, c| m.max(c.max_end))
+        );
+        if let Some(l) = &node.left {
+            assert!(cmp(&l.interval, &node.interval).is_le());
+        }
+        if let Some(r) = &node.right {
+            assert!(cmp(&r.interval, &node.interval).is_ge());
+        }
+        node.height
+    }
+
+    //@ Inserts and removes random intervals in a tree and a `Vec`, and compares the results of
+    //@ random queries with a scan over the `Vec`.
+    #[quickcheck]
+    fn matches_vec(ops: Vec<(bool, u8, u8)>, queries: Vec<(u8, u8)>) -> bool {
+        let mut tree = IntervalTree::new();
+        let mut expected: Vec<Range<u8>> = vec![];
+        for (insert, a, b) in ops {
+            let interval = a.min(b) / 4..a.max(b) / 4 + 1;
+            if insert {
+                tree.insert(interval.clone(), ());
+                expected.push(interval);
+            } else {
+                let found = expected.iter().position(|i| *i == interval);
+                if tree.remove(&interval).is_some() != found.is_some() {
+                    return false;
+                }
+                found.map(|i| expected.swap_remove(i));
+            }
+        }
+        check(&tree.root);
+        expected.sort_by(cmp);
+        if !tree
+            .iter()
+            .map(|(i, _)| i.clone())
+            .eq(expected.iter().cloned())
+        {
+            return false;
+        }
+        queries.into_iter().all(|(a, b)| {
+            let range = a.min(b) / 4..a.max(b) / 4;
+            let point = a / 4;
+            let overlapping = expected
+                .iter()
+                .filter(|i| i.start < range.end && range.start < i.end && !range.is_empty());
+            let stabbing = expected.iter().filter(|i| i.contains(&point));
+            tree.overlapping(range.clone())
+                .map(|(i, _)| i)
+                .eq(overlapping)
+                && tree.stabbing(point).map(|(i, _)| i).eq(stabbing)
+        })
+    }
+}
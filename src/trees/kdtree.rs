@@ -0,0 +1,391 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use crate::sorts::quickselect::quickselect_by;
+
+//@ A [k-d tree](https://en.wikipedia.org/wiki/K-d_tree) finds the points closest to a query
+//@ point, for things like the nearest stores to a customer, or the most similar embeddings to a
+//@ search term. Every node splits the space in two along one axis, cycling through the axes as
+//@ the tree gets deeper, with the points on the low side in its left subtree and the rest in its
+//@ right one. A search goes down the side the query is on first, and only looks at the other
+//@ side if the splitting plane is closer than the points it already found.
+//@
+//@ The tree is built all at once, by putting the median of the points along the first axis at
+//@ the root, and recursing into both halves with the next axis. `quickselect` finds each median
+//@ in linear time, which makes the build O(n * log(n)), and keeps the tree perfectly balanced.
+//@ Like a binary heap, the tree doesn't need any pointers: the root of the points in `lo..hi` is
+//@ the one at `(lo + hi) / 2`, so it's all a single `Vec`.
+//@
+//@ - Building takes O(n * log(n)) time.
+//@ - `nearest` takes O(log(n)) time on average for well spread out points, but O(n) time in the
+//@   worst case.
+//@ - `within` takes about O(log(n) + m) time to find `m` points when the radius is small compared
+//@   with the spread of the points, but it can look at all of them in the worst case.
+//@
+//@ Distances are Euclidean. Pruning gets worse as `D` grows, because the closest points get
+//@ about as far away as the splitting planes, and beyond 20 or so dimensions a search looks at
+//@ most of the points anyway. Large embeddings need approximate methods instead, but for small
+//@ ones, a k-d tree is still a lot faster than a scan.
+pub struct KdTree<const D: usize, V = ()> {
+    points: Vec<([f64; D], V)>,
+}
+
+impl<const D: usize, V> KdTree<D, V> {
+    pub fn new(mut points: Vec<([f64; D], V)>) -> Self {
+        const { assert!(D > 0, "points need at least one dimension") };
+        assert!(
+            points.iter().flat_map(|(p, _)| p).all(|x| !x.is_nan()),
+            "coordinates can't be NaN"
+        );
+        build(&mut points, 0);
+        KdTree { points }
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    //@ The `k` points closest to `query`, with their distances, from closest to farthest.
+    pub fn nearest(&self, query: &[f64; D], k: usize) -> Vec<(&[f64; D], &V, f64)> {
+        let mut nearest = Nearest::new(k);
+        search(&self.points, 0, 0, query, &mut nearest);
+        self.found(nearest.into_sorted())
+    }
+
+    //@ All points at most `radius` away from `query`, from closest to farthest. The search is on
+    //@ squared distances, so a negative radius would act like a positive one, and is rejected.
+    pub fn within(&self, query: &[f64; D], radius: f64) -> Vec<(&[f64; D], &V, f64)> {
+        assert!(radius >= 0.0, "radius must be non-negative, got {radius}");
+        let mut within = Within::new(radius * radius);
+        search(&self.points, 0, 0, query, &mut within);
+        self.found(within.into_sorted())
+    }
+
+    //@ Turns the squared distances of the search back into distances.
+    fn found(&self, found: Vec<(f64, usize)>) -> Vec<(&[f64; D], &V, f64)> {
+        found
+            .into_iter()
+            .map(|(d, i)| (&self.points[i].0, &self.points[i].1, d.sqrt()))
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&[f64; D], &V)> {
+        self.points.iter().map(|(point, val)| (point, val))
+    }
+}
+
+impl<const D: usize, V> FromIterator<([f64; D], V)> for KdTree<D, V> {
+    fn from_iter<I: IntoIterator<Item = ([f64; D], V)>>(iter: I) -> Self {
+        KdTree::new(iter.into_iter().collect())
+    }
+}
+
+impl<const D: usize> FromIterator<[f64; D]> for KdTree<D> {
+    fn from_iter<I: IntoIterator<Item = [f64; D]>>(iter: I) -> Self {
+        KdTree::new(iter.into_iter().map(|point| (point, ())).collect())
+    }
+}
+
+//@ Puts the median along `axis` in the middle, which partitions the points around it, and
+//@ recurses into both sides. The recursion is only O(log(n)) deep.
+fn build<const D: usize, V>(points: &mut [([f64; D], V)], axis: usize) {
+    if points.len() <= 1 {
+        return;
+    }
+    let mid = points.len() / 2;
+    quickselect_by(points, mid, &mut |(a, _), (b, _)| {
+        a[axis].total_cmp(&b[axis])
+    });
+    let (left, right) = points.split_at_mut(mid);
+    build(left, (axis + 1) % D);
+    build(&mut right[1..], (axis + 1) % D);
+}
+
+fn squared_distance<const D: usize>(a: &[f64; D], b: &[f64; D]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum()
+}
+
+//@ Searches the subtree of the points in `points`, which start at `offset` in the whole tree.
+//@ Everything on the far side of the splitting plane is at least as far away as the plane, so
+//@ it's skipped if the plane is farther than what the search is looking for. Since the search
+//@ is on squared distances, so is the distance to the plane.
+fn search<const D: usize, V>(
+    points: &[([f64; D], V)],
+    offset: usize,
+    axis: usize,
+    query: &[f64; D],
+    found: &mut impl Neighbors,
+) {
+    if points.is_empty() {
+        return;
+    }
+    let mid = points.len() / 2;
+    let (point, _) = &points[mid];
+    found.offer(squared_distance(point, query), offset + mid);
+    let (left, right) = (&points[..mid], &points[mid + 1..]);
+    let (left, right) = ((left, offset), (right, offset + mid + 1));
+    let diff = query[axis] - point[axis];
+    let (near, far) = if diff < 0.0 {
+        (left, right)
+    } else {
+        (right, left)
+    };
+    let next = (axis + 1) % D;
+    search(near.0, near.1, next, query, found);
+    if diff * diff <= found.bound() {
+        search(far.0, far.1, next, query, found);
+    }
+}
+
+//@ ## Collecting Neighbors
+//@ Both kinds of queries walk the tree the same way, and only differ in which points they keep,
+//@ and how far away a subtree can be before it's not worth looking at. The [ball
+//@ tree](./ball_tree.html) searches with these too.
+pub(super) trait Neighbors {
+    //@ Points farther away than this don't matter.
+    fn bound(&self) -> f64;
+    fn offer(&mut self, dist: f64, index: usize);
+}
+
+//@ A candidate for the nearest points, ordered by distance, then by index.
+struct Candidate(f64, usize);
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+//@ The `k` nearest points found so far, in a max-heap, so the farthest of them is on top. Until
+//@ there are `k` of them, every point counts.
+pub(super) struct Nearest {
+    k: usize,
+    heap: BinaryHeap<Candidate>,
+}
+
+impl Nearest {
+    pub(super) fn new(k: usize) -> Self {
+        Nearest {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+
+    pub(super) fn into_sorted(self) -> Vec<(f64, usize)> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Candidate(dist, i)| (dist, i))
+            .collect()
+    }
+}
+
+impl Neighbors for Nearest {
+    fn bound(&self) -> f64 {
+        match self.heap.peek() {
+            _ if self.heap.len() < self.k => f64::INFINITY,
+            Some(farthest) => farthest.0,
+            None => f64::NEG_INFINITY,
+        }
+    }
+
+    fn offer(&mut self, dist: f64, index: usize) {
+        if dist < self.bound() {
+            self.heap.push(Candidate(dist, index));
+            if self.heap.len() > self.k {
+                self.heap.pop();
+            }
+        }
+    }
+}
+
+//@ All the points within `radius`.
+pub(super) struct Within {
+    radius: f64,
+    found: Vec<(f64, usize)>,
+}
+
+impl Within {
+    pub(super) fn new(radius: f64) -> Self {
+        Within {
+            radius,
+            found: vec![],
+        }
+    }
+
+    pub(super) fn into_sorted(mut self) -> Vec<(f64, usize)> {
+        self.found
+            .sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        self.found
+    }
+}
+
+impl Neighbors for Within {
+    fn bound(&self) -> f64 {
+        self.radius
+    }
+
+    fn offer(&mut self, dist: f64, index: usize) {
+        if dist <= self.radius {
+            self.found.push((dist, index));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use test::{black_box, Bencher};
+
+    use super::*;
+    use crate::trees::ball_tree::BallTree;
+
+    #[test]
+    fn ex1() {
+        let stores = KdTree::new(vec![
+            ([2.0, 3.0], "bakery"),
+            ([5.0, 4.0], "pharmacy"),
+            ([9.0, 6.0], "grocery"),
+            ([4.0, 7.0], "hardware"),
+            ([8.0, 1.0], "florist"),
+            ([7.0, 2.0], "bookshop"),
+        ]);
+        let nearest = stores.nearest(&[6.0, 2.5], 2);
+        let names: Vec<_> = nearest.iter().map(|(_, name, _)| **name).collect();
+        assert_eq!(names, ["bookshop", "pharmacy"]);
+        assert_eq!(nearest[0].2, 1.25f64.sqrt());
+
+        let walkable: Vec<_> = stores
+            .within(&[3.0, 4.5], 3.0)
+            .into_iter()
+            .map(|(_, name, _)| *name)
+            .collect();
+        assert_eq!(walkable, ["bakery", "pharmacy", "hardware"]);
+        assert!(stores.nearest(&[0.0, 0.0], 0).is_empty());
+        assert_eq!(stores.nearest(&[0.0, 0.0], 10).len(), 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "radius must be non-negative")]
+    fn negative_radius() {
+        let tree: KdTree<2> = [[0.0, 0.0]].into_iter().collect();
+        tree.within(&[0.0, 0.0], -3.0);
+    }
+
+    //@ Checks that every node splits its subtree along its axis.
+    fn check<const D: usize, V>(points: &[([f64; D], V)], axis: usize) {
+        if points.is_empty() {
+            return;
+        }
+        let mid = points.len() / 2;
+        let split = points[mid].0[axis];
+        assert!(points[..mid].iter().all(|(p, _)| p[axis] <= split));
+        assert!(points[mid + 1..].iter().all(|(p, _)| p[axis] >= split));
+        check(&points[..mid], (axis + 1) % D);
+        check(&points[mid + 1..], (axis + 1) % D);
+    }
+
+    fn brute_force(points: &[[f64; 3]], query: &[f64; 3]) -> Vec<f64> {
+        let mut dists: Vec<f64> = points
+            .iter()
+            .map(|p| squared_distance(p, query).sqrt())
+            .collect();
+        dists.sort_by(f64::total_cmp);
+        dists
+    }
+
+    //@ Coordinates are multiples of a half on a small grid, so there are lots of ties and
+    //@ duplicates. With ties, the points can come out in a different order than in a scan, but
+    //@ the distances can't.
+    #[quickcheck]
+    fn matches_brute_force(points: Vec<(i8, i8, i8)>, query: (i8, i8, i8), k: u8) -> bool {
+        let point = |(x, y, z): (i8, i8, i8)| [x, y, z].map(|c| f64::from(c % 16) / 2.0);
+        let points: Vec<_> = points.into_iter().map(point).collect();
+        let query = point(query);
+        let tree: KdTree<3> = points.iter().copied().collect();
+        check(&tree.points, 0);
+        let expected = brute_force(&points, &query);
+        let k = k % 16;
+        let radius = f64::from(k) / 2.0;
+        let k = usize::from(k);
+        let nearest: Vec<f64> = tree.nearest(&query, k).iter().map(|n| n.2).collect();
+        let within: Vec<f64> = tree.within(&query, radius).iter().map(|n| n.2).collect();
+        nearest == expected[..k.min(expected.len())]
+            && within
+                == expected
+                    .iter()
+                    .copied()
+                    .take_while(|&d| d <= radius)
+                    .collect::<Vec<_>>()
+    }
+
+    //@ Finding the 10 nearest of 100k random points in a unit cube, for 1000 queries, takes 290ms
+    //@ with a scan, 4.8ms with the k-d tree, and 10.1ms with the ball tree, which can only prune
+    //@ with the triangle inequality, and calls the metric through a pointer.
+    const N: usize = 100_000;
+
+    fn random_points(rng: &mut StdRng, n: usize) -> Vec<[f64; 3]> {
+        (0..n).map(|_| rng.gen()).collect()
+    }
+
+    #[bench]
+    fn bench_nearest_scan(b: &mut Bencher) {
+        let mut rng = StdRng::seed_from_u64(43);
+        let points = random_points(&mut rng, N);
+        let queries = random_points(&mut rng, 1000);
+        b.iter(|| {
+            for query in &queries {
+                let mut nearest = Nearest::new(10);
+                for (i, point) in points.iter().enumerate() {
+                    nearest.offer(squared_distance(point, query), i);
+                }
+                black_box(nearest.into_sorted());
+            }
+        });
+    }
+
+    #[bench]
+    fn bench_nearest_kdtree(b: &mut Bencher) {
+        let mut rng = StdRng::seed_from_u64(43);
+        let tree: KdTree<3> = random_points(&mut rng, N).into_iter().collect();
+        let queries = random_points(&mut rng, 1000);
+        b.iter(|| {
+            for query in &queries {
+                black_box(tree.nearest(query, 10));
+            }
+        });
+    }
+
+    #[bench]
+    fn bench_nearest_ball_tree(b: &mut Bencher) {
+        let mut rng = StdRng::seed_from_u64(43);
+        let tree = BallTree::new(random_points(&mut rng, N), |a, b| {
+            squared_distance(a, b).sqrt()
+        });
+        let queries = random_points(&mut rng, 1000);
+        b.iter(|| {
+            for query in &queries {
+                black_box(tree.nearest(query, 10));
+            }
+        });
+    }
+}
@@ -1,3 +1,5 @@
+use std::{cmp::Ordering, collections::VecDeque};
+
 #[derive(Default)]
 pub enum Heap<T> {
     #[default]
@@ -32,36 +34,45 @@ impl<T> Heap<T> {
     }
 }
 
-impl<T: PartialOrd> Heap<T> {
-    fn make(item: T, left: Heap<T>, right: Heap<T>) -> Self {
-        Heap::Item(Box::new(if left.rank() >= right.rank() {
-            Node {
-                rank: right.rank() + 1,
-                item,
-                left,
-                right,
-            }
-        } else {
-            Node {
-                rank: left.rank() + 1,
-                item,
-                left: right,
-                right: left,
+impl<T> Heap<T> {
+    //@ Merging walks down the right spines of both heaps, always descending into the heap with
+    //@ the smaller root, and then rebuilds the spine bottom-up, swapping children wherever the
+    //@ right child would outrank the left.
+    //@ This is done with an explicit stack instead of recursion, so deep spines can't overflow
+    //@ the call stack. `le(a, b)` decides whether `a` belongs above `b`.
+    fn merge_by(mut a: Heap<T>, mut b: Heap<T>, le: &impl Fn(&T, &T) -> bool) -> Self {
+        let mut spine: Vec<Box<Node<T>>> = vec![];
+        let mut merged = loop {
+            match (a, b) {
+                (Heap::Empty, h) | (h, Heap::Empty) => break h,
+                (Heap::Item(h1), Heap::Item(h2)) => {
+                    let (mut top, other) = if le(&h1.item, &h2.item) {
+                        (h1, h2)
+                    } else {
+                        (h2, h1)
+                    };
+                    a = std::mem::take(&mut top.right);
+                    b = Heap::Item(other);
+                    spine.push(top);
+                }
             }
-        }))
-    }
+        };
 
-    fn merge(a: Heap<T>, b: Heap<T>) -> Self {
-        match (a, b) {
-            (Heap::Empty, h) | (h, Heap::Empty) => h,
-            (Heap::Item(h1), Heap::Item(h2)) => {
-                if h1.item <= h2.item {
-                    Heap::make(h1.item, h1.left, Heap::merge(h1.right, Heap::Item(h2)))
-                } else {
-                    Heap::make(h2.item, h2.left, Heap::merge(Heap::Item(h1), h2.right))
-                }
+        while let Some(mut node) = spine.pop() {
+            node.right = merged;
+            if node.left.rank() < node.right.rank() {
+                std::mem::swap(&mut node.left, &mut node.right);
             }
+            node.rank = node.right.rank() + 1;
+            merged = Heap::Item(node);
         }
+        merged
+    }
+}
+
+impl<T: PartialOrd> Heap<T> {
+    pub fn merge(a: Heap<T>, b: Heap<T>) -> Self {
+        Heap::merge_by(a, b, &|x, y| x <= y)
     }
 
     pub fn insert(self, item: T) -> Self {
@@ -83,8 +94,200 @@ impl<T: PartialOrd> Heap<T> {
         }
     }
 }
+
+//@ ## LeftistHeap
+//@ `Heap` is a persistent-style API: every operation consumes the heap and returns a new one.
+//@ `LeftistHeap` wraps it into a mutable priority queue that tracks its length and orders items
+//@ with a comparator. `LeftistHeap::new()` is a min-heap; for a max-heap either push
+//@ `std::cmp::Reverse` items or pass a comparator like `|a, b| b.cmp(a)`.
+pub struct LeftistHeap<T, C = fn(&T, &T) -> Ordering> {
+    root: Heap<T>,
+    len: usize,
+    cmp: C,
+}
+
+impl<T: Ord> LeftistHeap<T> {
+    pub fn new() -> Self {
+        LeftistHeap::with_comparator(T::cmp)
+    }
+}
+
+impl<T: Ord> Default for LeftistHeap<T> {
+    fn default() -> Self {
+        LeftistHeap::new()
+    }
+}
+
+impl<T, C: Fn(&T, &T) -> Ordering> LeftistHeap<T, C> {
+    //@ The heap keeps the smallest item according to `cmp` on top.
+    pub fn with_comparator(cmp: C) -> Self {
+        LeftistHeap {
+            root: Heap::Empty,
+            len: 0,
+            cmp,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        match &self.root {
+            Heap::Empty => None,
+            Heap::Item(node) => Some(&node.item),
+        }
+    }
+
+    //@ Pushing is merging with a single node heap, which takes O(log(n)) time.
+    pub fn push(&mut self, item: T) {
+        self.meld(Heap::Item(Box::new(Node::new(item))));
+        self.len += 1;
+    }
+
+    //@ Popping removes the root and merges its two children, also in O(log(n)) time.
+    pub fn pop(&mut self) -> Option<T> {
+        match std::mem::take(&mut self.root) {
+            Heap::Empty => None,
+            Heap::Item(node) => {
+                let Node {
+                    item, left, right, ..
+                } = *node;
+                self.root = Heap::merge_by(left, right, &|a, b| (self.cmp)(a, b).is_le());
+                self.len -= 1;
+                Some(item)
+            }
+        }
+    }
+
+    //@ Merges all of `other`'s items into this heap in O(log(n + m)) time, leaving `other` empty.
+    //@ The items are ordered by this heap's comparator.
+    pub fn merge<D>(&mut self, mut other: LeftistHeap<T, D>) {
+        let root = std::mem::take(&mut other.root);
+        self.len += std::mem::take(&mut other.len);
+        self.meld(root);
+    }
+
+    fn meld(&mut self, other: Heap<T>) {
+        let root = std::mem::take(&mut self.root);
+        self.root = Heap::merge_by(root, other, &|a, b| (self.cmp)(a, b).is_le());
+    }
+
+    //@ Pops every item in sorted order, leaving the heap empty.
+    pub fn drain(&mut self) -> Drain<'_, T, C> {
+        Drain { heap: self }
+    }
+}
+
+//@ A `LeftistHeap` can be dropped with a deep left spine (e.g. by pushing items in descending
+//@ order), so the default recursive drop could overflow the stack. Instead, we take nodes apart
+//@ one at a time.
+impl<T, C> Drop for LeftistHeap<T, C> {
+    fn drop(&mut self) {
+        dismantle(std::mem::take(&mut self.root));
+    }
+}
+
+fn dismantle<T>(heap: Heap<T>) {
+    let mut stack = vec![heap];
+    while let Some(heap) = stack.pop() {
+        if let Heap::Item(node) = heap {
+            let Node { left, right, .. } = *node;
+            stack.push(left);
+            stack.push(right);
+        }
+    }
+}
+
+//@ Collecting an iterator pairs up singleton heaps and merges them round by round, which builds
+//@ the heap in O(n) time instead of O(n log(n)) for repeated pushes.
+impl<T: Ord> FromIterator<T> for LeftistHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut heap = LeftistHeap::new();
+        heap.extend(iter);
+        heap
+    }
+}
+
+impl<T, C: Fn(&T, &T) -> Ordering> Extend<T> for LeftistHeap<T, C> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut queue: VecDeque<Heap<T>> = iter
+            .into_iter()
+            .map(|item| Heap::Item(Box::new(Node::new(item))))
+            .collect();
+        self.len += queue.len();
+        while queue.len() > 1 {
+            let a = queue.pop_front().unwrap();
+            let b = queue.pop_front().unwrap();
+            queue.push_back(Heap::merge_by(a, b, &|a, b| (self.cmp)(a, b).is_le()));
+        }
+        if let Some(heap) = queue.pop_front() {
+            self.meld(heap);
+        }
+    }
+}
+
+pub struct Drain<'a, T, C: Fn(&T, &T) -> Ordering> {
+    heap: &'a mut LeftistHeap<T, C>,
+}
+
+impl<T, C: Fn(&T, &T) -> Ordering> Iterator for Drain<'_, T, C> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.heap.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.heap.len(), Some(self.heap.len()))
+    }
+}
+
+//@ Dropping a `Drain` early still empties the heap, like `Vec::drain`.
+impl<T, C: Fn(&T, &T) -> Ordering> Drop for Drain<'_, T, C> {
+    fn drop(&mut self) {
+        dismantle(std::mem::take(&mut self.heap.root));
+        self.heap.len = 0;
+    }
+}
+
+//@ Consuming a heap yields its items in sorted order.
+pub struct IntoIter<T, C: Fn(&T, &T) -> Ordering> {
+    heap: LeftistHeap<T, C>,
+}
+
+impl<T, C: Fn(&T, &T) -> Ordering> Iterator for IntoIter<T, C> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.heap.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.heap.len(), Some(self.heap.len()))
+    }
+}
+
+impl<T, C: Fn(&T, &T) -> Ordering> IntoIterator for LeftistHeap<T, C> {
+    type Item = T;
+    type IntoIter = IntoIter<T, C>;
+
+    fn into_iter(self) -> IntoIter<T, C> {
+        IntoIter { heap: self }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::cmp::Reverse;
+
+    use quickcheck_macros::quickcheck;
+
     use super::*;
 
     #[test]
@@ -111,4 +314,91 @@ mod tests {
             assert_eq!(node.right.find_min(), Some(&10));
         }
     }
+
+    #[test]
+    fn push_pop_peek() {
+        let mut heap = LeftistHeap::new();
+        assert_eq!(heap.peek(), None);
+
+        for i in [5, 3, 8, 1, 9] {
+            heap.push(i);
+        }
+        assert_eq!(heap.len(), 5);
+        assert_eq!(heap.peek(), Some(&1));
+        assert_eq!(heap.pop(), Some(1));
+        assert_eq!(heap.pop(), Some(3));
+        assert_eq!(heap.len(), 3);
+    }
+
+    #[test]
+    fn max_heap() {
+        let mut heap: LeftistHeap<_> = [5, 3, 8].into_iter().map(Reverse).collect();
+        assert_eq!(heap.pop(), Some(Reverse(8)));
+
+        let mut heap = LeftistHeap::with_comparator(|a: &i32, b: &i32| b.cmp(a));
+        heap.extend([5, 3, 8]);
+        assert_eq!(heap.drain().collect::<Vec<_>>(), vec![8, 5, 3]);
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn merge_heaps() {
+        let mut a: LeftistHeap<_> = [1, 4, 7].into_iter().collect();
+        let b: LeftistHeap<_> = [2, 5, 8].into_iter().collect();
+        a.merge(b);
+
+        assert_eq!(a.len(), 6);
+        assert_eq!(a.into_iter().collect::<Vec<_>>(), vec![1, 2, 4, 5, 7, 8]);
+    }
+
+    //@ Pushing in descending order makes every new item the root, with the old heap as its
+    //@ left child, so the left spine is as long as the heap. Popping and dropping it must not
+    //@ recurse down that spine.
+    #[test]
+    fn deep_spine() {
+        let mut heap = LeftistHeap::new();
+        for i in (0..1_000_000).rev() {
+            heap.push(i);
+        }
+        assert_eq!(heap.pop(), Some(0));
+        assert_eq!(heap.pop(), Some(1));
+    }
+
+    //@ Draining a heap built from arbitrary input must match sorting it.
+    #[quickcheck]
+    fn heap_sort(input: Vec<i32>) -> bool {
+        let heap: LeftistHeap<_> = input.iter().copied().collect();
+        let mut sorted = input;
+        sorted.sort();
+        heap.len() == sorted.len() && heap.into_iter().eq(sorted)
+    }
+
+    //@ Interleaving pushes, pops and merges must behave like a sorted vec.
+    #[quickcheck]
+    fn matches_sorted_vec(ops: Vec<(u8, i32)>) -> bool {
+        let mut heap = LeftistHeap::new();
+        let mut reference: Vec<i32> = vec![];
+        for (op, val) in ops {
+            match op % 3 {
+                0 => {
+                    heap.push(val);
+                    reference.push(val);
+                }
+                1 => {
+                    reference.sort_by(|a, b| b.cmp(a));
+                    if heap.pop() != reference.pop() {
+                        return false;
+                    }
+                }
+                _ => {
+                    heap.merge([val, val / 2].into_iter().collect::<LeftistHeap<_>>());
+                    reference.extend([val, val / 2]);
+                }
+            }
+            if heap.len() != reference.len() || heap.peek() != reference.iter().min() {
+                return false;
+            }
+        }
+        true
+    }
 }
@@ -1,8 +1,25 @@
 pub mod avl;
+pub mod ball_tree;
+pub mod binary_tree;
 pub mod bk_tree;
+pub mod bplus_tree;
+pub mod btree;
+pub mod edit_history;
 pub mod fenwick;
+pub mod fibonacci_heap;
 pub mod in_order;
+pub mod interval_tree;
+pub mod kdtree;
 pub mod leftist_heap;
+pub mod pairing_heap;
+pub mod persistent_leftist_heap;
+pub mod radix_tree;
 pub mod rope;
 pub mod segment;
+pub mod sparse_table;
+pub mod splay;
+pub mod sqrt_decomposition;
+pub mod treap;
 pub mod tree_node;
+pub mod trie;
+pub mod wavelet_tree;
@@ -0,0 +1,380 @@
+//@ A [Pairing Heap](https://en.wikipedia.org/wiki/Pairing_heap) is a heap-ordered multiway tree.
+//@ It's one of the simplest heaps that supports `decrease_key` efficiently, which makes it a good
+//@ fit for Dijkstra's and Prim's algorithms.
+//@
+//@ - `push` and `peek` take O(1) time.
+//@ - `pop` and `delete` take O(log(n)) amortized time.
+//@ - `decrease_key` only cuts and links a single node, but it leaves more work for later pops,
+//@   and its exact amortized cost is an open problem. Fredman showed it can't be O(1): it's
+//@   Ω(log(log(n))). The best known upper bound, by Pettie, is O(2^(2 sqrt(log(log(n))))), which
+//@   grows slower than log(n). So, unlike the Fibonacci heap, a pairing heap doesn't bring
+//@   Dijkstra's algorithm down to O(E + V log(V)), although it's often faster in practice.
+//@ - `meld` takes O(m) time, where `m` is the size of the other heap, since its nodes are copied
+//@   into this heap's arena.
+//@
+//@ Nodes live in an arena (a `Vec`), and a `Handle` is an index into it. Every node points to its
+//@ first child and its next sibling, plus a `prev` pointer back to either its previous sibling or,
+//@ for a first child, its parent, so that any node can be cut out of the tree in O(1) time.
+//@
+//@ A handle stays valid until its item is popped or deleted; after that, its slot may be reused by
+//@ a later push. Every slot counts how many times it has been freed, and a handle remembers the
+//@ count from when it was made, so a stale handle is caught instead of reaching the new item:
+//@ `get` and `delete` return `None` for it, and `decrease_key` panics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: usize,
+    generation: u32,
+}
+
+struct Node<T> {
+    item: T,
+    child: Option<usize>,
+    sibling: Option<usize>,
+    prev: Option<usize>,
+}
+
+pub struct PairingHeap<T> {
+    nodes: Vec<Option<Node<T>>>,
+    generations: Vec<u32>,
+    free: Vec<usize>,
+    root: Option<usize>,
+    len: usize,
+}
+
+impl<T: Ord> Default for PairingHeap<T> {
+    fn default() -> Self {
+        PairingHeap::new()
+    }
+}
+
+impl<T: Ord> PairingHeap<T> {
+    pub fn new() -> Self {
+        PairingHeap {
+            nodes: vec![],
+            generations: vec![],
+            free: vec![],
+            root: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn node(&self, i: usize) -> &Node<T> {
+        self.nodes[i]
+            .as_ref()
+            .expect("handle refers to a removed item")
+    }
+
+    fn node_mut(&mut self, i: usize) -> &mut Node<T> {
+        self.nodes[i]
+            .as_mut()
+            .expect("handle refers to a removed item")
+    }
+
+    //@ The slot of a handle's item, if it is still in the heap.
+    fn slot(&self, handle: Handle) -> Option<usize> {
+        (self.generations.get(handle.index) == Some(&handle.generation)).then_some(handle.index)
+    }
+
+    //@ Returns the item behind a handle, if it is still in the heap.
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        Some(&self.node(self.slot(handle)?).item)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.root.map(|root| &self.node(root).item)
+    }
+
+    //@ Pushing creates a single node tree and links it with the root.
+    pub fn push(&mut self, item: T) -> Handle {
+        let node = Some(Node {
+            item,
+            child: None,
+            sibling: None,
+            prev: None,
+        });
+        let i = match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = node;
+                i
+            }
+            None => {
+                self.nodes.push(node);
+                self.generations.push(0);
+                self.nodes.len() - 1
+            }
+        };
+        self.add_root(i);
+        self.len += 1;
+        Handle {
+            index: i,
+            generation: self.generations[i],
+        }
+    }
+
+    //@ Linking two trees makes the root with the larger item the first child of the other.
+    fn link(&mut self, a: usize, b: usize) -> usize {
+        let (parent, child) = if self.node(b).item < self.node(a).item {
+            (b, a)
+        } else {
+            (a, b)
+        };
+        let first = self.node(parent).child;
+        let node = self.node_mut(child);
+        node.sibling = first;
+        node.prev = Some(parent);
+        if let Some(first) = first {
+            self.node_mut(first).prev = Some(child);
+        }
+        self.node_mut(parent).child = Some(child);
+        parent
+    }
+
+    fn add_root(&mut self, i: usize) {
+        self.root = Some(match self.root {
+            Some(root) => self.link(root, i),
+            None => i,
+        });
+    }
+
+    //@ Cutting a node detaches it (and its subtree) from its parent's list of children.
+    fn cut(&mut self, i: usize) {
+        let Node { prev, sibling, .. } = *self.node(i);
+        if let Some(prev) = prev {
+            if self.node(prev).child == Some(i) {
+                self.node_mut(prev).child = sibling;
+            } else {
+                self.node_mut(prev).sibling = sibling;
+            }
+        }
+        if let Some(sibling) = sibling {
+            self.node_mut(sibling).prev = prev;
+        }
+        let node = self.node_mut(i);
+        node.prev = None;
+        node.sibling = None;
+    }
+
+    //@ When a node is removed, its children are combined with the classic two-pass strategy:
+    //@ first link them in pairs from left to right, then link the pairs from right to left.
+    //@ This is what gives `pop` its O(log(n)) amortized bound.
+    fn merge_children(&mut self, first: Option<usize>) -> Option<usize> {
+        let mut children = vec![];
+        let mut cur = first;
+        while let Some(child) = cur {
+            let node = self.node_mut(child);
+            cur = node.sibling.take();
+            node.prev = None;
+            children.push(child);
+        }
+
+        let pairs: Vec<usize> = children
+            .chunks(2)
+            .map(|pair| match *pair {
+                [a, b] => self.link(a, b),
+                [a] => a,
+                _ => unreachable!(),
+            })
+            .collect();
+        pairs
+            .into_iter()
+            .rev()
+            .reduce(|acc, tree| self.link(tree, acc))
+    }
+
+    //@ Frees a node's slot and returns its item along with the merged tree of its children.
+    fn remove(&mut self, i: usize) -> (T, Option<usize>) {
+        let node = self.nodes[i]
+            .take()
+            .expect("handle refers to a removed item");
+        self.generations[i] = self.generations[i].wrapping_add(1);
+        self.free.push(i);
+        self.len -= 1;
+        (node.item, self.merge_children(node.child))
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let root = self.root?;
+        let (item, rest) = self.remove(root);
+        self.root = rest;
+        Some(item)
+    }
+
+    //@ Decreasing a key cuts the node's subtree out of the tree and links it back in as a root.
+    //@ The subtree stays heap-ordered, since its root only got smaller.
+    pub fn decrease_key(&mut self, handle: Handle, item: T) {
+        let i = self.slot(handle).expect("handle refers to a removed item");
+        assert!(
+            item <= self.node(i).item,
+            "decrease_key can't increase an item"
+        );
+        self.node_mut(i).item = item;
+        if self.root != Some(i) {
+            self.cut(i);
+            self.add_root(i);
+        }
+    }
+
+    //@ Deleting an arbitrary node cuts it out, removes it, and links its children back in.
+    pub fn delete(&mut self, handle: Handle) -> Option<T> {
+        let i = self.slot(handle)?;
+        if self.root == Some(i) {
+            return self.pop();
+        }
+        self.cut(i);
+        let (item, rest) = self.remove(i);
+        if let Some(rest) = rest {
+            self.add_root(rest);
+        }
+        Some(item)
+    }
+
+    //@ Melding moves all of `other`'s nodes into this heap's arena, which takes O(m) time for the
+    //@ copy, and then links the two roots. Since every index of `other` shifts by the same
+    //@ offset, this returns a function that translates `other`'s handles into handles of this
+    //@ heap.
+    pub fn meld(&mut self, mut other: PairingHeap<T>) -> impl Fn(Handle) -> Handle {
+        let offset = self.nodes.len();
+        let shift = |i: &mut Option<usize>| *i = i.map(|i| i + offset);
+        for node in other.nodes.iter_mut().flatten() {
+            shift(&mut node.child);
+            shift(&mut node.sibling);
+            shift(&mut node.prev);
+        }
+        self.nodes.append(&mut other.nodes);
+        self.generations.append(&mut other.generations);
+        self.free.extend(other.free.iter().map(|i| i + offset));
+        self.len += other.len;
+        if let Some(root) = other.root {
+            self.add_root(root + offset);
+        }
+        move |handle| Handle {
+            index: handle.index + offset,
+            generation: handle.generation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    #[test]
+    fn push_pop() {
+        let mut heap = PairingHeap::new();
+        for i in [5, 3, 8, 1, 9] {
+            heap.push(i);
+        }
+        assert_eq!(heap.peek(), Some(&1));
+        assert_eq!(heap.len(), 5);
+
+        let popped: Vec<_> = std::iter::from_fn(|| heap.pop()).collect();
+        assert_eq!(popped, vec![1, 3, 5, 8, 9]);
+    }
+
+    #[test]
+    fn decrease_key_and_delete() {
+        let mut heap = PairingHeap::new();
+        let handles: Vec<_> = [10, 20, 30, 40].into_iter().map(|i| heap.push(i)).collect();
+        heap.pop();
+
+        heap.decrease_key(handles[3], 5);
+        assert_eq!(heap.peek(), Some(&5));
+        assert_eq!(heap.get(handles[3]), Some(&5));
+
+        assert_eq!(heap.delete(handles[2]), Some(30));
+        assert_eq!(heap.delete(handles[2]), None);
+        assert_eq!(heap.pop(), Some(5));
+        assert_eq!(heap.pop(), Some(20));
+        assert_eq!(heap.pop(), None);
+    }
+
+    #[test]
+    fn meld_heaps() {
+        let mut a = PairingHeap::new();
+        a.push(3);
+        let mut b = PairingHeap::new();
+        let handle = b.push(7);
+        b.push(1);
+
+        let translate = a.meld(b);
+        a.decrease_key(translate(handle), 0);
+        assert_eq!(a.len(), 3);
+        assert_eq!(a.pop(), Some(0));
+        assert_eq!(a.pop(), Some(1));
+        assert_eq!(a.pop(), Some(3));
+    }
+
+    //@ A handle to a popped item doesn't reach the item that reuses its slot.
+    #[test]
+    fn stale_handle() {
+        let mut heap = PairingHeap::new();
+        let stale = heap.push(1);
+        assert_eq!(heap.pop(), Some(1));
+        let fresh = heap.push(2);
+        assert_eq!(heap.get(stale), None);
+        assert_eq!(heap.delete(stale), None);
+        assert_eq!(heap.get(fresh), Some(&2));
+    }
+
+    #[test]
+    #[should_panic(expected = "handle refers to a removed item")]
+    fn decrease_stale_handle() {
+        let mut heap = PairingHeap::new();
+        let stale = heap.push(1);
+        heap.pop();
+        heap.push(2);
+        heap.decrease_key(stale, 0);
+    }
+
+    //@ We run a random sequence of pushes, pops, decrease-keys and deletes against a plain vec of
+    //@ `(handle, item)` pairs. Items are tagged with a unique id so that the minimum is never
+    //@ ambiguous.
+    #[quickcheck]
+    fn matches_vec(ops: Vec<(u8, usize, i32)>) -> bool {
+        let mut heap = PairingHeap::new();
+        let mut reference: Vec<(Handle, (i32, usize))> = vec![];
+        for (id, (op, idx, val)) in ops.into_iter().enumerate() {
+            match op % 4 {
+                0 => reference.push((heap.push((val, id)), (val, id))),
+                1 => {
+                    let min = reference.iter().map(|&(_, item)| item).min();
+                    if heap.pop() != min {
+                        return false;
+                    }
+                    reference.retain(|&(_, item)| Some(item) != min);
+                }
+                2 if !reference.is_empty() => {
+                    let idx = idx % reference.len();
+                    let (handle, (old, id)) = reference[idx];
+                    let new = (old.saturating_sub((val.unsigned_abs() / 2) as i32), id);
+                    heap.decrease_key(handle, new);
+                    reference[idx].1 = new;
+                }
+                3 if !reference.is_empty() => {
+                    let (handle, old) = reference.swap_remove(idx % reference.len());
+                    if heap.delete(handle) != Some(old) {
+                        return false;
+                    }
+                }
+                _ => {}
+            }
+            if heap.len() != reference.len()
+                || heap.peek() != reference.iter().map(|(_, item)| item).min()
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
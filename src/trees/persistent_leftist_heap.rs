@@ -0,0 +1,267 @@
+use std::rc::Rc;
+
+//@ A persistent version of the [leftist heap](./leftist_heap.html).
+//@ The `Box`ed heap gives up its old version on every operation, since `insert` and `delete_min`
+//@ consume it. Here, nodes are shared through `Rc` and never mutated, so every operation returns
+//@ a new heap while the old one stays valid.
+//@
+//@ A merge only rebuilds the nodes along the right spines it walks down, which is O(log(n)) of
+//@ them, and shares every other subtree with the heaps it came from. This makes it cheap to keep
+//@ many versions of a frontier alive at once, like in branch-and-bound or beam search.
+//@
+//@ Rebuilt nodes need their own copy of the item, so items must be `Clone`. For large search
+//@ states, store them behind an `Rc` to keep that copy cheap.
+pub struct PersistentHeap<T> {
+    root: Option<Rc<Node<T>>>,
+}
+
+//@ Each node caches its rank (the length of its right spine) and the size of its subtree, so the
+//@ heap's length is O(1) to read.
+struct Node<T> {
+    rank: usize,
+    len: usize,
+    item: T,
+    left: Option<Rc<Node<T>>>,
+    right: Option<Rc<Node<T>>>,
+}
+
+//@ Cloning a heap is just bumping the reference count of its root.
+impl<T> Clone for PersistentHeap<T> {
+    fn clone(&self) -> Self {
+        PersistentHeap {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<T> Default for PersistentHeap<T> {
+    fn default() -> Self {
+        PersistentHeap { root: None }
+    }
+}
+
+fn rank<T>(heap: &Option<Rc<Node<T>>>) -> usize {
+    heap.as_ref().map_or(0, |node| node.rank)
+}
+
+fn len<T>(heap: &Option<Rc<Node<T>>>) -> usize {
+    heap.as_ref().map_or(0, |node| node.len)
+}
+
+impl<T: Ord + Clone> PersistentHeap<T> {
+    pub fn new() -> Self {
+        PersistentHeap::default()
+    }
+
+    pub fn len(&self) -> usize {
+        len(&self.root)
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn find_min(&self) -> Option<&T> {
+        self.root.as_ref().map(|node| &node.item)
+    }
+
+    pub fn insert(&self, item: T) -> Self {
+        let node = Rc::new(Node {
+            rank: 1,
+            len: 1,
+            item,
+            left: None,
+            right: None,
+        });
+        PersistentHeap {
+            root: merge(self.root.clone(), Some(node)),
+        }
+    }
+
+    //@ Deleting the minimum merges the root's children, which are both shared as they are.
+    pub fn delete_min(&self) -> Self {
+        match &self.root {
+            None => PersistentHeap::default(),
+            Some(node) => PersistentHeap {
+                root: merge(node.left.clone(), node.right.clone()),
+            },
+        }
+    }
+
+    pub fn merge(&self, other: &Self) -> Self {
+        PersistentHeap {
+            root: merge(self.root.clone(), other.root.clone()),
+        }
+    }
+
+    //@ Iterates in sorted order without touching this heap, by walking down a chain of cheap
+    //@ `delete_min` versions.
+    pub fn iter(&self) -> Iter<T> {
+        Iter { heap: self.clone() }
+    }
+}
+
+//@ Merging walks down the right spines like the mutable heap does, but instead of reusing the
+//@ nodes it passes through, it remembers their items and left children, and builds new nodes for
+//@ them on the way back up.
+fn merge<T: Ord + Clone>(
+    mut a: Option<Rc<Node<T>>>,
+    mut b: Option<Rc<Node<T>>>,
+) -> Option<Rc<Node<T>>> {
+    let mut spine = vec![];
+    let mut merged = loop {
+        match (a, b) {
+            (None, h) | (h, None) => break h,
+            (Some(h1), Some(h2)) => {
+                let (top, other) = if h1.item <= h2.item {
+                    (h1, h2)
+                } else {
+                    (h2, h1)
+                };
+                spine.push((top.item.clone(), top.left.clone()));
+                a = top.right.clone();
+                b = Some(other);
+            }
+        }
+    };
+
+    while let Some((item, left)) = spine.pop() {
+        let (left, right) = if rank(&left) >= rank(&merged) {
+            (left, merged)
+        } else {
+            (merged, left)
+        };
+        merged = Some(Rc::new(Node {
+            rank: rank(&right) + 1,
+            len: len(&left) + len(&right) + 1,
+            item,
+            left,
+            right,
+        }));
+    }
+    merged
+}
+
+//@ The last reference to a long left spine would otherwise be dropped recursively, so, like
+//@ `LeftistHeap`, nodes are taken apart one at a time. Only nodes that aren't shared with
+//@ another version are actually freed.
+impl<T> Drop for Node<T> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Rc<Node<T>>> = self.left.take().into_iter().collect();
+        stack.extend(self.right.take());
+        while let Some(node) = stack.pop() {
+            if let Ok(mut node) = Rc::try_unwrap(node) {
+                stack.extend(node.left.take());
+                stack.extend(node.right.take());
+            }
+        }
+    }
+}
+
+pub struct Iter<T> {
+    heap: PersistentHeap<T>,
+}
+
+impl<T: Ord + Clone> Iterator for Iter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let min = self.heap.find_min()?.clone();
+        self.heap = self.heap.delete_min();
+        Some(min)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.heap.len(), Some(self.heap.len()))
+    }
+}
+
+impl<T: Ord + Clone> FromIterator<T> for PersistentHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(PersistentHeap::new(), |heap, item| heap.insert(item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    #[test]
+    fn versions_persist() {
+        let empty = PersistentHeap::new();
+        let a = empty.insert(5).insert(3);
+        let b = a.insert(1);
+        let c = b.delete_min().delete_min();
+
+        assert_eq!(empty.find_min(), None);
+        assert_eq!(a.iter().collect::<Vec<_>>(), vec![3, 5]);
+        assert_eq!(b.iter().collect::<Vec<_>>(), vec![1, 3, 5]);
+        assert_eq!(c.iter().collect::<Vec<_>>(), vec![5]);
+        assert_eq!((a.len(), b.len(), c.len()), (2, 3, 1));
+    }
+
+    //@ Deleting the minimum of a heap whose root has a single child returns that child as is.
+    #[test]
+    fn shares_structure() {
+        let heap = PersistentHeap::new().insert(2).insert(1);
+        let rest = heap.delete_min();
+
+        let child = heap.root.as_ref().unwrap().left.as_ref().unwrap();
+        assert!(Rc::ptr_eq(child, rest.root.as_ref().unwrap()));
+    }
+
+    #[test]
+    fn merge_versions() {
+        let a: PersistentHeap<_> = [4, 1, 7].into_iter().collect();
+        let b: PersistentHeap<_> = [3, 9].into_iter().collect();
+
+        let merged = a.merge(&b);
+        assert_eq!(merged.iter().collect::<Vec<_>>(), vec![1, 3, 4, 7, 9]);
+        assert_eq!(a.len() + b.len(), merged.len());
+    }
+
+    #[test]
+    fn deep_spine() {
+        let heap: PersistentHeap<_> = (0..1_000_000).rev().collect();
+        assert_eq!(heap.delete_min().find_min(), Some(&1));
+    }
+
+    //@ We branch off random earlier versions and check at the end that every version still
+    //@ matches its own sorted vec.
+    #[quickcheck]
+    fn versions_match_vecs(ops: Vec<(usize, usize, bool, i32)>) -> bool {
+        let mut versions = vec![(PersistentHeap::new(), vec![])];
+        for (from, with, insert, val) in ops {
+            let (heap, items) = &versions[from % versions.len()];
+            let (heap, mut items) = (heap.clone(), items.clone());
+            let next = if insert {
+                items.push(val);
+                heap.insert(val)
+            } else if val % 2 == 0 {
+                items.sort();
+                if !items.is_empty() {
+                    items.remove(0);
+                }
+                heap.delete_min()
+            } else {
+                //@ Merging a version with itself doubles it, so we cap the size.
+                let (other, other_items) = &versions[with % versions.len()];
+                if items.len() + other_items.len() > 1000 {
+                    continue;
+                }
+                items.extend(other_items);
+                heap.merge(other)
+            };
+            versions.push((next, items));
+        }
+
+        versions.into_iter().all(|(heap, mut items)| {
+            items.sort();
+            heap.len() == items.len() && heap.iter().eq(items)
+        })
+    }
+}
@@ -0,0 +1,332 @@
+use std::mem;
+
+//@ A [Radix Tree](https://en.wikipedia.org/wiki/Radix_tree) is a [trie](./trie.html) where
+//@ every chain of nodes that have a single child and no value is merged into one edge, labeled
+//@ with the whole run of symbols. A trie of `n` keys can have a node for every symbol of every
+//@ key, but a radix tree has at most `2n` nodes, since every node either holds a key or branches.
+//@ That saves a lot of memory and pointer chasing for long keys that don't share much, like URLs
+//@ or file paths.
+//@
+//@ The price is that edges have to be split when a new key branches off in the middle of one,
+//@ and merged back when removing a key leaves a node with a single child and no value.
+pub struct RadixTree<S, V> {
+    root: Node<S, V>,
+    len: usize,
+}
+
+//@ Edge labels are never empty, and the labels of the children of a node all start with a
+//@ different symbol, which they're sorted by.
+struct Node<S, V> {
+    val: Option<V>,
+    children: Vec<(Vec<S>, Node<S, V>)>,
+}
+
+impl<S, V> Node<S, V> {
+    fn new(val: Option<V>) -> Self {
+        Node {
+            val,
+            children: vec![],
+        }
+    }
+}
+
+fn common_prefix<S: Eq>(a: &[S], b: &[S]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+impl<S: Ord + Copy, V> Node<S, V> {
+    //@ Finds the child whose label starts with the first symbol of `key`.
+    fn search(&self, key: &[S]) -> Result<usize, usize> {
+        let first = key.first().expect("keys below the root aren't empty");
+        self.children
+            .binary_search_by_key(first, |(label, _)| label[0])
+    }
+
+    //@ Removes `key` below this node. A child that's left with no value is dropped if it has no
+    //@ children either, and merged with its only child if it has one.
+    fn remove(&mut self, key: &[S]) -> Option<V> {
+        if key.is_empty() {
+            return self.val.take();
+        }
+        let i = self.search(key).ok()?;
+        let (label, child) = &mut self.children[i];
+        let rest = key.strip_prefix(label.as_slice())?;
+        let removed = child.remove(rest)?;
+        if child.val.is_none() {
+            match child.children.len() {
+                0 => {
+                    self.children.remove(i);
+                }
+                1 => {
+                    let (suffix, grandchild) = child.children.pop().expect("there's one child");
+                    label.extend(suffix);
+                    *child = grandchild;
+                }
+                _ => {}
+            }
+        }
+        Some(removed)
+    }
+}
+
+impl<S: Ord + Copy, V> Default for RadixTree<S, V> {
+    fn default() -> Self {
+        RadixTree::new()
+    }
+}
+
+impl<S: Ord + Copy, V> RadixTree<S, V> {
+    pub fn new() -> Self {
+        RadixTree {
+            root: Node::new(None),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    //@ Walks down as far as the edges match the key. There are three ways that can end:
+    //@ - The key runs out at a node, which gets the value.
+    //@ - No edge starts with the next symbol, so the rest of the key becomes a new edge.
+    //@ - The key and an edge part ways in the middle of the edge, which gets split there, and the
+    //@   node in the middle either gets the value or the rest of the key as a second child.
+    pub fn insert(&mut self, mut key: &[S], val: V) -> Option<V> {
+        let mut node = &mut self.root;
+        while !key.is_empty() {
+            let i = match node.search(key) {
+                Ok(i) => i,
+                Err(i) => {
+                    node.children
+                        .insert(i, (key.to_vec(), Node::new(Some(val))));
+                    self.len += 1;
+                    return None;
+                }
+            };
+            let (label, child) = &mut node.children[i];
+            let common = common_prefix(label, key);
+            if common < label.len() {
+                let suffix = label.split_off(common);
+                let old = mem::replace(child, Node::new(None));
+                child.children.push((suffix, old));
+                if common < key.len() {
+                    child
+                        .children
+                        .push((key[common..].to_vec(), Node::new(Some(val))));
+                    child.children.sort_by_key(|(label, _)| label[0]);
+                } else {
+                    child.val = Some(val);
+                }
+                self.len += 1;
+                return None;
+            }
+            key = &key[common..];
+            node = child;
+        }
+        let old = node.val.replace(val);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    pub fn remove(&mut self, key: &[S]) -> Option<V> {
+        let removed = self.root.remove(key)?;
+        self.len -= 1;
+        Some(removed)
+    }
+
+    //@ Walks down while the whole label of an edge matches. If the key ends in the middle of an
+    //@ edge, we stop there, and return how much of the label is left over.
+    fn find(&self, mut key: &[S]) -> Option<(&Node<S, V>, &[S])> {
+        let mut node = &self.root;
+        while !key.is_empty() {
+            let (label, child) = &node.children[node.search(key).ok()?];
+            let common = common_prefix(label, key);
+            if common == key.len() {
+                return Some((child, &label[common..]));
+            }
+            if common < label.len() {
+                return None;
+            }
+            key = &key[common..];
+            node = child;
+        }
+        Some((node, &[]))
+    }
+
+    pub fn get(&self, key: &[S]) -> Option<&V> {
+        match self.find(key)? {
+            (node, []) => node.val.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn contains_key(&self, key: &[S]) -> bool {
+        self.get(key).is_some()
+    }
+
+    //@ Like in the trie, the longest key that is a prefix of `key` is the last value on the path
+    //@ that spells out `key`, but we can only stop at nodes, so an edge has to match completely.
+    pub fn longest_prefix<'k>(&self, key: &'k [S]) -> Option<(&'k [S], &V)> {
+        let mut node = &self.root;
+        let mut longest = node.val.as_ref().map(|val| (0, val));
+        let mut depth = 0;
+        while depth < key.len() {
+            let Ok(i) = node.search(&key[depth..]) else {
+                break;
+            };
+            let (label, child) = &node.children[i];
+            if !key[depth..].starts_with(label) {
+                break;
+            }
+            depth += label.len();
+            node = child;
+            if let Some(val) = &node.val {
+                longest = Some((depth, val));
+            }
+        }
+        longest.map(|(len, val)| (&key[..len], val))
+    }
+
+    //@ All keys that start with `prefix`, in lexicographic order. If the prefix ends in the
+    //@ middle of an edge, the whole subtree below that edge matches, and the rest of the label is
+    //@ part of every key in it.
+    pub fn starts_with(&self, prefix: &[S]) -> Iter<'_, S, V> {
+        Iter {
+            stack: self
+                .find(prefix)
+                .map(|(node, rest)| ([prefix, rest].concat(), node))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, S, V> {
+        self.starts_with(&[])
+    }
+}
+
+//@ The same pre-order traversal as the trie's, except that keys grow by a whole label at a time.
+pub struct Iter<'a, S, V> {
+    stack: Vec<(Vec<S>, &'a Node<S, V>)>,
+}
+
+impl<'a, S: Copy, V> Iterator for Iter<'a, S, V> {
+    type Item = (Vec<S>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((key, node)) = self.stack.pop() {
+            for (label, child) in node.children.iter().rev() {
+                self.stack.push(([key.as_slice(), label].concat(), child));
+            }
+            if let Some(val) = &node.val {
+                return Some((key, val));
+            }
+        }
+        None
+    }
+}
+
+impl<S: Ord + Copy, V, K: AsRef<[S]>> FromIterator<(K, V)> for RadixTree<S, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut tree = RadixTree::new();
+        for (key, val) in iter {
+            tree.insert(key.as_ref(), val);
+        }
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    fn labels<V>(node: &Node<u8, V>) -> Vec<&str> {
+        node.children
+            .iter()
+            .map(|(label, _)| std::str::from_utf8(label).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn ex1() {
+        let mut tree: RadixTree<u8, usize> = ["romane", "romanus", "romulus", "rubens", "ruber"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, word)| (word, i))
+            .collect();
+        assert_eq!(labels(&tree.root), ["r"]);
+        let r = &tree.root.children[0].1;
+        assert_eq!(labels(r), ["om", "ube"]);
+        assert_eq!(labels(&r.children[0].1), ["an", "ulus"]);
+
+        assert_eq!(tree.get(b"romanus"), Some(&1));
+        assert_eq!(tree.get(b"roman"), None);
+        assert_eq!(tree.get(b"romanusx"), None);
+        let completions: Vec<_> = tree.starts_with(b"roma").map(|(key, _)| key).collect();
+        assert_eq!(completions, [b"romane".to_vec(), b"romanus".to_vec()]);
+        assert_eq!(
+            tree.longest_prefix(b"rubenstein"),
+            Some((b"rubens".as_slice(), &3))
+        );
+
+        //@ Removing `romulus` leaves the `om` node with a single child, so the two edges merge.
+        assert_eq!(tree.remove(b"romulus"), Some(2));
+        let r = &tree.root.children[0].1;
+        assert_eq!(labels(r), ["oman", "ube"]);
+        assert_eq!(tree.len(), 4);
+    }
+
+    //@ Checks that edges aren't empty and start with distinct, sorted symbols, and that every
+    //@ node other than the root has a value or branches.
+    fn is_compressed<S: Ord, V>(node: &Node<S, V>, is_root: bool) -> bool {
+        (is_root || node.val.is_some() || node.children.len() >= 2)
+            && node.children.iter().all(|(label, _)| !label.is_empty())
+            && node.children.windows(2).all(|w| w[0].0[0] < w[1].0[0])
+            && node
+                .children
+                .iter()
+                .all(|(_, child)| is_compressed(child, false))
+    }
+
+    #[quickcheck]
+    fn matches_btreemap(ops: Vec<(bool, Vec<u8>)>, query: Vec<u8>) -> bool {
+        let small = |key: Vec<u8>| -> Vec<u8> { key.into_iter().take(6).map(|b| b % 3).collect() };
+        let mut tree = RadixTree::new();
+        let mut expected = BTreeMap::new();
+        for (i, (insert, key)) in ops.into_iter().enumerate() {
+            let key = small(key);
+            let same = if insert {
+                tree.insert(&key, i) == expected.insert(key, i)
+            } else {
+                tree.remove(&key) == expected.remove(&key)
+            };
+            if !same || tree.len() != expected.len() || !is_compressed(&tree.root, true) {
+                return false;
+            }
+        }
+        let query = small(query);
+        let prefix = &query[..query.len() / 2];
+        let longest = (0..=query.len())
+            .rev()
+            .find_map(|len| expected.get_key_value(&query[..len]));
+        tree.iter().eq(expected.iter().map(|(k, v)| (k.clone(), v)))
+            && tree.starts_with(prefix).eq(expected
+                .iter()
+                .filter(|(k, _)| k.starts_with(prefix))
+                .map(|(k, v)| (k.clone(), v)))
+            && tree.longest_prefix(&query) == longest.map(|(k, v)| (k.as_slice(), v))
+            && expected.keys().all(|k| tree.get(k) == expected.get(k))
+    }
+}
@@ -1,18 +1,112 @@
 // From: https://github.com/kavirajk/rope
-use std::rc::Rc;
+use std::{
+    fmt,
+    ops::{Add, Bound, RangeBounds, Sub},
+    rc::Rc,
+};
 
-#[derive(Debug)]
+use crate::strings::rabin_karp::RollingHash;
+
+//@ A [Rope](https://en.wikipedia.org/wiki/Rope_(data_structure)) is a binary tree of string
+//@ pieces, which makes inserting and deleting in the middle of a large text cheap compared to a
+//@ flat `String`.
+//@
+//@ All offsets in the API are char offsets, not byte offsets, so the rope can never be split in
+//@ the middle of a UTF-8 code point. To find its way down the tree by chars while still slicing
+//@ strings by bytes, every node caches both counts.
+//@
+//@ Nodes also count the newlines below them, which lets an editor jump between char offsets and
+//@ `(line, column)` positions in O(log(n)) time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    pub bytes: usize,
+    pub chars: usize,
+    pub newlines: usize,
+}
+
+impl Metrics {
+    fn of(s: &str) -> Self {
+        Metrics {
+            bytes: s.len(),
+            chars: s.chars().count(),
+            newlines: s.bytes().filter(|&b| b == b'\n').count(),
+        }
+    }
+}
+
+impl Add for Metrics {
+    type Output = Metrics;
+
+    fn add(self, other: Metrics) -> Metrics {
+        Metrics {
+            bytes: self.bytes + other.bytes,
+            chars: self.chars + other.chars,
+            newlines: self.newlines + other.newlines,
+        }
+    }
+}
+
+impl Sub for Metrics {
+    type Output = Metrics;
+
+    fn sub(self, other: Metrics) -> Metrics {
+        Metrics {
+            bytes: self.bytes - other.bytes,
+            chars: self.chars - other.chars,
+            newlines: self.newlines - other.newlines,
+        }
+    }
+}
+
+//@ ## Balancing
+//@ Left alone, repeated splits and joins produce a degenerate, deep tree, and leaves of arbitrary
+//@ size: huge leaves make every edit inside them slow, while tiny ones waste memory and depth.
+//@ So the rope keeps two invariants:
+//@ - It's balanced like an AVL tree: the depths of the two children of any node differ by at
+//@   most one, so the depth is O(log(n)).
+//@ - Every leaf holds at most `MAX_LEAF` bytes, and every leaf except the first and the last
+//@   holds at least `MIN_LEAF` bytes. The first and last leaves are exempt because a split can
+//@   always leave a small piece at either end; they get fixed up when they're joined to
+//@   something else.
+pub const MIN_LEAF: usize = 256;
+pub const MAX_LEAF: usize = 1024;
+
+//@ UTF-8 continuation bytes look like `0b10xxxxxx`; every other byte starts a char.
+fn is_continuation(b: u8) -> bool {
+    b & 0xC0 == 0x80
+}
+
+//@ Returns the byte offset of the char at `chars`, or the length of `s` if it's past the end.
+fn char_to_byte(s: &str, chars: usize) -> usize {
+    s.char_indices().nth(chars).map_or(s.len(), |(i, _)| i)
+}
+
+//@ A node's weight is the metrics of its left subtree, which is all we need to decide which way
+//@ to go for a given offset. It also keeps its total, so the length of a rope is O(1) to read,
+//@ and its height for balancing.
+//@
+//@ ## Persistence
+//@ Children are shared through `Rc`, so cloning a rope only copies its root. Edits never change
+//@ a shared node: splits and joins build new nodes on top of the old subtrees, and in-place leaf
+//@ edits copy the path they walk down with `Rc::make_mut`. Every edit thus creates O(log(n))
+//@ new nodes and shares the rest with the old version, which is what makes cheap snapshots and
+//@ [undo](./edit_history.html) possible.
+#[derive(Clone, Debug)]
 pub struct Node {
-    weight: usize,
-    left: Option<Box<Rope>>,
-    right: Option<Box<Rope>>,
+    weight: Metrics,
+    total: Metrics,
+    height: usize,
+    left: Rc<Rope>,
+    right: Rc<Rope>,
 }
 
-#[derive(Debug)]
+//@ A leaf is a view of `metrics.bytes` bytes of a shared buffer starting at `start`, so
+//@ splitting a leaf doesn't copy any text. Both ends always sit on char boundaries.
+#[derive(Clone, Debug)]
 pub struct Leaf {
     buf: Rc<String>,
     start: usize,
-    end: usize,
+    metrics: Metrics,
 }
 
 impl Leaf {
@@ -20,36 +114,35 @@ impl Leaf {
         Self {
             buf: Rc::new(s.to_string()),
             start: 0,
-            end: s.len() - 1,
+            metrics: Metrics::of(s),
         }
     }
 
-    fn weight(&self) -> usize {
-        self.end - self.start + 1
+    fn as_str(&self) -> &str {
+        &self.buf[self.start..self.start + self.metrics.bytes]
     }
 
+    //@ Splitting a leaf turns the char offset into a byte offset and hands out two views of the
+    //@ same buffer. Offsets past the end are clamped.
     fn split(&self, offset: usize) -> (Leaf, Leaf) {
-        if offset == 0 {
-            return (Leaf::new(""), Leaf::new(&self.buf.as_ref().clone()));
-        }
-
-        if offset >= self.weight() {
-            return (Leaf::new(&self.buf.as_ref().clone()), Leaf::new(""));
-        }
-
-        let (left, right) = self.buf.split_at(self.start + offset);
-        ((Leaf::new(left)), Leaf::new(right))
-    }
-
-    fn report(&self, start: usize, end: usize) -> Option<String> {
-        match start >= self.start && end <= self.end {
-            true => Some(self.buf[start..end + 1].to_string()),
-            false => None,
-        }
+        let s = self.as_str();
+        let left = Metrics::of(&s[..char_to_byte(s, offset)]);
+        (
+            Leaf {
+                buf: Rc::clone(&self.buf),
+                start: self.start,
+                metrics: left,
+            },
+            Leaf {
+                buf: Rc::clone(&self.buf),
+                start: self.start + left.bytes,
+                metrics: self.metrics - left,
+            },
+        )
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Rope {
     Node(Node),
     Leaf(Leaf),
@@ -57,50 +150,96 @@ pub enum Rope {
 
 impl Rope {
     pub fn new(s: &str) -> Rope {
-        Rope::Leaf(Leaf::new(s))
+        Rope::from(s.to_string())
+    }
+
+    //@ A string longer than `MAX_LEAF` is cut into evenly sized leaves that all share one buffer,
+    //@ so each of them is between roughly `MAX_LEAF / 2` and `MAX_LEAF` bytes long.
+    fn from_buf(buf: Rc<String>) -> Rope {
+        let s = buf.as_str();
+        let pieces = s.len().div_ceil(MAX_LEAF - 3).max(1);
+        let mut leaves = vec![];
+        let mut start = 0;
+        for i in 1..=pieces {
+            let mut end = s.len() * i / pieces;
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            leaves.push(Leaf {
+                buf: Rc::clone(&buf),
+                start,
+                metrics: Metrics::of(&s[start..end]),
+            });
+            start = end;
+        }
+        Rope::from_leaves(leaves)
+    }
+
+    fn from_leaves(mut leaves: Vec<Leaf>) -> Rope {
+        if leaves.len() == 1 {
+            return Rope::Leaf(leaves.pop().unwrap());
+        }
+        let right = leaves.split_off(leaves.len() / 2);
+        Rope::node(Rope::from_leaves(leaves), Rope::from_leaves(right))
+    }
+
+    fn node(left: Rope, right: Rope) -> Rope {
+        let weight = left.metrics();
+        Rope::Node(Node {
+            weight,
+            total: weight + right.metrics(),
+            height: left.depth().max(right.depth()) + 1,
+            left: Rc::new(left),
+            right: Rc::new(right),
+        })
     }
 
     pub fn buf(&self) -> Option<&str> {
         match self {
             Rope::Node(_) => None,
-            Rope::Leaf(leaf) => Some(&leaf.buf),
+            Rope::Leaf(leaf) => Some(leaf.as_str()),
+        }
+    }
+
+    fn metrics(&self) -> Metrics {
+        match self {
+            Rope::Node(node) => node.total,
+            Rope::Leaf(leaf) => leaf.metrics,
         }
     }
 
+    //@ Returns the char at char offset `i`.
     pub fn index(&self, i: usize) -> Option<char> {
         match self {
-            Rope::Node(node) => match i <= node.weight {
-                true => node.left.as_ref()?.index(i),
-                false => node.right.as_ref()?.index(i - node.weight),
+            Rope::Node(node) => match i < node.weight.chars {
+                true => node.left.index(i),
+                false => node.right.index(i - node.weight.chars),
             },
-            Rope::Leaf(leaf) => leaf.buf.chars().nth(i),
+            Rope::Leaf(leaf) => leaf.as_str().chars().nth(i),
         }
     }
 
+    //@ The number of chars in the left subtree, or in the leaf itself.
     pub fn weight(&self) -> usize {
         match self {
-            Rope::Node(node) => node.weight,
-            Rope::Leaf(leaf) => leaf.weight(),
+            Rope::Node(node) => node.weight.chars,
+            Rope::Leaf(leaf) => leaf.metrics.chars,
         }
     }
 
+    //@ The length of the rope in chars.
     pub fn len(&self) -> usize {
-        match self {
-            Rope::Node(node) => {
-                node.weight
-                    + node
-                        .right
-                        .as_ref()
-                        .expect("right node cannot be None")
-                        .len()
-            }
-            Rope::Leaf(leaf) => leaf.weight(),
-        }
+        self.metrics().chars
+    }
+
+    //@ The length of the rope in bytes.
+    pub fn len_bytes(&self) -> usize {
+        self.metrics().bytes
     }
 
     #[must_use]
     pub fn is_empty(&self) -> bool {
-        self.len() == 0
+        self.len_bytes() == 0
     }
 
     pub fn is_leaf(&self) -> bool {
@@ -111,52 +250,210 @@ impl Rope {
         matches!(self, Rope::Node(_))
     }
 
+    //@ The number of nodes on the longest path from the root to a leaf.
+    pub fn depth(&self) -> usize {
+        match self {
+            Rope::Node(node) => node.height,
+            Rope::Leaf(_) => 0,
+        }
+    }
+
+    fn first_leaf(&self) -> &Leaf {
+        match self {
+            Rope::Node(node) => node.left.first_leaf(),
+            Rope::Leaf(leaf) => leaf,
+        }
+    }
+
+    fn last_leaf(&self) -> &Leaf {
+        match self {
+            Rope::Node(node) => node.right.last_leaf(),
+            Rope::Leaf(leaf) => leaf,
+        }
+    }
+
+    fn to_text(&self) -> String {
+        self.chunks().collect()
+    }
+
+    //@ ## Reading
+    //@ The iterators below borrow the leaves' text directly, so reading a rope never copies it.
+    pub fn chunks(&self) -> Chunks<'_> {
+        Chunks::new(self, 0, self.len())
+    }
+
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.chunks().flat_map(str::chars)
+    }
+
+    pub fn bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.chunks().flat_map(str::bytes)
+    }
+
+    //@ Borrows the chars in `range` without copying them. Like slicing a `str`, this panics if the
+    //@ range is out of bounds.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> RopeSlice<'_> {
+        let (start, end) = resolve(range, self.len());
+        RopeSlice {
+            rope: self,
+            start,
+            end,
+        }
+    }
+
+    //@ Joining two ropes puts the last leaf of `left` next to the first leaf of `right`, and
+    //@ either of them might be too small to be in the middle of a rope. If so, we split the
+    //@ leaves around the seam off both sides until we have at least `MIN_LEAF` bytes, and cut
+    //@ that text into new leaves. Small ropes are simply merged into a single leaf.
+    //@ It keeps taking `Box`es so existing callers don't break.
+    #[allow(clippy::boxed_local)]
     pub fn join(left: Box<Rope>, right: Box<Rope>) -> Rope {
-        Rope::Node(Node {
-            weight: left.len(),
-            left: Some(left),
-            right: Some(right),
-        })
+        let (mut left, mut right) = (*left, *right);
+        if left.is_empty() {
+            return right;
+        }
+        if right.is_empty() {
+            return left;
+        }
+        if left.len_bytes() + right.len_bytes() <= MAX_LEAF {
+            return Rope::new(&(left.to_text() + &right.to_text()));
+        }
+        if left.last_leaf().metrics.bytes >= MIN_LEAF
+            && right.first_leaf().metrics.bytes >= MIN_LEAF
+        {
+            return Rope::concat(left, right);
+        }
+
+        let mut seam = String::new();
+        while seam.len() < MIN_LEAF && !(left.is_empty() && right.is_empty()) {
+            if !left.is_empty() {
+                let at = left.len() - left.last_leaf().metrics.chars;
+                let (rest, last) = left.split_off(at);
+                seam.insert_str(0, last.buf().expect("a single leaf"));
+                left = rest;
+            }
+            if !right.is_empty() {
+                let at = right.first_leaf().metrics.chars;
+                let (first, rest) = right.split_off(at);
+                seam.push_str(first.buf().expect("a single leaf"));
+                right = rest;
+            }
+        }
+        Rope::concat(Rope::concat(left, Rope::new(&seam)), right)
     }
 
+    //@ Concatenation works like joining two AVL trees: if one side is more than one level
+    //@ deeper, we walk down its inner spine until we find a subtree of about the same depth as
+    //@ the other side, join them there, and rebalance on the way back up. This takes
+    //@ O(|depth(left) - depth(right)|) time.
+    fn concat(left: Rope, right: Rope) -> Rope {
+        if left.is_empty() {
+            return right;
+        }
+        if right.is_empty() {
+            return left;
+        }
+        let (hl, hr) = (left.depth(), right.depth());
+        if hl > hr + 1 {
+            let Rope::Node(node) = left else {
+                unreachable!()
+            };
+            let right = Rope::concat(Rc::unwrap_or_clone(node.right), right);
+            Rope::balance(Rc::unwrap_or_clone(node.left), right)
+        } else if hr > hl + 1 {
+            let Rope::Node(node) = right else {
+                unreachable!()
+            };
+            let left = Rope::concat(left, Rc::unwrap_or_clone(node.left));
+            Rope::balance(left, Rc::unwrap_or_clone(node.right))
+        } else {
+            Rope::node(left, right)
+        }
+    }
+
+    //@ Builds a node from two subtrees whose depths differ by at most two, with a single or
+    //@ double rotation if they differ by exactly two.
+    fn balance(left: Rope, right: Rope) -> Rope {
+        let (hl, hr) = (left.depth(), right.depth());
+        if hl > hr + 1 {
+            let Rope::Node(l) = left else { unreachable!() };
+            if l.left.depth() >= l.right.depth() {
+                Rope::node(
+                    Rc::unwrap_or_clone(l.left),
+                    Rope::node(Rc::unwrap_or_clone(l.right), right),
+                )
+            } else {
+                let Rope::Node(lr) = Rc::unwrap_or_clone(l.right) else {
+                    unreachable!()
+                };
+                Rope::node(
+                    Rope::node(Rc::unwrap_or_clone(l.left), Rc::unwrap_or_clone(lr.left)),
+                    Rope::node(Rc::unwrap_or_clone(lr.right), right),
+                )
+            }
+        } else if hr > hl + 1 {
+            let Rope::Node(r) = right else { unreachable!() };
+            if r.right.depth() >= r.left.depth() {
+                Rope::node(
+                    Rope::node(left, Rc::unwrap_or_clone(r.left)),
+                    Rc::unwrap_or_clone(r.right),
+                )
+            } else {
+                let Rope::Node(rl) = Rc::unwrap_or_clone(r.left) else {
+                    unreachable!()
+                };
+                Rope::node(
+                    Rope::node(left, Rc::unwrap_or_clone(rl.left)),
+                    Rope::node(Rc::unwrap_or_clone(rl.right), Rc::unwrap_or_clone(r.right)),
+                )
+            }
+        } else {
+            Rope::node(left, right)
+        }
+    }
+
+    //@ Splits the rope at char offset `offset` into the chars before it and the chars from it
+    //@ onwards. The contents are moved out, so `self` is left empty.
     pub fn split(&mut self, offset: usize) -> (Rope, Rope) {
+        std::mem::replace(self, Rope::new("")).split_off(offset)
+    }
+
+    //@ Splitting goes down to the leaf containing the offset and concatenates the pieces back
+    //@ together on the way up. The concatenations telescope, so this takes O(log(n)) time.
+    fn split_off(self, offset: usize) -> (Rope, Rope) {
         match self {
             Rope::Leaf(leaf) => {
                 let (l, r) = leaf.split(offset);
                 (Rope::Leaf(l), Rope::Leaf(r))
             }
             Rope::Node(node) => {
-                let w = node.weight;
-
-                // < not <= because w - always length of the string (offset -1)
-                if offset < w {
-                    let (l, r) = node
-                        .left
-                        .as_mut()
-                        .expect("left child cannot be empty")
-                        .split(offset);
-                    let r = Rope::join(
-                        Box::new(r),
-                        node.right.take().expect("right child cannot be empty"),
-                    );
-                    return (l, r);
+                let w = node.weight.chars;
+                match offset.cmp(&w) {
+                    std::cmp::Ordering::Less => {
+                        let (l, r) = Rc::unwrap_or_clone(node.left).split_off(offset);
+                        (l, Rope::concat(r, Rc::unwrap_or_clone(node.right)))
+                    }
+                    std::cmp::Ordering::Equal => (
+                        Rc::unwrap_or_clone(node.left),
+                        Rc::unwrap_or_clone(node.right),
+                    ),
+                    std::cmp::Ordering::Greater => {
+                        let (l, r) = Rc::unwrap_or_clone(node.right).split_off(offset - w);
+                        (Rope::concat(Rc::unwrap_or_clone(node.left), l), r)
+                    }
                 }
-
-                let (l, r) = node
-                    .right
-                    .as_mut()
-                    .expect("right child cannot be empty")
-                    .split(offset - w);
-                let l = Rope::join(
-                    Box::new(l),
-                    node.right.take().expect("left child cannot be empty"),
-                );
-                (l, r)
             }
         }
     }
 
+    //@ Inserts `s` at char offset `offset`, moving the contents out of `self` like `split`.
+    //@ Most edits are small, so if `s` fits into the leaf at `offset`, we rewrite just that leaf
+    //@ and update the metrics on the path to it, which leaves the shape of the tree untouched.
     pub fn insert(&mut self, s: &str, offset: usize) -> Rope {
+        if self.insert_in_leaf(s, offset, Metrics::of(s)) {
+            return std::mem::replace(self, Rope::new(""));
+        }
+
         let (l, r) = self.split(offset);
 
         let leaf = Rope::new(s);
@@ -166,7 +463,46 @@ impl Rope {
         Rope::join(Box::new(tmp), Box::new(r))
     }
 
+    fn insert_in_leaf(&mut self, s: &str, offset: usize, added: Metrics) -> bool {
+        match self {
+            Rope::Leaf(leaf) => {
+                if leaf.metrics.bytes + added.bytes > MAX_LEAF {
+                    return false;
+                }
+                let text = leaf.as_str();
+                let at = char_to_byte(text, offset);
+                *leaf = Leaf::new(&[&text[..at], s, &text[at..]].concat());
+                true
+            }
+            Rope::Node(node) => {
+                let inserted = if offset <= node.weight.chars {
+                    let inserted = Rc::make_mut(&mut node.left).insert_in_leaf(s, offset, added);
+                    if inserted {
+                        node.weight = node.weight + added;
+                    }
+                    inserted
+                } else {
+                    Rc::make_mut(&mut node.right).insert_in_leaf(
+                        s,
+                        offset - node.weight.chars,
+                        added,
+                    )
+                };
+                if inserted {
+                    node.total = node.total + added;
+                }
+                inserted
+            }
+        }
+    }
+
+    //@ Deletes the chars from `start` to `end`, inclusive. Like `insert`, a deletion that stays
+    //@ within one leaf, and doesn't make it too small, only rewrites that leaf.
     pub fn delete(&mut self, start: usize, end: usize) -> Rope {
+        if self.delete_in_leaf(start, end + 1, true, true).is_some() {
+            return std::mem::replace(self, Rope::new(""));
+        }
+
         let (l, mut r) = self.split(start);
 
         let (_, r2) = r.split(end - start + 1);
@@ -174,26 +510,508 @@ impl Rope {
         Rope::join(Box::new(l), Box::new(r2))
     }
 
+    //@ Returns the metrics of the deleted text on success. `first` and `last` tell whether we're
+    //@ in the first or last leaf of the rope, which may shrink below `MIN_LEAF`.
+    fn delete_in_leaf(
+        &mut self,
+        start: usize,
+        end: usize,
+        first: bool,
+        last: bool,
+    ) -> Option<Metrics> {
+        match self {
+            Rope::Leaf(leaf) => {
+                let text = leaf.as_str();
+                let from = char_to_byte(text, start);
+                let to = from + char_to_byte(&text[from..], end - start);
+                let removed = Metrics::of(&text[from..to]);
+                let remaining = leaf.metrics.bytes - removed.bytes;
+                let allowed =
+                    remaining >= MIN_LEAF || (first && last) || ((first || last) && remaining > 0);
+                if !allowed || removed.chars != end - start {
+                    return None;
+                }
+                *leaf = Leaf::new(&[&text[..from], &text[to..]].concat());
+                Some(removed)
+            }
+            Rope::Node(node) => {
+                let w = node.weight.chars;
+                let removed = if end <= w {
+                    let removed =
+                        Rc::make_mut(&mut node.left).delete_in_leaf(start, end, first, false)?;
+                    node.weight = node.weight - removed;
+                    removed
+                } else if start >= w {
+                    Rc::make_mut(&mut node.right).delete_in_leaf(start - w, end - w, false, last)?
+                } else {
+                    return None;
+                };
+                node.total = node.total - removed;
+                Some(removed)
+            }
+        }
+    }
+
+    //@ Returns the chars from `start` to `end`, inclusive, or `None` if the range is out of
+    //@ bounds.
     pub fn report(&self, start: usize, end: usize) -> Option<String> {
+        if start > end || end >= self.len() {
+            return None;
+        }
+        let mut out = String::new();
+        self.push_range(start, end + 1, &mut out);
+        Some(out)
+    }
+
+    //@ Appends the chars in `start..end` to `out`, only visiting the subtrees that overlap it.
+    fn push_range(&self, start: usize, end: usize, out: &mut String) {
         match self {
-            Rope::Leaf(leaf) => leaf.report(start, end),
+            Rope::Leaf(leaf) => {
+                let s = leaf.as_str();
+                let from = char_to_byte(s, start);
+                let to = from + char_to_byte(&s[from..], end - start);
+                out.push_str(&s[from..to]);
+            }
             Rope::Node(node) => {
-                let len = end - start + 1;
-                if len <= node.weight {
-                    return node.left.as_ref()?.report(start, end);
+                let w = node.weight.chars;
+                if start < w {
+                    node.left.push_range(start, end.min(w), out);
+                }
+                if end > w {
+                    node.right.push_range(start.saturating_sub(w), end - w, out);
+                }
+            }
+        }
+    }
+
+    //@ ## Lines
+    //@ A line ends at a `\n`, and a `\r\n` pair counts as a single line ending. Like in an
+    //@ editor, a rope with `k` newlines has `k + 1` lines, so a trailing newline is followed by an
+    //@ empty last line.
+    pub fn len_lines(&self) -> usize {
+        self.metrics().newlines + 1
+    }
+
+    //@ Returns the line that char offset `i` is on, by counting the newlines before it.
+    //@ Offsets past the end are clamped to the last line.
+    pub fn char_to_line(&self, i: usize) -> usize {
+        match self {
+            Rope::Node(node) => match i < node.weight.chars {
+                true => node.left.char_to_line(i),
+                false => node.weight.newlines + node.right.char_to_line(i - node.weight.chars),
+            },
+            Rope::Leaf(leaf) => {
+                let s = leaf.as_str();
+                Metrics::of(&s[..char_to_byte(s, i)]).newlines
+            }
+        }
+    }
+
+    //@ Returns the char offset where `line` starts, which is right after its preceding newline.
+    //@ To find the `line`th newline, we go left whenever the left subtree has at least `line` of
+    //@ them.
+    pub fn line_to_char(&self, line: usize) -> Option<usize> {
+        if line == 0 {
+            return Some(0);
+        }
+        match self {
+            Rope::Node(node) => match line <= node.weight.newlines {
+                true => node.left.line_to_char(line),
+                false => {
+                    Some(node.weight.chars + node.right.line_to_char(line - node.weight.newlines)?)
+                }
+            },
+            Rope::Leaf(leaf) => leaf
+                .as_str()
+                .chars()
+                .enumerate()
+                .filter(|&(_, c)| c == '\n')
+                .nth(line - 1)
+                .map(|(i, _)| i + 1),
+        }
+    }
+
+    //@ Returns the char offset of the line ending of a line that ends just before `next`, which
+    //@ is the `\r` of a `\r\n` pair, or else the `\n`.
+    fn line_end(&self, next: usize) -> usize {
+        let end = next - 1;
+        match end > 0 && self.slice(end - 1..end).chars().eq(['\r']) {
+            true => end - 1,
+            false => end,
+        }
+    }
+
+    //@ Converts a char offset to a zero-based `(line, column)` pair, where the column is counted
+    //@ in chars. An offset inside a `\r\n` pair is clamped to the start of the line ending.
+    pub fn char_to_line_col(&self, i: usize) -> (usize, usize) {
+        let i = i.min(self.len());
+        let line = self.char_to_line(i);
+        let start = self.line_to_char(line).expect("line is in bounds");
+        let i = match self.line_to_char(line + 1) {
+            Some(next) => i.min(self.line_end(next)),
+            None => i,
+        };
+        (line, i - start)
+    }
+
+    //@ Converts a `(line, column)` pair back to a char offset. The column may point at most one
+    //@ past the line's last char, i.e. at its line ending.
+    pub fn line_col_to_char(&self, line: usize, col: usize) -> Option<usize> {
+        let start = self.line_to_char(line)?;
+        let end = match self.line_to_char(line + 1) {
+            Some(next) => self.line_end(next),
+            None => self.len(),
+        };
+        (start + col <= end).then_some(start + col)
+    }
+
+    //@ Returns the contents of `line` without its line ending, in O(log(n) + line length) time:
+    //@ we find where it starts, then read chunks until we hit the next newline.
+    pub fn line(&self, line: usize) -> Option<String> {
+        let start = self.line_to_char(line)?;
+        let mut out = String::new();
+        for chunk in Chunks::new(self, start, self.len()) {
+            match chunk.find('\n') {
+                Some(i) => {
+                    out.push_str(&chunk[..i]);
+                    if out.ends_with('\r') {
+                        out.pop();
+                    }
+                    break;
+                }
+                None => out.push_str(chunk),
+            }
+        }
+        Some(out)
+    }
+
+    pub fn lines(&self) -> Lines<'_> {
+        Lines {
+            rope: self,
+            line: 0,
+        }
+    }
+
+    //@ ## Search
+    //@ Returns the char offsets of all non-overlapping matches of `pattern`, from left to right
+    //@ like `str::match_indices`. An empty pattern has no matches.
+    //@
+    //@ This is [Rabin-Karp](../strings/rabin_karp.html) run over the rope's chunks, so it takes
+    //@ O(n) expected time and never builds the whole text. A match can straddle any number of
+    //@ leaves, so we append each chunk to a buffer that still holds the current window, and only
+    //@ drop the bytes that have slid out of it. Both the pattern and the text are valid UTF-8, so
+    //@ a byte match always starts on a char boundary, and we only need to count the char-starting
+    //@ bytes that leave the window to know its char offset.
+    pub fn find_all(&self, pattern: &str) -> Vec<usize> {
+        let pattern = pattern.as_bytes();
+        let p = pattern.len();
+        if p == 0 || p > self.len_bytes() {
+            return vec![];
+        }
+        let target = RollingHash::of(pattern).hash();
+        let mut hash: Option<RollingHash> = None;
+        let mut buf = vec![];
+        let mut matches = vec![];
+        //@ `start` and `chars` are the byte and char offsets of `buf[0]`, and `next` is the first
+        //@ byte offset where a match may start without overlapping the previous one.
+        let (mut start, mut chars, mut next) = (0, 0, 0);
+        let mut checked = false;
+        for chunk in self.chunks() {
+            buf.extend_from_slice(chunk.as_bytes());
+            if buf.len() < p {
+                continue;
+            }
+            let hash = hash.get_or_insert_with(|| RollingHash::of(&buf[..p]));
+            let mut i = 0;
+            loop {
+                if !checked
+                    && start + i >= next
+                    && hash.hash() == target
+                    && &buf[i..i + p] == pattern
+                {
+                    matches.push(chars);
+                    next = start + i + p;
+                }
+                checked = true;
+                if i + p == buf.len() {
+                    break;
+                }
+                hash.roll(buf[i], buf[i + p]);
+                if !is_continuation(buf[i]) {
+                    chars += 1;
+                }
+                i += 1;
+                checked = false;
+            }
+            buf.drain(..i);
+            start += i;
+        }
+        matches
+    }
+
+    //@ Replaces every match of `pattern` with `replacement`, returning a new rope and leaving
+    //@ this one as it is. The text between matches is split off and joined back together, so it
+    //@ is shared with this rope instead of copied, and the replacement's leaves are shared by
+    //@ every match.
+    pub fn replace_all(&self, pattern: &str, replacement: &str) -> Rope {
+        let matches = self.find_all(pattern);
+        let (skip, replacement) = (pattern.chars().count(), Rope::new(replacement));
+        let mut out = Rope::new("");
+        let mut rest = self.clone();
+        let mut consumed = 0;
+        for m in matches {
+            let (before, tail) = rest.split_off(m - consumed);
+            let (_, tail) = tail.split_off(skip);
+            out = Rope::join(Box::new(out), Box::new(before));
+            out = Rope::join(Box::new(out), Box::new(replacement.clone()));
+            rest = tail;
+            consumed = m + skip;
+        }
+        Rope::join(Box::new(out), Box::new(rest))
+    }
+}
+
+//@ Iterates over the text of the leaves in order, trimmed to a range of chars.
+//@ The stack holds the subtrees we still have to visit, so finding the first leaf takes
+//@ O(log(n)) time and every following leaf O(1) amortized time. Only the first and last chunks
+//@ need to be trimmed, which is where we have to count chars.
+pub struct Chunks<'a> {
+    stack: Vec<&'a Rope>,
+    skip: usize,
+    remaining: usize,
+}
+
+impl<'a> Chunks<'a> {
+    fn new(rope: &'a Rope, start: usize, end: usize) -> Self {
+        let mut stack = vec![];
+        let mut rope = rope;
+        let mut skip = start;
+        while let Rope::Node(node) = rope {
+            if skip < node.weight.chars {
+                stack.push(&*node.right);
+                rope = &node.left;
+            } else {
+                skip -= node.weight.chars;
+                rope = &node.right;
+            }
+        }
+        stack.push(rope);
+        Chunks {
+            stack,
+            skip,
+            remaining: end.saturating_sub(start),
+        }
+    }
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        while self.remaining > 0 {
+            match self.stack.pop()? {
+                Rope::Node(node) => {
+                    self.stack.push(&node.right);
+                    self.stack.push(&node.left);
+                }
+                Rope::Leaf(leaf) => {
+                    let s = leaf.as_str();
+                    let mut s = &s[char_to_byte(s, self.skip)..];
+                    let mut chars = leaf.metrics.chars.saturating_sub(self.skip);
+                    self.skip = 0;
+                    if chars > self.remaining {
+                        s = &s[..char_to_byte(s, self.remaining)];
+                        chars = self.remaining;
+                    }
+                    self.remaining -= chars;
+                    if !s.is_empty() {
+                        return Some(s);
+                    }
                 }
-                let l = node.left.as_ref()?.report(start, node.weight - 1)?;
-                let r = node.right.as_ref()?.report(0, len - node.weight - 1)?;
-                Some(l + &r)
             }
         }
+        None
+    }
+}
+
+//@ Yields every line of the rope without its line ending, `len_lines()` of them in total.
+pub struct Lines<'a> {
+    rope: &'a Rope,
+    line: usize,
+}
+
+impl Iterator for Lines<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let line = self.rope.line(self.line)?;
+        self.line += 1;
+        Some(line)
+    }
+}
+
+//@ Turns any kind of range into a half-open `(start, end)` pair of char offsets, checking that
+//@ it fits into `len` chars.
+fn resolve(range: impl RangeBounds<usize>, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&i) => i + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i + 1,
+        Bound::Excluded(&i) => i,
+        Bound::Unbounded => len,
+    };
+    assert!(
+        start <= end && end <= len,
+        "range {start}..{end} out of bounds for a rope of {len} chars"
+    );
+    (start, end)
+}
+
+//@ ## Slices
+//@ A `RopeSlice` is a borrowed view of a char range of a rope, like a `&str` is of a `String`.
+//@ It's just the rope and two offsets, so taking a slice is O(1), and reading it walks down to
+//@ its first leaf in O(log(n)) time.
+#[derive(Clone, Copy, Debug)]
+pub struct RopeSlice<'a> {
+    rope: &'a Rope,
+    start: usize,
+    end: usize,
+}
+
+impl<'a> RopeSlice<'a> {
+    //@ The length of the slice in chars.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn chunks(&self) -> Chunks<'a> {
+        Chunks::new(self.rope, self.start, self.end)
+    }
+
+    pub fn chars(&self) -> impl Iterator<Item = char> + 'a {
+        self.chunks().flat_map(str::chars)
+    }
+
+    pub fn bytes(&self) -> impl Iterator<Item = u8> + 'a {
+        self.chunks().flat_map(str::bytes)
+    }
+
+    //@ Slices a slice, with `range` relative to the start of this one.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> RopeSlice<'a> {
+        let (start, end) = resolve(range, self.len());
+        RopeSlice {
+            rope: self.rope,
+            start: self.start + start,
+            end: self.start + end,
+        }
+    }
+}
+
+//@ Compares chunk by chunk, without ever building the whole string.
+fn chunks_eq<'a>(chunks: impl Iterator<Item = &'a str>, mut other: &str) -> bool {
+    for chunk in chunks {
+        match other.strip_prefix(chunk) {
+            Some(rest) => other = rest,
+            None => return false,
+        }
+    }
+    other.is_empty()
+}
+
+impl fmt::Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chunks().try_for_each(|chunk| f.write_str(chunk))
+    }
+}
+
+impl fmt::Display for RopeSlice<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chunks().try_for_each(|chunk| f.write_str(chunk))
+    }
+}
+
+impl From<&str> for Rope {
+    fn from(s: &str) -> Self {
+        Rope::new(s)
+    }
+}
+
+//@ An owned `String` becomes the shared buffer of the leaves as is, so nothing is copied.
+impl From<String> for Rope {
+    fn from(s: String) -> Self {
+        Rope::from_buf(Rc::new(s))
+    }
+}
+
+impl<'a> From<RopeSlice<'a>> for Rope {
+    fn from(slice: RopeSlice<'a>) -> Self {
+        Rope::from(slice.to_string())
+    }
+}
+
+impl FromIterator<char> for Rope {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        Rope::from(iter.into_iter().collect::<String>())
+    }
+}
+
+impl PartialEq<str> for Rope {
+    fn eq(&self, other: &str) -> bool {
+        self.len_bytes() == other.len() && chunks_eq(self.chunks(), other)
+    }
+}
+
+impl PartialEq<&str> for Rope {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl PartialEq<str> for RopeSlice<'_> {
+    fn eq(&self, other: &str) -> bool {
+        chunks_eq(self.chunks(), other)
+    }
+}
+
+impl PartialEq<&str> for RopeSlice<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+//@ Two ropes with the same text can be cut into different leaves, so they're compared byte by
+//@ byte.
+impl PartialEq for Rope {
+    fn eq(&self, other: &Rope) -> bool {
+        self.len_bytes() == other.len_bytes() && self.bytes().eq(other.bytes())
     }
 }
 
+impl Eq for Rope {}
+
 #[cfg(test)]
 mod tests {
+    use quickcheck_macros::quickcheck;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use test::{black_box, Bencher};
+
     use super::*;
 
+    fn contents(rope: &Rope) -> String {
+        match rope.len() {
+            0 => String::new(),
+            len => rope.report(0, len - 1).unwrap(),
+        }
+    }
+
     #[test]
     fn test_rope_new() {
         let rope = Rope::new("Hello, World!");
@@ -257,4 +1075,477 @@ mod tests {
         rope = rope.delete(2, 4);
         assert_eq!(rope.report(0, 9).unwrap(), "He, World!");
     }
+
+    #[test]
+    fn test_rope_unicode() {
+        let mut rope = Rope::new("héllo wörld");
+        rope = rope.insert("🦀 ", 6);
+        assert_eq!(contents(&rope), "héllo 🦀 wörld");
+        assert_eq!(rope.index(6), Some('🦀'));
+        assert_eq!((rope.len(), rope.len_bytes()), (13, 18));
+
+        rope = rope.delete(1, 1);
+        assert_eq!(rope.report(0, 4).unwrap(), "hllo ");
+    }
+
+    #[test]
+    fn test_rope_empty() {
+        let mut rope = Rope::new("");
+        assert!(rope.is_empty());
+        assert_eq!(rope.index(0), None);
+        assert_eq!(rope.report(0, 0), None);
+
+        rope = rope.insert("ü", 0);
+        assert_eq!(contents(&rope), "ü");
+    }
+
+    //@ Every operation is compared against the same operation on a `String`, converting char
+    //@ offsets to byte offsets on the `String` side. quickcheck's strings contain arbitrary
+    //@ Unicode, so multi-byte chars show up all the time.
+    #[quickcheck]
+    fn matches_string(init: String, ops: Vec<(bool, usize, usize, String)>) -> bool {
+        let mut rope = Rope::new(&init);
+        let mut string = init;
+        for (insert, a, b, s) in ops {
+            let len = string.chars().count();
+            if insert {
+                let at = a % (len + 1);
+                rope = rope.insert(&s, at);
+                string.insert_str(char_to_byte(&string, at), &s);
+            } else if len > 0 {
+                let (start, end) = (a % len, b % len);
+                let (start, end) = (start.min(end), start.max(end));
+                rope = rope.delete(start, end);
+                string.replace_range(
+                    char_to_byte(&string, start)..char_to_byte(&string, end + 1),
+                    "",
+                );
+            }
+
+            if contents(&rope) != string
+                || rope.len() != string.chars().count()
+                || rope.len_bytes() != string.len()
+            {
+                return false;
+            }
+        }
+        string
+            .chars()
+            .enumerate()
+            .all(|(i, c)| rope.index(i) == Some(c))
+    }
+
+    #[quickcheck]
+    fn split_matches_string(parts: Vec<String>, at: usize) -> bool {
+        let mut rope = parts.iter().fold(Rope::new(""), |rope, part| {
+            Rope::join(Box::new(rope), Box::new(Rope::new(part)))
+        });
+        let string = parts.concat();
+        let at = at % (string.chars().count() + 1);
+
+        let (left, right) = rope.split(at);
+        let (l, r) = string.split_at(char_to_byte(&string, at));
+        rope.is_empty() && contents(&left) == l && contents(&right) == r
+    }
+
+    #[test]
+    fn test_rope_lines() {
+        let rope = Rope::join(
+            Box::new(Rope::new("first\r\nsec")),
+            Box::new(Rope::new("ond\n\nläst")),
+        );
+        assert_eq!(rope.len_lines(), 4);
+        assert_eq!(rope.line(0).unwrap(), "first");
+        assert_eq!(rope.line(1).unwrap(), "second");
+        assert_eq!(rope.line(2).unwrap(), "");
+        assert_eq!(rope.line(3).unwrap(), "läst");
+        assert_eq!(rope.line(4), None);
+
+        assert_eq!(rope.char_to_line_col(9), (1, 2));
+        assert_eq!(rope.line_col_to_char(1, 2), Some(9));
+        assert_eq!(rope.line_col_to_char(3, 4), Some(rope.len()));
+        assert_eq!(rope.line_col_to_char(2, 1), None);
+
+        //@ The `\n` of a `\r\n` is at the same column as the `\r`.
+        let rope = Rope::new("ab\r\ncd");
+        assert_eq!(rope.line_col_to_char(0, 2), Some(2));
+        assert_eq!(rope.line_col_to_char(0, 3), None);
+        assert_eq!(rope.char_to_line_col(3), (0, 2));
+    }
+
+    //@ Lines are checked against `str::split('\n')`, which has the same convention of yielding
+    //@ a last empty line after a trailing newline. The input is built from arbitrary pieces
+    //@ separated by `\n` or `\r\n`, so that there are enough lines to look at. The `\n` of a
+    //@ `\r\n` pair is at the same column as its `\r`, and no column goes past the line ending.
+    #[quickcheck]
+    fn lines_match_string(pieces: Vec<(String, bool)>) -> bool {
+        let mut string = String::new();
+        let mut rope = Rope::new("");
+        for (piece, crlf) in pieces {
+            let piece = piece + if crlf { "\r\n" } else { "\n" };
+            string.push_str(&piece);
+            rope = Rope::join(Box::new(rope), Box::new(Rope::new(&piece)));
+        }
+
+        let expected: Vec<&str> = string
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .collect();
+        if rope.len_lines() != expected.len() || !rope.lines().eq(expected.iter().copied()) {
+            return false;
+        }
+
+        let mut line = 0;
+        let mut col = 0;
+        let mut prev = None;
+        for (i, c) in string.chars().enumerate() {
+            let crlf = c == '\n' && prev == Some('\r');
+            col -= usize::from(crlf);
+            if rope.char_to_line_col(i) != (line, col)
+                || rope.line_col_to_char(line, col) != Some(i - usize::from(crlf))
+            {
+                return false;
+            }
+            if c == '\n' {
+                if rope.line_col_to_char(line, col + 1).is_some() {
+                    return false;
+                }
+                line += 1;
+                col = 0;
+            } else {
+                col += 1;
+            }
+            prev = Some(c);
+        }
+        true
+    }
+
+    //@ Editing the end of a clone leaves the original alone, and the clone still shares the
+    //@ untouched left half with it.
+    #[test]
+    fn test_rope_clones_share_structure() {
+        let text = "abcd".repeat(1_000);
+        let original = Rope::new(&text);
+        let edited = original.clone().insert("xyz", text.len());
+
+        assert_eq!(original, text.as_str());
+        assert_eq!(edited, (text.clone() + "xyz").as_str());
+        let (Rope::Node(a), Rope::Node(b)) = (&original, &edited) else {
+            panic!("both ropes span several leaves");
+        };
+        assert!(Rc::ptr_eq(&a.left, &b.left));
+        assert!(!Rc::ptr_eq(&a.right, &b.right));
+    }
+
+    #[test]
+    fn test_rope_iterators() {
+        let text = "añb\n".repeat(1_000);
+        let rope = Rope::from(text.as_str());
+        assert!(rope.chunks().count() > 1);
+        assert_eq!(rope.chunks().collect::<String>(), text);
+        assert!(rope.chars().eq(text.chars()));
+        assert!(rope.bytes().eq(text.bytes()));
+    }
+
+    #[test]
+    fn test_rope_slice() {
+        let rope = Rope::new("Hello, wörld!");
+        let slice = rope.slice(7..);
+        assert_eq!(slice, "wörld!");
+        assert_eq!(slice.len(), 6);
+        assert_eq!(slice.slice(..=1), "wö");
+        assert_eq!(slice.slice(6..6), "");
+        assert_eq!(rope.slice(..).to_string(), "Hello, wörld!");
+        assert_eq!(Rope::from(rope.slice(1..5)), "ello");
+        assert!(slice.bytes().eq("wörld!".bytes()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rope_slice_out_of_bounds() {
+        Rope::new("abc").slice(2..4);
+    }
+
+    #[test]
+    fn test_rope_conversions() {
+        let rope = Rope::from(String::from("Hello, World!"));
+        assert_eq!(rope, "Hello, World!");
+        assert_ne!(rope, "Hello, World");
+        assert_eq!(format!("[{rope}]"), "[Hello, World!]");
+
+        let collected: Rope = "Hello, World!".chars().collect();
+        assert_eq!(collected, rope);
+        assert_ne!(collected, Rope::new("Hello"));
+    }
+
+    //@ Slices of a rope built from many pieces are checked against slices of the same string, so
+    //@ they start and end at arbitrary places within leaves.
+    #[quickcheck]
+    fn slice_matches_string(parts: Vec<String>, a: usize, b: usize) -> bool {
+        let rope = parts.iter().fold(Rope::new(""), |rope, part| {
+            Rope::join(Box::new(rope), Box::new(Rope::from(part.as_str())))
+        });
+        let string = parts.concat();
+        let len = string.chars().count();
+        let (start, end) = (a % (len + 1), b % (len + 1));
+        let (start, end) = (start.min(end), start.max(end));
+
+        let expected = &string[char_to_byte(&string, start)..char_to_byte(&string, end)];
+        let slice = rope.slice(start..end);
+        rope == string.as_str()
+            && slice == expected
+            && format!("{slice}") == expected
+            && slice.chars().eq(expected.chars())
+            && slice.len() == end - start
+    }
+
+    #[test]
+    fn test_rope_find_all() {
+        let rope = Rope::new("añaña aña");
+        assert_eq!(rope.find_all("aña"), vec![0, 6]);
+        assert_eq!(rope.find_all("ñ"), vec![1, 3, 7]);
+        assert_eq!(rope.find_all(""), Vec::<usize>::new());
+        assert_eq!(rope.find_all("añaña añaña"), Vec::<usize>::new());
+        assert_eq!(Rope::new("aaaa").find_all("aa"), vec![0, 2]);
+    }
+
+    //@ The needle is planted every 1,000 chars, so it lands across leaf boundaries all the time.
+    #[test]
+    fn test_rope_find_across_leaves() {
+        let text = ("x".repeat(994) + "needle").repeat(100);
+        let rope = Rope::new(&text);
+        let expected: Vec<usize> = (0..100).map(|i| i * 1_000 + 994).collect();
+        assert_eq!(rope.find_all("needle"), expected);
+
+        let replaced = rope.replace_all("needle", "pin");
+        assert_eq!(replaced, text.replace("needle", "pin").as_str());
+        assert!(is_valid(&replaced));
+        assert_eq!(rope, text.as_str());
+    }
+
+    //@ Matches are compared against `str::match_indices`, with its byte offsets turned into char
+    //@ offsets. The pattern is taken from the text itself half of the time, so that there's
+    //@ something to find.
+    #[quickcheck]
+    fn find_matches_string(
+        parts: Vec<String>,
+        pattern: String,
+        from: usize,
+        replacement: String,
+    ) -> bool {
+        let rope = parts.iter().fold(Rope::new(""), |rope, part| {
+            Rope::join(Box::new(rope), Box::new(Rope::new(part)))
+        });
+        let string = parts.concat();
+        let pattern = match from % 2 {
+            0 => pattern,
+            _ => string.chars().skip(from % 7).take(from % 3 + 1).collect(),
+        };
+
+        let expected: Vec<usize> = match pattern.is_empty() {
+            true => vec![],
+            false => string
+                .match_indices(&pattern)
+                .map(|(i, _)| string[..i].chars().count())
+                .collect(),
+        };
+        let replaced = rope.replace_all(&pattern, &replacement);
+        let expected_text = match pattern.is_empty() {
+            true => string.clone(),
+            false => string.replace(&pattern, &replacement),
+        };
+        rope.find_all(&pattern) == expected
+            && replaced == expected_text.as_str()
+            && is_valid(&replaced)
+    }
+
+    //@ Checks the cached metrics and heights, the AVL balance, and the leaf size bounds.
+    fn is_valid(rope: &Rope) -> bool {
+        fn leaves<'a>(rope: &'a Rope, out: &mut Vec<&'a Leaf>) -> bool {
+            match rope {
+                Rope::Leaf(leaf) => {
+                    out.push(leaf);
+                    true
+                }
+                Rope::Node(node) => {
+                    let (l, r) = (&node.left, &node.right);
+                    node.weight == l.metrics()
+                        && node.total == l.metrics() + r.metrics()
+                        && node.height == l.depth().max(r.depth()) + 1
+                        && l.depth().abs_diff(r.depth()) <= 1
+                        && leaves(l, out)
+                        && leaves(r, out)
+                }
+            }
+        }
+
+        let mut out = vec![];
+        if !leaves(rope, &mut out) {
+            return false;
+        }
+        let bounded = out.iter().all(|leaf| leaf.metrics.bytes <= MAX_LEAF);
+        let interior = match out.len() {
+            0..=2 => true,
+            n => out[1..n - 1]
+                .iter()
+                .all(|leaf| leaf.metrics.bytes >= MIN_LEAF),
+        };
+        let non_empty = out.len() == 1 || out.iter().all(|leaf| leaf.metrics.bytes > 0);
+        bounded && interior && non_empty
+    }
+
+    #[test]
+    fn test_rope_chunks_large_input() {
+        let text = "ab\u{e9}".repeat(2_000);
+        let rope = Rope::new(&text);
+        assert!(is_valid(&rope));
+        assert!(rope.depth() > 0);
+        assert_eq!(contents(&rope), text);
+    }
+
+    //@ Inserting repeated strings makes pieces larger than a leaf, so the seam handling and
+    //@ rebalancing get exercised along with the contents.
+    #[quickcheck]
+    fn stays_balanced(ops: Vec<(bool, usize, usize, String, u8)>) -> bool {
+        let mut rope = Rope::new("");
+        let mut string = String::new();
+        for (insert, a, b, s, times) in ops {
+            let len = string.chars().count();
+            if insert {
+                let s = s.repeat(times as usize % 64);
+                let at = a % (len + 1);
+                rope = rope.insert(&s, at);
+                string.insert_str(char_to_byte(&string, at), &s);
+            } else if len > 0 {
+                let (start, end) = (a % len, b % len);
+                let (start, end) = (start.min(end), start.max(end));
+                rope = rope.delete(start, end);
+                string.replace_range(
+                    char_to_byte(&string, start)..char_to_byte(&string, end + 1),
+                    "",
+                );
+            }
+            if !is_valid(&rope) || contents(&rope) != string {
+                return false;
+            }
+        }
+        true
+    }
+
+    //@ ## Benchmarks
+    //@ Random edits on an ASCII document, where char offsets and byte offsets are the same, so
+    //@ the `String` can be edited at the same positions. Each edit either inserts a few chars or
+    //@ deletes a few chars.
+    const EDITS: usize = 100_000;
+
+    fn document(len: usize) -> String {
+        let mut rng = StdRng::seed_from_u64(42);
+        (0..len)
+            .map(|_| match rng.gen_range(0..40) {
+                0 => '\n',
+                _ => rng.gen_range('a'..='z'),
+            })
+            .collect()
+    }
+
+    fn edit_rope(mut rope: Rope, rng: &mut StdRng) -> Rope {
+        let at = rng.gen_range(0..=rope.len());
+        if rng.gen_bool(0.5) || rope.len() < 10 {
+            rope.insert("hello", at)
+        } else {
+            let at = at.min(rope.len() - 5);
+            rope.delete(at, at + 4)
+        }
+    }
+
+    fn edit_string(string: &mut String, rng: &mut StdRng) {
+        let at = rng.gen_range(0..=string.len());
+        if rng.gen_bool(0.5) || string.len() < 10 {
+            string.insert_str(at, "hello");
+        } else {
+            let at = at.min(string.len() - 5);
+            string.replace_range(at..at + 5, "");
+        }
+    }
+
+    //@ After 100,000 random edits, the depth of the rope is still within the AVL bound of
+    //@ `1.44 * log2(leaves)`.
+    #[test]
+    fn depth_stays_bounded() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut rope = Rope::new(&document(1 << 16));
+        for _ in 0..EDITS {
+            rope = edit_rope(rope, &mut rng);
+        }
+        let max_leaves = (rope.len_bytes() / MIN_LEAF + 2) as f64;
+        assert!(is_valid(&rope));
+        assert!(rope.depth() as f64 <= 1.44 * max_leaves.log2() + 1.0);
+    }
+
+    fn bench_rope(b: &mut Bencher, len: usize) {
+        let doc = document(len);
+        b.iter(|| {
+            let mut rng = StdRng::seed_from_u64(7);
+            let mut rope = Rope::new(&doc);
+            for _ in 0..EDITS {
+                rope = edit_rope(rope, &mut rng);
+            }
+            black_box(rope.depth())
+        })
+    }
+
+    fn bench_string(b: &mut Bencher, len: usize) {
+        let doc = document(len);
+        b.iter(|| {
+            let mut rng = StdRng::seed_from_u64(7);
+            let mut string = doc.clone();
+            for _ in 0..EDITS {
+                edit_string(&mut string, &mut rng);
+            }
+            black_box(string.len())
+        })
+    }
+
+    //@ Every bench applies 100,000 edits. A `String` has to shift everything after the edit, so
+    //@ it slows down linearly with the size of the document, while the rope barely notices:
+    //@ `bench_rope_edits_64k   ... bench:   145,827,302 ns/iter (+/- 43,572,040)`
+    //@ `bench_rope_edits_1m    ... bench:   187,876,775 ns/iter (+/- 60,924,163)`
+    //@ `bench_string_edits_64k ... bench:    55,136,717 ns/iter (+/- 16,328,196)`
+    //@ `bench_string_edits_1m  ... bench: 1,393,371,684 ns/iter (+/- 211,459,898)`
+    #[bench]
+    fn bench_rope_edits_64k(b: &mut Bencher) {
+        bench_rope(b, 1 << 16);
+    }
+
+    #[bench]
+    fn bench_rope_edits_1m(b: &mut Bencher) {
+        bench_rope(b, 1 << 20);
+    }
+
+    #[bench]
+    fn bench_string_edits_64k(b: &mut Bencher) {
+        bench_string(b, 1 << 16);
+    }
+
+    #[bench]
+    fn bench_string_edits_1m(b: &mut Bencher) {
+        bench_string(b, 1 << 20);
+    }
+
+    //@ Searching a 4MB rope. `str::matches` on a flat string is about twice as fast, thanks to
+    //@ its vectorized byte search, even counting the time it takes to flatten the rope. What the
+    //@ rope saves is memory: it never needs a second copy of the whole document.
+    //@ `bench_find_all_flattened_string ... bench: 11,979,807 ns/iter (+/- 6,278,729)`
+    //@ `bench_find_all_rope             ... bench: 24,800,763 ns/iter (+/- 2,040,278)`
+    #[bench]
+    fn bench_find_all_rope(b: &mut Bencher) {
+        let rope = Rope::new(&document(1 << 22));
+        b.iter(|| black_box(rope.find_all("hello").len()))
+    }
+
+    #[bench]
+    fn bench_find_all_flattened_string(b: &mut Bencher) {
+        let rope = Rope::new(&document(1 << 22));
+        b.iter(|| black_box(rope.to_string().matches("hello").count()))
+    }
 }
@@ -0,0 +1,106 @@
+//@ A [Sparse Table](https://cp-algorithms.com/data_structures/sparse-table.html) answers range
+//@ queries over an immutable array.
+//@ It takes O(n log(n)) time and space to build, and answers queries in O(1) time as long as the
+//@ operation is idempotent (`op(x, x) == x`), like min, max, gcd, bitwise and/or.
+//@
+//@ Row `k` of the table stores the answer for every window of length `2^k`, so
+//@ `table[k][i] = op(arr[i..i + 2^k])`.
+pub struct SparseTable<T, Op> {
+    table: Vec<Vec<T>>,
+    op: Op,
+}
+
+impl<T: Clone, Op: Fn(&T, &T) -> T> SparseTable<T, Op> {
+    //@ Row 0 is the array itself. Every following row combines two windows of the previous row,
+    //@ each half as long: `table[k][i] = op(table[k - 1][i], table[k - 1][i + 2^(k - 1)])`.
+    pub fn new(arr: &[T], op: Op) -> Self {
+        let mut table = vec![arr.to_vec()];
+        let mut k = 1;
+        while (1 << k) <= arr.len() {
+            let prev = &table[k - 1];
+            let half = 1 << (k - 1);
+            let row = (0..=arr.len() - (1 << k))
+                .map(|i| op(&prev[i], &prev[i + half]))
+                .collect();
+            table.push(row);
+            k += 1;
+        }
+
+        SparseTable { table, op }
+    }
+
+    pub fn len(&self) -> usize {
+        self.table[0].len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    //@ Queries the half-open range `[l, r)`.
+    //@ We take the largest power of two `2^k` that fits in the range and combine the window
+    //@ starting at `l` with the window ending at `r`. They may overlap, which is why the operation
+    //@ has to be idempotent.
+    pub fn query(&self, l: usize, r: usize) -> Option<T> {
+        if l >= r || r > self.len() {
+            return None;
+        }
+        let k = (r - l).ilog2() as usize;
+        Some((self.op)(&self.table[k][l], &self.table[k][r - (1 << k)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    fn gcd(a: &u64, b: &u64) -> u64 {
+        let (mut a, mut b) = (*a, *b);
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    }
+
+    #[test]
+    fn ex1() {
+        let table = SparseTable::new(&[4, 3, 2, 8, 5, 1, 2, 1], |a: &u32, b: &u32| *a.min(b));
+
+        assert_eq!(table.query(0, 8), Some(1));
+        assert_eq!(table.query(0, 3), Some(2));
+        assert_eq!(table.query(3, 5), Some(5));
+        assert_eq!(table.query(3, 3), None);
+        assert_eq!(table.query(0, 9), None);
+    }
+
+    #[test]
+    fn gcd_query() {
+        let table = SparseTable::new(&[12, 18, 24, 7, 14], gcd);
+
+        assert_eq!(table.query(0, 3), Some(6));
+        assert_eq!(table.query(3, 5), Some(7));
+        assert_eq!(table.query(0, 5), Some(1));
+    }
+
+    //@ To check the table, we compare every possible range against a naive scan.
+    #[quickcheck]
+    fn sparse_table_min_max(input: Vec<i32>) -> bool {
+        let min = SparseTable::new(&input, |a: &i32, b: &i32| *a.min(b));
+        let max = SparseTable::new(&input, |a: &i32, b: &i32| *a.max(b));
+
+        for l in 0..input.len() {
+            for r in (l + 1)..=input.len() {
+                if min.query(l, r) != input[l..r].iter().min().copied() {
+                    return false;
+                }
+                if max.query(l, r) != input[l..r].iter().max().copied() {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
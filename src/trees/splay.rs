@@ -0,0 +1,392 @@
+use std::{cmp::Ordering, mem};
+
+//@ A [Splay Tree](https://en.wikipedia.org/wiki/Splay_tree) is a binary search tree that keeps
+//@ no balance information at all. Instead, every operation finishes by "splaying" the node it
+//@ looked for, rotating it all the way up to the root. That can leave the tree badly unbalanced,
+//@ but the rotations are chosen so that they roughly halve the depth of every node on the path,
+//@ and the amortized cost of every operation is O(log(n)).
+//@
+//@ Splaying also keeps recently used keys near the root, so a splay tree adapts to skewed
+//@ access patterns: looking up the same few keys over and over is much cheaper than O(log(n)).
+//@ The price is that even lookups change the tree, so `contains` takes `&mut self`.
+//@
+//@ Since the tree can get as deep as `n`, nothing here is recursive, including `Drop`.
+type Link<K> = Option<Box<Node<K>>>;
+
+//@ Nodes count the size of their subtree, so that splitting doesn't have to count the nodes
+//@ it splits off.
+struct Node<K> {
+    key: K,
+    size: usize,
+    left: Link<K>,
+    right: Link<K>,
+}
+
+impl<K> Node<K> {
+    fn update(&mut self) {
+        self.size = size(&self.left) + 1 + size(&self.right);
+    }
+}
+
+fn size<K>(link: &Link<K>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+pub struct SplayTree<K> {
+    root: Link<K>,
+}
+
+//@ ## Splaying
+//@ This is the top-down version of splaying, which needs no parent pointers. On the way down,
+//@ every node we pass is either larger than the key we're looking for, so it ends up in the
+//@ right subtree of the new root, or smaller, so it ends up in the left subtree. Nodes that go
+//@ right are collected in `right`, where each one is going to be the left child of the one
+//@ before it, and the same goes for `left`. When two steps in a row go the same way, we rotate
+//@ first, which is the "zig-zig" case that halves the depth of the path.
+//@
+//@ If the key isn't there, the last node on the path becomes the root instead, which is the
+//@ key's predecessor or successor.
+//@
+//@ Instead of a key, this takes a function that compares the key we're looking for with the key
+//@ of a node, so that splaying the largest key is just a search that always goes right.
+fn splay_by<K>(root: Box<Node<K>>, target: impl Fn(&K) -> Ordering) -> Box<Node<K>> {
+    let mut left: Vec<Box<Node<K>>> = vec![];
+    let mut right: Vec<Box<Node<K>>> = vec![];
+    let mut t = root;
+    loop {
+        match target(&t.key) {
+            Ordering::Less => {
+                let Some(mut child) = t.left.take() else {
+                    break;
+                };
+                if target(&child.key) == Ordering::Less {
+                    t.left = child.right.take();
+                    t.update();
+                    child.right = Some(t);
+                    t = child;
+                    let Some(next) = t.left.take() else {
+                        break;
+                    };
+                    right.push(mem::replace(&mut t, next));
+                } else {
+                    right.push(mem::replace(&mut t, child));
+                }
+            }
+            Ordering::Greater => {
+                let Some(mut child) = t.right.take() else {
+                    break;
+                };
+                if target(&child.key) == Ordering::Greater {
+                    t.right = child.left.take();
+                    t.update();
+                    child.left = Some(t);
+                    t = child;
+                    let Some(next) = t.right.take() else {
+                        break;
+                    };
+                    left.push(mem::replace(&mut t, next));
+                } else {
+                    left.push(mem::replace(&mut t, child));
+                }
+            }
+            Ordering::Equal => break,
+        }
+    }
+    //@ Reassemble: the new root's own children go at the bottom of the two collected paths, and
+    //@ the sizes along them are recomputed bottom-up.
+    let mut below = t.left.take();
+    for mut node in left.into_iter().rev() {
+        node.right = below;
+        node.update();
+        below = Some(node);
+    }
+    t.left = below;
+    let mut below = t.right.take();
+    for mut node in right.into_iter().rev() {
+        node.left = below;
+        node.update();
+        below = Some(node);
+    }
+    t.right = below;
+    t.update();
+    t
+}
+
+fn splay<K: Ord>(root: Box<Node<K>>, key: &K) -> Box<Node<K>> {
+    splay_by(root, |k| key.cmp(k))
+}
+
+//@ Splays the largest key, which leaves the root without a right child.
+fn splay_max<K>(root: Box<Node<K>>) -> Box<Node<K>> {
+    splay_by(root, |_| Ordering::Greater)
+}
+
+impl<K: Ord> Default for SplayTree<K> {
+    fn default() -> Self {
+        SplayTree::new()
+    }
+}
+
+impl<K: Ord> SplayTree<K> {
+    pub fn new() -> Self {
+        SplayTree { root: None }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    //@ Splays `key`, or its neighbour if it isn't there, and returns whether the root is `key`.
+    fn splay(&mut self, key: &K) -> bool {
+        match self.root.take() {
+            Some(root) => {
+                let root = self.root.insert(splay(root, key));
+                root.key == *key
+            }
+            None => false,
+        }
+    }
+
+    pub fn contains(&mut self, key: &K) -> bool {
+        self.splay(key)
+    }
+
+    //@ After splaying, the root is the key's predecessor or successor, so the new node can take
+    //@ its place as the root, with the old root and one of its subtrees on one side and the other
+    //@ subtree on the other side.
+    pub fn insert(&mut self, key: K) -> bool {
+        if self.splay(&key) {
+            return false;
+        }
+        let mut node = Box::new(Node {
+            key,
+            size: 1,
+            left: None,
+            right: None,
+        });
+        if let Some(mut root) = self.root.take() {
+            if root.key < node.key {
+                node.right = root.right.take();
+                root.update();
+                node.left = Some(root);
+            } else {
+                node.left = root.left.take();
+                root.update();
+                node.right = Some(root);
+            }
+            node.update();
+        }
+        self.root = Some(node);
+        true
+    }
+
+    //@ Once `key` is splayed to the root, removing it leaves two subtrees, which are merged.
+    pub fn remove(&mut self, key: &K) -> bool {
+        if !self.splay(key) {
+            return false;
+        }
+        let root = self.root.take().expect("the key was found");
+        self.root = join(root.left, root.right);
+        true
+    }
+
+    //@ Moves every key that is at least `key` into a new tree, like `BTreeSet::split_off`.
+    //@ After splaying, everything on one side of the root goes, and the root goes with whichever
+    //@ side it belongs to.
+    pub fn split_off(&mut self, key: &K) -> Self {
+        self.splay(key);
+        let Some(mut root) = self.root.take() else {
+            return SplayTree::new();
+        };
+        let right = if root.key < *key {
+            let right = root.right.take();
+            root.update();
+            self.root = Some(root);
+            right
+        } else {
+            self.root = root.left.take();
+            root.update();
+            Some(root)
+        };
+        SplayTree { root: right }
+    }
+
+    //@ The inverse of `split_off`. Every key in `self` has to be less than every key in `right`.
+    pub fn merge(mut self, mut right: Self) -> Self {
+        let root = match (self.root.take(), right.root.take()) {
+            (Some(left), Some(right)) => {
+                let left = splay_max(left);
+                let right = splay(right, &left.key);
+                assert!(left.key < right.key, "the trees to merge overlap");
+                join(Some(left), Some(right))
+            }
+            (left, right) => left.or(right),
+        };
+        SplayTree { root }
+    }
+
+    pub fn iter(&self) -> Iter<'_, K> {
+        let mut iter = Iter { stack: vec![] };
+        iter.push_left_spine(&self.root);
+        iter
+    }
+}
+
+//@ Joins two trees where every key on the left is smaller than every key on the right, by
+//@ splaying the largest key on the left, which leaves room for the right tree as its right child.
+fn join<K: Ord>(left: Link<K>, right: Link<K>) -> Link<K> {
+    let Some(left) = left else {
+        return right;
+    };
+    let mut root = splay_max(left);
+    root.right = right;
+    root.update();
+    Some(root)
+}
+
+impl<K: Ord> FromIterator<K> for SplayTree<K> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut tree = SplayTree::new();
+        for key in iter {
+            tree.insert(key);
+        }
+        tree
+    }
+}
+
+//@ The default `Drop` would recurse once per level.
+impl<K> Drop for SplayTree<K> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Box<Node<K>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
+pub struct Iter<'a, K> {
+    stack: Vec<&'a Node<K>>,
+}
+
+impl<'a, K> Iter<'a, K> {
+    fn push_left_spine(&mut self, mut link: &'a Link<K>) {
+        while let Some(node) = link {
+            self.stack.push(node);
+            link = &node.left;
+        }
+    }
+}
+
+impl<'a, K> Iterator for Iter<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        let node = self.stack.pop()?;
+        self.push_left_spine(&node.right);
+        Some(&node.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    #[test]
+    fn ex1() {
+        let mut tree: SplayTree<i32> = [5, 1, 9, 3, 7].into_iter().collect();
+        assert!(!tree.insert(3));
+        assert!(tree.contains(&7));
+        assert_eq!(tree.root.as_ref().map(|root| root.key), Some(7));
+        assert!(tree.remove(&5));
+        assert!(!tree.remove(&5));
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 3, 7, 9]);
+
+        let right = tree.split_off(&4);
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(right.iter().copied().collect::<Vec<_>>(), vec![7, 9]);
+        let tree = tree.merge(right);
+        assert_eq!(tree.len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "overlap")]
+    fn merge_overlapping() {
+        let left: SplayTree<i32> = [1, 5].into_iter().collect();
+        let right: SplayTree<i32> = [3, 7].into_iter().collect();
+        left.merge(right);
+    }
+
+    fn depth<K>(root: &Link<K>) -> usize {
+        let mut stack: Vec<(&Node<K>, usize)> = root.iter().map(|node| (&**node, 1)).collect();
+        let mut max = 0;
+        while let Some((node, depth)) = stack.pop() {
+            max = max.max(depth);
+            for child in [&node.left, &node.right].into_iter().flatten() {
+                stack.push((child, depth + 1));
+            }
+        }
+        max
+    }
+
+    //@ Checks the subtree sizes. This one is recursive, so it's only for small trees.
+    fn check_sizes<K>(link: &Link<K>) -> usize {
+        let Some(node) = link else {
+            return 0;
+        };
+        let size = check_sizes(&node.left) + 1 + check_sizes(&node.right);
+        assert_eq!(size, node.size);
+        size
+    }
+
+    //@ Inserting sorted keys turns the tree into a path, since every new key becomes the root
+    //@ with the rest of the tree on its left. Looking up the smallest key then splays it up from
+    //@ the bottom, which roughly halves the depth. A recursive splay or drop would overflow the
+    //@ stack here.
+    #[test]
+    fn sorted_inserts() {
+        let n = 100_000;
+        let mut tree: SplayTree<u32> = (0..n).collect();
+        assert_eq!(depth(&tree.root), n as usize);
+        assert!(tree.contains(&0));
+        assert!(depth(&tree.root) <= n as usize / 2 + 2);
+        assert!(tree.iter().copied().eq(0..n));
+    }
+
+    #[quickcheck]
+    fn splay_matches_btreeset(ops: Vec<(u8, u8)>, at: u8) -> bool {
+        let mut tree = SplayTree::new();
+        let mut expected = BTreeSet::new();
+        for (op, key) in ops {
+            let same = match op % 3 {
+                0 => tree.insert(key) == expected.insert(key),
+                1 => tree.remove(&key) == expected.remove(&key),
+                _ => tree.contains(&key) == expected.contains(&key),
+            };
+            if !same || tree.len() != expected.len() {
+                return false;
+            }
+        }
+        check_sizes(&tree.root);
+        let right = tree.split_off(&at);
+        check_sizes(&tree.root);
+        check_sizes(&right.root);
+        let expected_right = expected.split_off(&at);
+        let split = tree.iter().eq(expected.iter())
+            && right.iter().eq(expected_right.iter())
+            && tree.len() == expected.len()
+            && right.len() == expected_right.len();
+        let merged = tree.merge(right);
+        expected.extend(expected_right);
+        split && merged.iter().eq(expected.iter()) && merged.len() == expected.len()
+    }
+}
@@ -0,0 +1,232 @@
+//@ [Sqrt Decomposition](https://cp-algorithms.com/data_structures/sqrt_decomposition.html) splits
+//@ an array into blocks of about `sqrt(n)` elements and caches the aggregate of every block.
+//@
+//@ Queries combine at most two partial blocks element by element and every full block in between
+//@ by its cached aggregate, so they take O(sqrt(n)) time.
+//@ Point updates recompute a single block, also in O(sqrt(n)) time.
+//@ Unlike a sparse table the operation doesn't need to be idempotent, only associative, so sums
+//@ work too.
+pub struct SqrtDecomposition<T, Op> {
+    arr: Vec<T>,
+    blocks: Vec<T>,
+    block_size: usize,
+    op: Op,
+}
+
+impl<T: Clone, Op: Fn(&T, &T) -> T> SqrtDecomposition<T, Op> {
+    pub fn new(arr: &[T], op: Op) -> Self {
+        let block_size = (arr.len() as f64).sqrt().ceil().max(1.0) as usize;
+        let blocks = arr
+            .chunks(block_size)
+            .map(|chunk| Self::fold(&op, chunk))
+            .collect();
+
+        SqrtDecomposition {
+            arr: arr.to_vec(),
+            blocks,
+            block_size,
+            op,
+        }
+    }
+
+    fn fold(op: &Op, items: &[T]) -> T {
+        let (first, rest) = items.split_first().expect("blocks are never empty");
+        rest.iter().fold(first.clone(), |acc, x| op(&acc, x))
+    }
+
+    pub fn len(&self) -> usize {
+        self.arr.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    //@ Sets the value at `idx` and recomputes the aggregate of the block it belongs to.
+    pub fn update(&mut self, idx: usize, val: T) {
+        self.arr[idx] = val;
+        let block = idx / self.block_size;
+        let start = block * self.block_size;
+        let end = (start + self.block_size).min(self.arr.len());
+        self.blocks[block] = Self::fold(&self.op, &self.arr[start..end]);
+    }
+
+    //@ Queries the half-open range `[l, r)`.
+    pub fn query(&self, mut l: usize, r: usize) -> Option<T> {
+        if l >= r || r > self.len() {
+            return None;
+        }
+        let mut ans: Option<T> = None;
+        let mut push = |x: &T| {
+            ans = Some(match ans.take() {
+                Some(acc) => (self.op)(&acc, x),
+                None => x.clone(),
+            });
+        };
+
+        while l < r {
+            //@ If `l` sits at the start of a block that ends within the range, take the whole
+            //@ block at once.
+            if l.is_multiple_of(self.block_size) && l + self.block_size <= r {
+                push(&self.blocks[l / self.block_size]);
+                l += self.block_size;
+            } else {
+                push(&self.arr[l]);
+                l += 1;
+            }
+        }
+        ans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use test::{black_box, Bencher};
+
+    use super::*;
+    use crate::trees::{segment::SegmentTree, sparse_table::SparseTable};
+
+    #[test]
+    fn ex1() {
+        let mut sqrt = SqrtDecomposition::new(&[4, 3, 2, 8, 5, 1, 2, 1], |a: &u32, b: &u32| a + b);
+
+        assert_eq!(sqrt.query(0, 8), Some(26));
+        assert_eq!(sqrt.query(2, 5), Some(15));
+
+        sqrt.update(3, 0);
+        assert_eq!(sqrt.query(2, 5), Some(7));
+        assert_eq!(sqrt.query(5, 5), None);
+    }
+
+    //@ We apply a sequence of updates to both the structure and a plain vec, and then check every
+    //@ range against a naive sum.
+    #[quickcheck]
+    fn sqrt_decomposition_sum(input: Vec<i64>, updates: Vec<(usize, i64)>) -> bool {
+        let mut input: Vec<i64> = input.into_iter().map(|x| x % 1_000_000).collect();
+        let mut sqrt = SqrtDecomposition::new(&input, |a: &i64, b: &i64| a + b);
+        if input.is_empty() {
+            return sqrt.query(0, 1).is_none();
+        }
+
+        for (idx, val) in updates {
+            let idx = idx % input.len();
+            input[idx] = val % 1_000_000;
+            sqrt.update(idx, input[idx]);
+        }
+
+        for l in 0..input.len() {
+            for r in (l + 1)..=input.len() {
+                if sqrt.query(l, r) != Some(input[l..r].iter().sum()) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    //@ ## Benchmarks
+    //@ All three structures answer min queries over the same random array.
+    //@ Note that `SegmentTree::query` is one-indexed and inclusive, so `[l, r)` becomes
+    //@ `(l + 1, r)`.
+    const N: usize = 10_000;
+    const OPS: usize = 1_000;
+
+    fn input() -> (Vec<u32>, Vec<(usize, usize)>) {
+        let mut rng = StdRng::seed_from_u64(42);
+        let arr = (0..N).map(|_| rng.gen()).collect();
+        let ranges = (0..OPS)
+            .map(|_| {
+                let l = rng.gen_range(0..N);
+                (l, rng.gen_range(l + 1..=N))
+            })
+            .collect();
+        (arr, ranges)
+    }
+
+    fn min(a: &u32, b: &u32) -> u32 {
+        *a.min(b)
+    }
+
+    //@ The static workload builds once outside of the timed loop and then only queries.
+    //@ The sparse table wins by a wide margin, since every query is two lookups:
+    //@ `bench_static_segment_tree        ... bench:      84,125 ns/iter (+/- 42,090)`
+    //@ `bench_static_sparse_table        ... bench:      12,673 ns/iter (+/- 1,820)`
+    //@ `bench_static_sqrt_decomposition  ... bench:     375,196 ns/iter (+/- 85,708)`
+    #[bench]
+    fn bench_static_segment_tree(b: &mut Bencher) {
+        let (arr, ranges) = input();
+        let tree = SegmentTree::new(&arr);
+        b.iter(|| {
+            for &(l, r) in &ranges {
+                black_box(tree.query(l + 1, r));
+            }
+        })
+    }
+
+    #[bench]
+    fn bench_static_sparse_table(b: &mut Bencher) {
+        let (arr, ranges) = input();
+        let table = SparseTable::new(&arr, min);
+        b.iter(|| {
+            for &(l, r) in &ranges {
+                black_box(table.query(l, r));
+            }
+        })
+    }
+
+    #[bench]
+    fn bench_static_sqrt_decomposition(b: &mut Bencher) {
+        let (arr, ranges) = input();
+        let sqrt = SqrtDecomposition::new(&arr, min);
+        b.iter(|| {
+            for &(l, r) in &ranges {
+                black_box(sqrt.query(l, r));
+            }
+        })
+    }
+
+    //@ The dynamic workload interleaves a point update before every query.
+    //@ A sparse table can't be updated, so it has to be rebuilt every time, which makes it
+    //@ unusable here. The segment tree's O(log(n)) updates beat sqrt decomposition's O(sqrt(n)):
+    //@ `bench_dynamic_segment_tree       ... bench:     122,172 ns/iter (+/- 47,670)`
+    //@ `bench_dynamic_sparse_table       ... bench: 335,963,279 ns/iter (+/- 140,603,036)`
+    //@ `bench_dynamic_sqrt_decomposition ... bench:     409,708 ns/iter (+/- 111,132)`
+    #[bench]
+    fn bench_dynamic_segment_tree(b: &mut Bencher) {
+        let (arr, ranges) = input();
+        b.iter(|| {
+            let mut tree = SegmentTree::new(&arr);
+            for &(l, r) in &ranges {
+                tree.update(l + 1, r as u32);
+                black_box(tree.query(l + 1, r));
+            }
+        })
+    }
+
+    #[bench]
+    fn bench_dynamic_sparse_table(b: &mut Bencher) {
+        let (mut arr, ranges) = input();
+        b.iter(|| {
+            for &(l, r) in &ranges {
+                arr[l] = r as u32;
+                let table = SparseTable::new(&arr, min);
+                black_box(table.query(l, r));
+            }
+        })
+    }
+
+    #[bench]
+    fn bench_dynamic_sqrt_decomposition(b: &mut Bencher) {
+        let (arr, ranges) = input();
+        b.iter(|| {
+            let mut sqrt = SqrtDecomposition::new(&arr, min);
+            for &(l, r) in &ranges {
+                sqrt.update(l, r as u32);
+                black_box(sqrt.query(l, r));
+            }
+        })
+    }
+}
@@ -0,0 +1,593 @@
+use std::{
+    cmp::Ordering,
+    fmt, mem,
+    ops::{Bound, RangeBounds},
+};
+
+//@ A [Treap](https://en.wikipedia.org/wiki/Treap) is a binary search tree by key and a heap by
+//@ priority at the same time, where every node gets a random priority when it's created.
+//@ The shape of a treap only depends on the priorities, so it's the same shape a binary search
+//@ tree would have if its keys had been inserted in a random order, which makes it O(log(n))
+//@ deep in expectation, no matter what order the keys actually came in.
+//@
+//@ Everything is built out of two operations, which both walk down a single path:
+//@ - `split` cuts a treap into the nodes that go left of some point and the ones that go right.
+//@ - `merge` joins two treaps where every node of the first goes left of every node of the
+//@   second, by keeping whichever root has the higher priority and merging the rest into its
+//@   inner side.
+//@
+//@ Inserting is a split around the new key and two merges, and removing is two splits and a
+//@ merge, so all of them take O(log(n)) expected time.
+//@
+//@ Since nodes also count the size of their subtree, a treap can be split by position instead of
+//@ by key, which turns it into a sequence: the [`ImplicitTreap`](#implicit-treaps) below.
+type Link<T> = Option<Box<Node<T>>>;
+
+struct Node<T> {
+    val: T,
+    priority: u64,
+    size: usize,
+    //@ Set when the whole subtree should be read in reverse, but the children haven't been
+    //@ swapped yet. Only implicit treaps use it.
+    reversed: bool,
+    left: Link<T>,
+    right: Link<T>,
+}
+
+impl<T> Node<T> {
+    fn new(val: T) -> Box<Self> {
+        Box::new(Node {
+            val,
+            priority: rand::random(),
+            size: 1,
+            reversed: false,
+            left: None,
+            right: None,
+        })
+    }
+
+    fn update(&mut self) {
+        self.size = size(&self.left) + 1 + size(&self.right);
+    }
+
+    //@ Pushes a pending reversal one level down. Every function that looks at a node's children
+    //@ through a `&mut` has to call this first.
+    fn push(&mut self) {
+        if mem::take(&mut self.reversed) {
+            mem::swap(&mut self.left, &mut self.right);
+            for child in [&mut self.left, &mut self.right].into_iter().flatten() {
+                child.reversed ^= true;
+            }
+        }
+    }
+}
+
+fn size<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+fn merge<T>(left: Link<T>, right: Link<T>) -> Link<T> {
+    match (left, right) {
+        (None, link) | (link, None) => link,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                left.push();
+                left.right = merge(left.right.take(), Some(right));
+                left.update();
+                Some(left)
+            } else {
+                right.push();
+                right.left = merge(Some(left), right.left.take());
+                right.update();
+                Some(right)
+            }
+        }
+    }
+}
+
+//@ Splits into the keys that are less than `key`, and the rest.
+fn split_by_key<T: Ord>(link: Link<T>, key: &T) -> (Link<T>, Link<T>) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+    node.push();
+    if node.val < *key {
+        let (left, right) = split_by_key(node.right.take(), key);
+        node.right = left;
+        node.update();
+        (Some(node), right)
+    } else {
+        let (left, right) = split_by_key(node.left.take(), key);
+        node.left = right;
+        node.update();
+        (left, Some(node))
+    }
+}
+
+//@ Splits into the first `n` nodes, in order, and the rest.
+fn split_at<T>(link: Link<T>, n: usize) -> (Link<T>, Link<T>) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+    node.push();
+    let left_size = size(&node.left);
+    if n <= left_size {
+        let (left, right) = split_at(node.left.take(), n);
+        node.left = right;
+        node.update();
+        (left, Some(node))
+    } else {
+        let (left, right) = split_at(node.right.take(), n - left_size - 1);
+        node.right = left;
+        node.update();
+        (Some(node), right)
+    }
+}
+
+//@ Walks down to the `i`th node with a shared reference, so it can't push reversals down.
+//@ Instead it keeps track of whether the subtree it's in is flipped, in which case its left and
+//@ right children trade places.
+fn nth<T>(mut link: &Link<T>, mut i: usize) -> Option<&T> {
+    let mut flipped = false;
+    while let Some(node) = link {
+        flipped ^= node.reversed;
+        let (left, right) = children(node, flipped);
+        let left_size = size(left);
+        match i.cmp(&left_size) {
+            Ordering::Less => link = left,
+            Ordering::Equal => return Some(&node.val),
+            Ordering::Greater => {
+                i -= left_size + 1;
+                link = right;
+            }
+        }
+    }
+    None
+}
+
+fn children<T>(node: &Node<T>, flipped: bool) -> (&Link<T>, &Link<T>) {
+    if flipped {
+        (&node.right, &node.left)
+    } else {
+        (&node.left, &node.right)
+    }
+}
+
+//@ An in-order iterator that, like `nth`, keeps track of which subtrees are flipped instead of
+//@ pushing the reversals down. Every node on the stack is stored with whether its children are
+//@ flipped.
+pub struct Iter<'a, T> {
+    stack: Vec<(&'a Node<T>, bool)>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn new(root: &'a Link<T>) -> Self {
+        let mut iter = Iter { stack: vec![] };
+        iter.push_left_spine(root, false);
+        iter
+    }
+
+    fn push_left_spine(&mut self, mut link: &'a Link<T>, mut flipped: bool) {
+        while let Some(node) = link {
+            flipped ^= node.reversed;
+            self.stack.push((node, flipped));
+            link = children(node, flipped).0;
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let (node, flipped) = self.stack.pop()?;
+        self.push_left_spine(children(node, flipped).1, flipped);
+        Some(&node.val)
+    }
+}
+
+//@ ## Treaps
+//@ A set of ordered keys.
+pub struct Treap<K> {
+    root: Link<K>,
+}
+
+impl<K: Ord> Default for Treap<K> {
+    fn default() -> Self {
+        Treap::new()
+    }
+}
+
+impl<K: Ord> Treap<K> {
+    pub fn new() -> Self {
+        Treap { root: None }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        let mut link = &self.root;
+        while let Some(node) = link {
+            link = match key.cmp(&node.val) {
+                Ordering::Less => &node.left,
+                Ordering::Equal => return true,
+                Ordering::Greater => &node.right,
+            };
+        }
+        false
+    }
+
+    pub fn first(&self) -> Option<&K> {
+        nth(&self.root, 0)
+    }
+
+    pub fn last(&self) -> Option<&K> {
+        nth(&self.root, self.len().checked_sub(1)?)
+    }
+
+    //@ Returns whether the key was new.
+    pub fn insert(&mut self, key: K) -> bool {
+        if self.contains(&key) {
+            return false;
+        }
+        let (left, right) = split_by_key(self.root.take(), &key);
+        self.root = merge(merge(left, Some(Node::new(key))), right);
+        true
+    }
+
+    //@ Everything from `key` on is split off, and if the first of those nodes is `key`, it's
+    //@ dropped before merging the rest back.
+    pub fn remove(&mut self, key: &K) -> bool {
+        let (left, right) = split_by_key(self.root.take(), key);
+        let (first, rest) = split_at(right, 1);
+        let found = first.as_ref().is_some_and(|node| node.val == *key);
+        self.root = merge(left, if found { rest } else { merge(first, rest) });
+        found
+    }
+
+    //@ Moves every key that is at least `key` into a new treap, like `BTreeSet::split_off`.
+    pub fn split_off(&mut self, key: &K) -> Self {
+        let (left, right) = split_by_key(self.root.take(), key);
+        self.root = left;
+        Treap { root: right }
+    }
+
+    //@ The inverse of `split_off`. Every key in `self` has to be less than every key in `right`.
+    pub fn merge(self, right: Self) -> Self {
+        if let (Some(last), Some(first)) = (self.last(), right.first()) {
+            assert!(last < first, "the treaps to merge overlap");
+        }
+        Treap {
+            root: merge(self.root, right.root),
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, K> {
+        Iter::new(&self.root)
+    }
+}
+
+impl<K: Ord> FromIterator<K> for Treap<K> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut treap = Treap::new();
+        for key in iter {
+            treap.insert(key);
+        }
+        treap
+    }
+}
+
+//@ ## Implicit Treaps
+//@ An implicit treap doesn't store keys at all. The key of a node is its position in the
+//@ in-order traversal, which is never stored either, since it can be worked out from the subtree
+//@ sizes on the way down. Splitting by position instead of by key turns the treap into a
+//@ sequence where inserting or removing at any index, cutting and concatenating all take
+//@ O(log(n)) expected time, like in a [rope](./rope.html).
+//@
+//@ Reversing a range is O(log(n)) too: it's split out into its own treap, whose root gets marked
+//@ as reversed, and merged back. The mark is pushed down lazily, one level at a time, whenever a
+//@ split or merge goes through the node.
+pub struct ImplicitTreap<T> {
+    root: Link<T>,
+}
+
+impl<T> Default for ImplicitTreap<T> {
+    fn default() -> Self {
+        ImplicitTreap::new()
+    }
+}
+
+impl<T> ImplicitTreap<T> {
+    pub fn new() -> Self {
+        ImplicitTreap { root: None }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        nth(&self.root, index)
+    }
+
+    //@ Inserts `val` so that it ends up at `index`, shifting everything after it, like
+    //@ `Vec::insert`. Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, val: T) {
+        assert!(index <= self.len(), "index {index} out of bounds");
+        let (left, right) = split_at(self.root.take(), index);
+        self.root = merge(merge(left, Some(Node::new(val))), right);
+    }
+
+    pub fn push(&mut self, val: T) {
+        self.root = merge(self.root.take(), Some(Node::new(val)));
+    }
+
+    //@ Panics if `index >= len`.
+    pub fn remove(&mut self, index: usize) -> T {
+        assert!(index < self.len(), "index {index} out of bounds");
+        let (left, right) = split_at(self.root.take(), index);
+        let (node, right) = split_at(right, 1);
+        self.root = merge(left, right);
+        node.expect("the index is in bounds").val
+    }
+
+    //@ Splits the sequence in two at `index`, like `Vec::split_off`.
+    pub fn split_off(&mut self, index: usize) -> Self {
+        assert!(index <= self.len(), "index {index} out of bounds");
+        let (left, right) = split_at(self.root.take(), index);
+        self.root = left;
+        ImplicitTreap { root: right }
+    }
+
+    pub fn append(&mut self, other: Self) {
+        self.root = merge(self.root.take(), other.root);
+    }
+
+    pub fn reverse(&mut self, range: impl RangeBounds<usize>) {
+        let (start, end) = resolve(range, self.len());
+        let (left, right) = split_at(self.root.take(), start);
+        let (mut middle, right) = split_at(right, end - start);
+        if let Some(node) = &mut middle {
+            node.reversed ^= true;
+        }
+        self.root = merge(merge(left, middle), right);
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(&self.root)
+    }
+}
+
+fn resolve(range: impl RangeBounds<usize>, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&i) => i + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i + 1,
+        Bound::Excluded(&i) => i,
+        Bound::Unbounded => len,
+    };
+    assert!(
+        start <= end && end <= len,
+        "range {start}..{end} out of bounds for a sequence of length {len}"
+    );
+    (start, end)
+}
+
+impl<T> FromIterator<T> for ImplicitTreap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut treap = ImplicitTreap::new();
+        for val in iter {
+            treap.push(val);
+        }
+        treap
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ImplicitTreap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use quickcheck_macros::quickcheck;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use test::{black_box, Bencher};
+
+    use super::*;
+    use crate::trees::rope::Rope;
+
+    #[test]
+    fn ex1() {
+        let mut treap: Treap<i32> = [5, 1, 9, 3, 7].into_iter().collect();
+        assert!(!treap.insert(3));
+        assert!(treap.contains(&7));
+        assert!(treap.remove(&5));
+        assert!(!treap.remove(&5));
+        assert_eq!(treap.iter().copied().collect::<Vec<_>>(), vec![1, 3, 7, 9]);
+
+        let right = treap.split_off(&4);
+        assert_eq!(treap.iter().copied().collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(right.iter().copied().collect::<Vec<_>>(), vec![7, 9]);
+        let treap = treap.merge(right);
+        assert_eq!((treap.first(), treap.last()), (Some(&1), Some(&9)));
+    }
+
+    #[test]
+    #[should_panic(expected = "overlap")]
+    fn merge_overlapping() {
+        let left: Treap<i32> = [1, 5].into_iter().collect();
+        let right: Treap<i32> = [3, 7].into_iter().collect();
+        left.merge(right);
+    }
+
+    #[test]
+    fn implicit() {
+        let mut seq: ImplicitTreap<char> = "abcdef".chars().collect();
+        seq.insert(3, 'x');
+        assert_eq!(seq.remove(0), 'a');
+        seq.reverse(1..4);
+        assert_eq!(seq.iter().collect::<String>(), "bdxcef");
+        assert_eq!(seq.get(2), Some(&'x'));
+        assert_eq!(seq.get(6), None);
+
+        let mut rest = seq.split_off(4);
+        rest.append(seq);
+        assert_eq!(format!("{rest:?}"), "['e', 'f', 'b', 'd', 'x', 'c']");
+    }
+
+    //@ Checks the heap order, the subtree sizes and that the depth stays logarithmic.
+    fn check<T>(link: &Link<T>, depth: usize, max_depth: &mut usize) -> usize {
+        let Some(node) = link else {
+            return 0;
+        };
+        *max_depth = (*max_depth).max(depth);
+        for child in [&node.left, &node.right].into_iter().flatten() {
+            assert!(child.priority <= node.priority);
+        }
+        let size =
+            check(&node.left, depth + 1, max_depth) + 1 + check(&node.right, depth + 1, max_depth);
+        assert_eq!(size, node.size);
+        size
+    }
+
+    #[quickcheck]
+    fn treap_matches_btreeset(ops: Vec<(bool, u8)>, at: u8) -> bool {
+        let mut treap = Treap::new();
+        let mut expected = BTreeSet::new();
+        for (insert, key) in ops {
+            let same = if insert {
+                treap.insert(key) == expected.insert(key)
+            } else {
+                treap.remove(&key) == expected.remove(&key)
+            };
+            if !same {
+                return false;
+            }
+        }
+        check(&treap.root, 0, &mut 0);
+        let right = treap.split_off(&at);
+        let expected_right = expected.split_off(&at);
+        treap.iter().eq(expected.iter())
+            && right.iter().eq(expected_right.iter())
+            && treap.merge(right).len() == expected.len() + expected_right.len()
+    }
+
+    //@ Applies random inserts, removes and reversals to an implicit treap and a `Vec`.
+    #[quickcheck]
+    fn implicit_matches_vec(ops: Vec<(u8, usize, usize)>) -> bool {
+        let mut seq = ImplicitTreap::new();
+        let mut expected = vec![];
+        for (i, (op, a, b)) in ops.into_iter().enumerate() {
+            let len = expected.len();
+            match op % 3 {
+                0 => {
+                    seq.insert(a % (len + 1), i);
+                    expected.insert(a % (len + 1), i);
+                }
+                1 if len > 0 => {
+                    if seq.remove(a % len) != expected.remove(a % len) {
+                        return false;
+                    }
+                }
+                _ => {
+                    let (a, b) = (a % (len + 1), b % (len + 1));
+                    let (start, end) = (a.min(b), a.max(b));
+                    seq.reverse(start..end);
+                    expected[start..end].reverse();
+                }
+            }
+        }
+        check(&seq.root, 0, &mut 0);
+        seq.iter().eq(expected.iter())
+            && (0..=expected.len()).all(|i| seq.get(i) == expected.get(i))
+    }
+
+    //@ Inserting sorted keys would make a plain binary search tree a path, but the random
+    //@ priorities keep the treap shallow: the expected depth is about `2 * ln(n)`, or 28 here.
+    #[test]
+    fn sorted_inserts_stay_shallow() {
+        let treap: Treap<u32> = (0..1 << 16).collect();
+        let mut depth = 0;
+        check(&treap.root, 1, &mut depth);
+        assert!(depth < 60, "depth {depth}");
+    }
+
+    //@ ## Benchmarks
+    //@ The same 100,000 random edits on a 64k document as the rope benchmarks, with the implicit
+    //@ treap holding one byte per node.
+    const EDITS: usize = 100_000;
+
+    fn document(len: usize) -> String {
+        let mut rng = StdRng::seed_from_u64(42);
+        (0..len).map(|_| rng.gen_range('a'..='z')).collect()
+    }
+
+    fn edit_treap(seq: &mut ImplicitTreap<u8>, rng: &mut StdRng) {
+        let at = rng.gen_range(0..=seq.len());
+        let mut rest = seq.split_off(at);
+        if rng.gen_bool(0.5) || seq.len() + rest.len() < 10 {
+            seq.append("hello".bytes().collect());
+        } else {
+            rest = rest.split_off(rest.len().min(5));
+        }
+        seq.append(rest);
+    }
+
+    //@ The two are about as fast, since most of the time goes into the splits and merges on
+    //@ the way down either tree. Memory is a different story: the treap spends a 40-byte node on
+    //@ every byte of text, where the rope keeps up to a few hundred bytes in a leaf. For text, the
+    //@ rope wins, while the treap works for any element type and can reverse a range in
+    //@ O(log(n)) time:
+    //@ `bench_implicit_treap_edits_64k ... bench: 177,156,169 ns/iter (+/- 44,819,696)`
+    //@ `bench_rope_edits_64k           ... bench: 155,084,666 ns/iter (+/- 47,245,186)`
+    #[bench]
+    fn bench_implicit_treap_edits_64k(b: &mut Bencher) {
+        let doc = document(1 << 16);
+        b.iter(|| {
+            let mut rng = StdRng::seed_from_u64(7);
+            let mut seq: ImplicitTreap<u8> = doc.bytes().collect();
+            for _ in 0..EDITS {
+                edit_treap(&mut seq, &mut rng);
+            }
+            black_box(seq.len())
+        })
+    }
+
+    #[bench]
+    fn bench_rope_edits_64k(b: &mut Bencher) {
+        let doc = document(1 << 16);
+        b.iter(|| {
+            let mut rng = StdRng::seed_from_u64(7);
+            let mut rope = Rope::new(&doc);
+            for _ in 0..EDITS {
+                let at = rng.gen_range(0..=rope.len());
+                rope = if rng.gen_bool(0.5) || rope.len() < 10 {
+                    rope.insert("hello", at)
+                } else {
+                    let at = at.min(rope.len() - 5);
+                    rope.delete(at, at + 4)
+                };
+            }
+            black_box(rope.len())
+        })
+    }
+}
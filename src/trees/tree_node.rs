@@ -1,19 +1,20 @@
 use std::cell::RefCell;
 use std::fmt;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
 
-pub type BSTNode = Option<Rc<RefCell<TreeNode>>>;
+//@ Trees hold `i32`s by default, like on LeetCode, but any type works.
+pub type BSTNode<T = i32> = Option<Rc<RefCell<TreeNode<T>>>>;
 
 #[derive(Clone, Debug, PartialEq, Eq, Ord, PartialOrd)]
-pub struct TreeNode {
-    pub val: i32,
-    pub left: BSTNode,
-    pub right: BSTNode,
+pub struct TreeNode<T = i32> {
+    pub val: T,
+    pub left: BSTNode<T>,
+    pub right: BSTNode<T>,
 }
 
-impl TreeNode {
+impl<T> TreeNode<T> {
     #[inline]
-    pub fn new(val: i32) -> Self {
+    pub fn new(val: T) -> Self {
         TreeNode {
             val,
             left: None,
@@ -81,7 +82,7 @@ impl fmt::Debug for ListNode {
     }
 }
 
-type LLNode = Option<Box<ListNode>>;
+pub type LLNode = Option<Box<ListNode>>;
 
 #[macro_export]
 macro_rules! slist {
@@ -90,11 +91,11 @@ macro_rules! slist {
     };
     ($($e:expr), *) => {
         {
-            let mut head = Box::new($crate::ListNode::new(0));
+            let mut head = Box::new($crate::trees::tree_node::ListNode::new(0));
             let mut ref_head = &mut head;
 
             $(
-            ref_head.next = Some(Box::new($crate::ListNode::new($e)));
+            ref_head.next = Some(Box::new($crate::trees::tree_node::ListNode::new($e)));
             ref_head = ref_head.next.as_mut().unwrap();
             )*
 
@@ -109,8 +110,36 @@ macro_rules! vec_string {
     ($($e:expr), *) => {vec![$($e.to_owned()), *]};
 }
 
-#[derive(Clone, Default)]
-pub struct GraphNode {
-    val: i32,
-    neighbors: Vec<Rc<GraphNode>>,
+//@ A graph node in the style of LeetCode's "Clone Graph". Nodes are shared through
+//@ `Rc<RefCell<..>>`, so that edges can be added after both of their nodes exist, which is what
+//@ cycles need.
+//@ Edges are `Weak` references, so a cycle of nodes doesn't keep itself alive forever: whoever
+//@ builds a graph owns its nodes through their `GraphLink`s, and the whole graph is freed once
+//@ those are dropped. See [graph_node](../graphs/graph_node.html) for building, cloning and
+//@ traversing graphs.
+pub type GraphLink<T = i32> = Rc<RefCell<GraphNode<T>>>;
+
+#[derive(Clone, Debug, Default)]
+pub struct GraphNode<T = i32> {
+    pub val: T,
+    pub neighbors: Vec<Weak<RefCell<GraphNode<T>>>>,
+}
+
+impl<T> GraphNode<T> {
+    pub fn new(val: T) -> GraphLink<T> {
+        Rc::new(RefCell::new(GraphNode {
+            val,
+            neighbors: vec![],
+        }))
+    }
+
+    //@ Adds a directed edge. An undirected edge is two of them.
+    pub fn connect(from: &GraphLink<T>, to: &GraphLink<T>) {
+        from.borrow_mut().neighbors.push(Rc::downgrade(to));
+    }
+
+    //@ Returns the neighbors that are still alive, in the order they were connected.
+    pub fn neighbors(&self) -> Vec<GraphLink<T>> {
+        self.neighbors.iter().filter_map(Weak::upgrade).collect()
+    }
 }
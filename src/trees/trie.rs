@@ -0,0 +1,280 @@
+//@ A [Trie](https://en.wikipedia.org/wiki/Trie), or prefix tree, stores keys that are sequences
+//@ of symbols, with one edge per symbol, so that all keys with the same prefix share the path
+//@ that spells it out. Looking up a key of length `m` takes O(m * log(σ)) time, where `σ` is the
+//@ size of the alphabet, no matter how many keys there are, and all keys that start with a
+//@ prefix sit in a single subtree.
+//@
+//@ The alphabet is up to the caller: a `Trie<u8, V>` works on the bytes of strings, which keeps
+//@ the branching small, while a `Trie<char, V>` has one edge per char, so its paths are shorter
+//@ for non-ASCII text. Any `Ord` type works, like the four bases of DNA.
+//@
+//@ Children are kept in a `Vec` sorted by symbol, which is smaller than a `HashMap` or an array
+//@ indexed by symbol, and makes a depth-first traversal come out in lexicographic order.
+pub struct Trie<S, V> {
+    root: Node<S, V>,
+    len: usize,
+}
+
+struct Node<S, V> {
+    val: Option<V>,
+    children: Vec<(S, Node<S, V>)>,
+}
+
+impl<S, V> Node<S, V> {
+    fn new() -> Self {
+        Node {
+            val: None,
+            children: vec![],
+        }
+    }
+}
+
+impl<S: Ord + Copy, V> Node<S, V> {
+    fn child(&self, symbol: S) -> Option<&Node<S, V>> {
+        let i = self
+            .children
+            .binary_search_by_key(&symbol, |(s, _)| *s)
+            .ok()?;
+        Some(&self.children[i].1)
+    }
+
+    //@ Removes `key` below this node, along with every node on its path that no longer leads to
+    //@ a key.
+    fn remove(&mut self, key: &[S]) -> Option<V> {
+        let Some((&first, rest)) = key.split_first() else {
+            return self.val.take();
+        };
+        let i = self
+            .children
+            .binary_search_by_key(&first, |(s, _)| *s)
+            .ok()?;
+        let child = &mut self.children[i].1;
+        let removed = child.remove(rest)?;
+        if child.val.is_none() && child.children.is_empty() {
+            self.children.remove(i);
+        }
+        Some(removed)
+    }
+}
+
+impl<S: Ord + Copy, V> Default for Trie<S, V> {
+    fn default() -> Self {
+        Trie::new()
+    }
+}
+
+impl<S: Ord + Copy, V> Trie<S, V> {
+    pub fn new() -> Self {
+        Trie {
+            root: Node::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    //@ Returns the old value if the key was already there.
+    pub fn insert(&mut self, key: &[S], val: V) -> Option<V> {
+        let mut node = &mut self.root;
+        for &symbol in key {
+            let i = match node.children.binary_search_by_key(&symbol, |(s, _)| *s) {
+                Ok(i) => i,
+                Err(i) => {
+                    node.children.insert(i, (symbol, Node::new()));
+                    i
+                }
+            };
+            node = &mut node.children[i].1;
+        }
+        let old = node.val.replace(val);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    pub fn remove(&mut self, key: &[S]) -> Option<V> {
+        let removed = self.root.remove(key)?;
+        self.len -= 1;
+        Some(removed)
+    }
+
+    fn find(&self, key: &[S]) -> Option<&Node<S, V>> {
+        key.iter()
+            .try_fold(&self.root, |node, &symbol| node.child(symbol))
+    }
+
+    pub fn get(&self, key: &[S]) -> Option<&V> {
+        self.find(key)?.val.as_ref()
+    }
+
+    pub fn contains_key(&self, key: &[S]) -> bool {
+        self.get(key).is_some()
+    }
+
+    //@ Finds the longest key that is a prefix of `key`, which is what a router does with the
+    //@ prefixes in its routing table, or a tokenizer with its vocabulary. It's the last node with
+    //@ a value on the path that spells out `key`.
+    pub fn longest_prefix<'k>(&self, key: &'k [S]) -> Option<(&'k [S], &V)> {
+        let mut node = &self.root;
+        let mut longest = node.val.as_ref().map(|val| (0, val));
+        for (i, &symbol) in key.iter().enumerate() {
+            let Some(child) = node.child(symbol) else {
+                break;
+            };
+            node = child;
+            if let Some(val) = &node.val {
+                longest = Some((i + 1, val));
+            }
+        }
+        longest.map(|(len, val)| (&key[..len], val))
+    }
+
+    //@ All keys that start with `prefix`, in lexicographic order.
+    pub fn starts_with(&self, prefix: &[S]) -> Iter<'_, S, V> {
+        Iter {
+            stack: self
+                .find(prefix)
+                .map(|node| (prefix.to_vec(), node))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, S, V> {
+        self.starts_with(&[])
+    }
+}
+
+//@ A pre-order traversal, which yields a key before all the keys it's a prefix of. Children are
+//@ pushed in reverse, so that the smallest symbol comes off the stack first.
+pub struct Iter<'a, S, V> {
+    stack: Vec<(Vec<S>, &'a Node<S, V>)>,
+}
+
+impl<'a, S: Copy, V> Iterator for Iter<'a, S, V> {
+    type Item = (Vec<S>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((key, node)) = self.stack.pop() {
+            for (symbol, child) in node.children.iter().rev() {
+                let mut key = key.clone();
+                key.push(*symbol);
+                self.stack.push((key, child));
+            }
+            if let Some(val) = &node.val {
+                return Some((key, val));
+            }
+        }
+        None
+    }
+}
+
+impl<S: Ord + Copy, V, K: AsRef<[S]>> FromIterator<(K, V)> for Trie<S, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut trie = Trie::new();
+        for (key, val) in iter {
+            trie.insert(key.as_ref(), val);
+        }
+        trie
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    fn keys<S: Copy, V>(iter: Iter<'_, S, V>) -> Vec<Vec<S>> {
+        iter.map(|(key, _)| key).collect()
+    }
+
+    #[test]
+    fn ex1() {
+        let mut trie: Trie<u8, usize> = ["tea", "ten", "to", "inn", "in", "i"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, word)| (word, i))
+            .collect();
+        assert_eq!(trie.len(), 6);
+        assert_eq!(trie.get(b"ten"), Some(&1));
+        assert_eq!(trie.get(b"te"), None);
+        assert_eq!(keys(trie.starts_with(b"te")), vec![b"tea", b"ten"]);
+        assert_eq!(trie.longest_prefix(b"inner"), Some((b"inn".as_slice(), &3)));
+        assert_eq!(trie.longest_prefix(b"tent"), Some((b"ten".as_slice(), &1)));
+        assert_eq!(trie.longest_prefix(b"xyz"), None);
+
+        assert_eq!(trie.remove(b"inn"), Some(3));
+        assert_eq!(trie.remove(b"inn"), None);
+        let all: Vec<String> = trie
+            .iter()
+            .map(|(key, _)| String::from_utf8(key).unwrap())
+            .collect();
+        assert_eq!(all, ["i", "in", "tea", "ten", "to"]);
+    }
+
+    //@ With chars as the alphabet, `é` is a single edge instead of two bytes.
+    #[test]
+    fn chars() {
+        let words: Vec<Vec<char>> = ["café", "cafés", "cab"]
+            .iter()
+            .map(|word| word.chars().collect())
+            .collect();
+        let trie: Trie<char, ()> = words.iter().map(|word| (word, ())).collect();
+        let completions: Vec<String> = trie
+            .starts_with(&['c', 'a', 'f'])
+            .map(|(key, _)| key.into_iter().collect())
+            .collect();
+        assert_eq!(completions, ["café", "cafés"]);
+        let key = ['c', 'a', 'f', 'é', 's'];
+        assert_eq!(trie.longest_prefix(&key[..4]), Some((&key[..4], &())));
+    }
+
+    //@ Checks that no node is a dead end, which `remove` has to prune.
+    fn no_dead_ends<S, V>(node: &Node<S, V>) -> bool {
+        node.children.iter().all(|(_, child)| {
+            (child.val.is_some() || !child.children.is_empty()) && no_dead_ends(child)
+        })
+    }
+
+    //@ Keys are drawn from a three letter alphabet, so that they share lots of prefixes.
+    #[quickcheck]
+    fn matches_btreemap(ops: Vec<(bool, Vec<u8>)>, query: Vec<u8>) -> bool {
+        let small = |key: Vec<u8>| -> Vec<u8> { key.into_iter().take(6).map(|b| b % 3).collect() };
+        let mut trie = Trie::new();
+        let mut expected = BTreeMap::new();
+        for (i, (insert, key)) in ops.into_iter().enumerate() {
+            let key = small(key);
+            let same = if insert {
+                trie.insert(&key, i) == expected.insert(key, i)
+            } else {
+                trie.remove(&key) == expected.remove(&key)
+            };
+            if !same || trie.len() != expected.len() {
+                return false;
+            }
+        }
+        let query = small(query);
+        let prefix = &query[..query.len() / 2];
+        let longest = (0..=query.len())
+            .rev()
+            .find_map(|len| expected.get_key_value(&query[..len]));
+        no_dead_ends(&trie.root)
+            && trie.iter().eq(expected.iter().map(|(k, v)| (k.clone(), v)))
+            && trie.starts_with(prefix).eq(expected
+                .iter()
+                .filter(|(k, _)| k.starts_with(prefix))
+                .map(|(k, v)| (k.clone(), v)))
+            && trie.longest_prefix(&query) == longest.map(|(k, v)| (k.as_slice(), v))
+    }
+}
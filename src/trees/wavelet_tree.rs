@@ -0,0 +1,308 @@
+use std::ops::Range;
+
+use crate::primitive::bitvec::RankSelect;
+
+//@ A [Wavelet Tree](https://en.wikipedia.org/wiki/Wavelet_Tree) stores a sequence of integers
+//@ in about as many bits as the integers themselves take, and answers questions about any range
+//@ of the sequence in O(log(σ)) time, where σ is the largest value:
+//@ - `access(i)`: the value at position `i`.
+//@ - `rank(value, i)`: how many times `value` occurs before position `i`.
+//@ - `select(value, k)`: the position of the `k`th occurrence of `value`. This one takes
+//@   O(log(σ) * log(n)) time instead, since select on a bit vector takes O(log(n)) time, and it
+//@   runs once per level.
+//@ - `quantile(range, k)`: the `k`th smallest value in a range, like its median.
+//@ - `range_freq(range, values)`: how many values in a range are within some bounds.
+//@
+//@ The root splits the sequence by the highest bit of the values: it has a bit vector with that
+//@ bit of every value, and the values with a zero go to its left child, in the same order, and
+//@ the ones with a one go to its right child. Every child does the same with the next bit, until
+//@ there are no bits left, and each leaf is every occurrence of a single value. Rank on the bit
+//@ vectors is what moves a position in a node to its position in a child.
+//@
+//@ The nodes of each level are next to each other in a single bit vector, in the same order as
+//@ their values, so a node is just a range of its level. The two children of a node cover the
+//@ same range one level down, with the left child first, and the left child's size is the number
+//@ of zeros in its parent. This layout doesn't need any pointers, and only takes `log(σ)`
+//@ [rank/select bit vectors](../primitive/bitvec.html) of `n` bits.
+pub struct WaveletTree {
+    levels: Vec<RankSelect>,
+    len: usize,
+}
+
+fn rank(bits: &RankSelect, bit: bool, i: usize) -> usize {
+    if bit {
+        bits.rank1(i)
+    } else {
+        bits.rank0(i)
+    }
+}
+
+//@ The child of `node` on the side of `bit`, one level down.
+fn child(bits: &RankSelect, node: &Range<usize>, bit: bool) -> Range<usize> {
+    let zeros = bits.rank0(node.end) - bits.rank0(node.start);
+    if bit {
+        node.start + zeros..node.end
+    } else {
+        node.start..node.start + zeros
+    }
+}
+
+//@ Where position `i` of `node`, which has `bit` at this level, is in the child on that side.
+fn descend(
+    bits: &RankSelect,
+    node: &Range<usize>,
+    child: &Range<usize>,
+    bit: bool,
+    i: usize,
+) -> usize {
+    child.start + rank(bits, bit, i) - rank(bits, bit, node.start)
+}
+
+impl WaveletTree {
+    //@ Every level is a stable partition of the one above it by the bits above its own, which a
+    //@ stable sort by those bits does too.
+    pub fn new(values: &[u64]) -> Self {
+        let max = values.iter().copied().max().unwrap_or(0);
+        let height = (u64::BITS - max.leading_zeros()) as usize;
+        let mut level = values.to_vec();
+        let mut levels = vec![];
+        for shift in (0..height).rev() {
+            levels.push(level.iter().map(|v| v >> shift & 1 == 1).collect());
+            level.sort_by_key(|v| v >> shift);
+        }
+        WaveletTree {
+            levels,
+            len: values.len(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn bit(&self, value: u64, depth: usize) -> bool {
+        value >> (self.levels.len() - 1 - depth) & 1 == 1
+    }
+
+    //@ Values that don't fit in the height of the tree never occur.
+    fn fits(&self, value: u64) -> bool {
+        self.levels.len() == 64 || value >> self.levels.len() == 0
+    }
+
+    //@ Follows position `i` down to its leaf, reading the value off bit by bit.
+    pub fn access(&self, mut i: usize) -> u64 {
+        assert!(
+            i < self.len,
+            "index {i} out of bounds for length {}",
+            self.len
+        );
+        let mut node = 0..self.len;
+        let mut value = 0;
+        for bits in &self.levels {
+            let bit = bits.get(i);
+            let next = child(bits, &node, bit);
+            i = descend(bits, &node, &next, bit, i);
+            node = next;
+            value = value << 1 | u64::from(bit);
+        }
+        value
+    }
+
+    //@ Follows `value` down to its leaf, keeping track of where position `i` ends up. Everything
+    //@ before it in the leaf is an occurrence of `value` before `i`.
+    pub fn rank(&self, value: u64, mut i: usize) -> usize {
+        assert!(
+            i <= self.len,
+            "index {i} out of bounds for length {}",
+            self.len
+        );
+        if !self.fits(value) {
+            return 0;
+        }
+        let mut node = 0..self.len;
+        for (depth, bits) in self.levels.iter().enumerate() {
+            let bit = self.bit(value, depth);
+            let next = child(bits, &node, bit);
+            i = descend(bits, &node, &next, bit, i);
+            node = next;
+        }
+        i - node.start
+    }
+
+    //@ Finds the leaf of `value`, where the `k`th occurrence is simply at `k`, and then goes back
+    //@ up, using select to find where each position came from in the parent.
+    pub fn select(&self, value: u64, k: usize) -> Option<usize> {
+        if !self.fits(value) {
+            return None;
+        }
+        let mut path = vec![];
+        let mut node = 0..self.len;
+        for (depth, bits) in self.levels.iter().enumerate() {
+            let bit = self.bit(value, depth);
+            let next = child(bits, &node, bit);
+            path.push((node.start, next.start, bit));
+            node = next;
+        }
+        if k >= node.len() {
+            return None;
+        }
+        let mut i = node.start + k;
+        for (bits, (start, child_start, bit)) in self.levels.iter().zip(path).rev() {
+            let k = rank(bits, bit, start) + i - child_start;
+            i = if bit {
+                bits.select1(k)
+            } else {
+                bits.select0(k)
+            }
+            .expect("the parent has it");
+        }
+        Some(i)
+    }
+
+    //@ The `k`th smallest value in `range`, counting from zero. At every level, if there are more
+    //@ than `k` zeros in the range, the value is in the left child, and otherwise it's in the
+    //@ right child, after skipping those zeros.
+    pub fn quantile(&self, range: Range<usize>, mut k: usize) -> Option<u64> {
+        assert!(
+            range.end <= self.len,
+            "range out of bounds for length {}",
+            self.len
+        );
+        if k >= range.len() {
+            return None;
+        }
+        let (mut node, mut range) = (0..self.len, range);
+        let mut value = 0;
+        for bits in &self.levels {
+            let zeros = bits.rank0(range.end) - bits.rank0(range.start);
+            let bit = k >= zeros;
+            if bit {
+                k -= zeros;
+            }
+            let next = child(bits, &node, bit);
+            range = descend(bits, &node, &next, bit, range.start)
+                ..descend(bits, &node, &next, bit, range.end);
+            node = next;
+            value = value << 1 | u64::from(bit);
+        }
+        Some(value)
+    }
+
+    //@ How many values in `range` are in `values`.
+    pub fn range_freq(&self, range: Range<usize>, values: Range<u64>) -> usize {
+        if values.start >= values.end || range.start >= range.end {
+            return 0;
+        }
+        self.count_less(range.clone(), values.end) - self.count_less(range, values.start)
+    }
+
+    //@ Counts the values in `range` that are less than `value`, by following `value` down. Where
+    //@ it goes right, everything that goes left is smaller.
+    fn count_less(&self, mut range: Range<usize>, value: u64) -> usize {
+        assert!(
+            range.end <= self.len,
+            "range out of bounds for length {}",
+            self.len
+        );
+        if !self.fits(value) {
+            return range.len();
+        }
+        let mut node = 0..self.len;
+        let mut less = 0;
+        for (depth, bits) in self.levels.iter().enumerate() {
+            let bit = self.bit(value, depth);
+            if bit {
+                less += bits.rank0(range.end) - bits.rank0(range.start);
+            }
+            let next = child(bits, &node, bit);
+            range = descend(bits, &node, &next, bit, range.start)
+                ..descend(bits, &node, &next, bit, range.end);
+            node = next;
+        }
+        less
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    #[test]
+    fn ex1() {
+        let values = [3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5];
+        let tree = WaveletTree::new(&values);
+        assert_eq!(
+            (0..values.len())
+                .map(|i| tree.access(i))
+                .collect::<Vec<_>>(),
+            values
+        );
+        assert_eq!(tree.rank(5, 9), 2);
+        assert_eq!(tree.rank(7, 9), 0);
+        assert_eq!(tree.select(5, 2), Some(10));
+        assert_eq!(tree.select(5, 3), None);
+        assert_eq!(tree.select(100, 0), None);
+        //@ The median of `1, 5, 9, 2, 6` is 5.
+        assert_eq!(tree.quantile(3..8, 2), Some(5));
+        assert_eq!(tree.quantile(3..8, 5), None);
+        assert_eq!(tree.range_freq(0..6, 2..5), 2);
+        assert_eq!(tree.range_freq(0..11, 0..u64::MAX), 11);
+    }
+
+    #[test]
+    fn zeros() {
+        let tree = WaveletTree::new(&[0, 0, 0]);
+        assert_eq!(tree.access(1), 0);
+        assert_eq!(tree.rank(0, 2), 2);
+        assert_eq!(tree.select(0, 2), Some(2));
+        assert_eq!(tree.select(1, 0), None);
+        assert_eq!(tree.quantile(0..3, 1), Some(0));
+        assert_eq!(tree.range_freq(0..3, 1..2), 0);
+    }
+
+    //@ The full range of `u64`, which needs all 64 levels.
+    #[test]
+    fn wide() {
+        let values = [u64::MAX, 0, 1 << 63, u64::MAX];
+        let tree = WaveletTree::new(&values);
+        assert_eq!(tree.access(2), 1 << 63);
+        assert_eq!(tree.rank(u64::MAX, 4), 2);
+        assert_eq!(tree.select(u64::MAX, 1), Some(3));
+        assert_eq!(tree.quantile(0..4, 1), Some(1 << 63));
+        assert_eq!(tree.range_freq(0..4, 1..u64::MAX), 1);
+    }
+
+    //@ Compares every query with a scan, on small values so that they repeat.
+    #[quickcheck]
+    fn matches_naive(values: Vec<u8>, queries: Vec<(usize, usize, u8, u8)>) -> bool {
+        let values: Vec<u64> = values.into_iter().map(|v| u64::from(v % 20)).collect();
+        let tree = WaveletTree::new(&values);
+        let n = values.len();
+        let access = (0..n).all(|i| tree.access(i) == values[i]);
+        access
+            && queries.into_iter().all(|(a, b, x, y)| {
+                let (a, b) = (a % (n + 1), b % (n + 1));
+                let range = a.min(b)..a.max(b);
+                let reversed = range.end..range.start;
+                let k = usize::from(y) % (range.len() + 1);
+                let (x, y) = (u64::from(x % 24), u64::from(y % 24));
+                let mut sorted = values[range.clone()].to_vec();
+                sorted.sort();
+                let occurrences: Vec<usize> = (0..n).filter(|&i| values[i] == x).collect();
+                tree.rank(x, range.end) == values[..range.end].iter().filter(|&&v| v == x).count()
+                    && tree.select(x, k) == occurrences.get(k).copied()
+                    && tree.quantile(range.clone(), k) == sorted.get(k).copied()
+                    && tree.quantile(reversed.clone(), k).is_none()
+                    && tree.range_freq(reversed, x..y) == 0
+                    && tree.range_freq(range, x..y)
+                        == sorted.iter().filter(|v| (x..y).contains(v)).count()
+            })
+    }
+}